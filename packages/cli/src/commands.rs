@@ -0,0 +1,463 @@
+use hikmah_core::backup::RestoreMode;
+use hikmah_core::import::ImportItem;
+use hikmah_core::library::Library;
+use hikmah_core::models::{BookMetadata, ImportOutcome};
+use hikmah_core::sync::{sync_library, SyncClient, SyncConfig};
+use hikmah_core::utils::format_size;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// Where `restore` looks for the passphrase of an encrypted backup
+const PASSPHRASE_VAR: &str = "HIKMAH_PASSPHRASE";
+/// Where `sync` looks for the account password
+const SYNC_PASSWORD_VAR: &str = "HIKMAH_SYNC_PASSWORD";
+
+/* =========================
+   COMMANDS
+========================= */
+
+pub enum Command {
+    Add(Vec<PathBuf>),
+    List {
+        filter: Filter,
+        json: bool,
+    },
+    Search {
+        query: String,
+        json: bool,
+    },
+    Remove(Vec<String>),
+    Export {
+        dir: PathBuf,
+        book_ids: Vec<String>,
+    },
+    Backup(PathBuf),
+    Restore {
+        file: PathBuf,
+        replace: bool,
+    },
+    Stats {
+        json: bool,
+    },
+    Sync {
+        server_url: String,
+        username: String,
+        register: bool,
+    },
+}
+
+impl Command {
+    /// Commands that write the library and need the lock
+    pub fn changes_library(&self) -> bool {
+        matches!(
+            self,
+            Command::Add(_) | Command::Remove(_) | Command::Restore { .. } | Command::Sync { .. }
+        )
+    }
+}
+
+/// `list` filters; all given ones must match (case-insensitive)
+#[derive(Default)]
+pub struct Filter {
+    pub author: Option<String>,
+    pub tag: Option<String>,
+    pub collection: Option<String>,
+    pub format: Option<String>,
+}
+
+pub fn run(command: Command, library: &mut Library) -> Result<(), String> {
+    match command {
+        Command::Add(paths) => add(library, paths),
+        Command::List { filter, json } => {
+            let books = filtered(library, &filter)?;
+            print_books(library, &books, json)
+        }
+        Command::Search { query, json } => {
+            let books = library.search_books(&query);
+            print_books(library, &books, json)
+        }
+        Command::Remove(ids) => remove(library, &ids),
+        Command::Export { dir, book_ids } => export(library, dir, &book_ids),
+        Command::Backup(file) => backup(library, file),
+        Command::Restore { file, replace } => restore(library, file, replace),
+        Command::Stats { json } => stats(library, json),
+        Command::Sync {
+            server_url,
+            username,
+            register,
+        } => sync(library, &server_url, &username, register),
+    }
+}
+
+/* =========================
+   ADD
+========================= */
+
+fn add(library: &mut Library, paths: Vec<PathBuf>) -> Result<(), String> {
+    let items = paths.into_iter().map(ImportItem::Path).collect();
+    let mut queue = library.start_import(items)?;
+    let runtime = runtime()?;
+
+    // Hasil dicetak begitu tiap file selesai, bukan di akhir
+    let mut printed = 0;
+    runtime.block_on(async {
+        while let Some(events) = queue.next_batch().await {
+            library.apply_import_events(events)?;
+            let outcomes = match &library.import_progress {
+                Some(progress) => &progress.outcomes,
+                None => &library.import_results,
+            };
+            for outcome in &outcomes[printed..] {
+                print_outcome(outcome);
+            }
+            printed = outcomes.len();
+        }
+        Ok::<_, String>(())
+    })?;
+
+    let failed = (library.import_results.iter())
+        .filter(|o| o.result.is_err())
+        .count();
+    let added = library.import_results.len() - failed;
+    println!("Added {added} books");
+    if failed > 0 {
+        return Err(format!("{failed} files could not be added"));
+    }
+    Ok(())
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime, String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start: {e}"))
+}
+
+fn print_outcome(outcome: &ImportOutcome) {
+    match &outcome.result {
+        Ok(title) => println!("added   {} ({})", title, outcome.file_name),
+        Err(e) => eprintln!("skipped {}: {}", outcome.file_name, e),
+    }
+}
+
+/* =========================
+   LIST / SEARCH
+========================= */
+
+fn filtered<'a>(library: &'a Library, filter: &Filter) -> Result<Vec<&'a BookMetadata>, String> {
+    let members: Option<BTreeSet<String>> = match &filter.collection {
+        Some(name) => {
+            let collections = library.collections();
+            let found = (collections.iter())
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .ok_or(format!("No collection named '{name}'"))?;
+            Some(found.1.iter().cloned().collect())
+        }
+        None => None,
+    };
+    let matches = |value: &str, wanted: &Option<String>| {
+        wanted
+            .as_ref()
+            .is_none_or(|w| value.to_lowercase().contains(&w.to_lowercase()))
+    };
+
+    Ok(library
+        .books
+        .iter()
+        .filter(|b| {
+            filter.author.is_none() || b.authors.iter().any(|a| matches(&a.name, &filter.author))
+        })
+        .filter(|b| filter.tag.is_none() || b.tags.iter().any(|t| matches(t, &filter.tag)))
+        .filter(|b| {
+            (filter.format.as_ref()).is_none_or(|f| b.format.to_extension().eq_ignore_ascii_case(f))
+        })
+        .filter(|b| members.as_ref().is_none_or(|m| m.contains(&b.id)))
+        .collect())
+}
+
+/// What scripts get from `--json`; no file data or covers
+#[derive(Serialize)]
+struct BookSummary<'a> {
+    id: &'a str,
+    title: &'a str,
+    authors: Vec<&'a str>,
+    series: Option<String>,
+    tags: &'a [String],
+    collections: Vec<&'a str>,
+    format: &'a str,
+    file_name: &'a str,
+    size: u64,
+    added_date: &'a str,
+    /// Percent read, when the page count is known
+    progress: Option<u8>,
+}
+
+fn print_books(library: &Library, books: &[&BookMetadata], json: bool) -> Result<(), String> {
+    let collections = library.collections();
+
+    if json {
+        let summaries: Vec<BookSummary> = (books.iter())
+            .map(|book| summarize(book, &collections))
+            .collect();
+        let out = serde_json::to_string_pretty(&summaries).map_err(|e| e.to_string())?;
+        println!("{out}");
+        return Ok(());
+    }
+
+    for book in books {
+        println!(
+            "{}  {} — {}  [{}, {}]",
+            short_id(&book.id),
+            book.title,
+            book.author_display(),
+            book.format.to_extension(),
+            format_size(book.size)
+        );
+    }
+    Ok(())
+}
+
+fn summarize<'a>(
+    book: &'a BookMetadata,
+    collections: &'a BTreeMap<String, Vec<String>>,
+) -> BookSummary<'a> {
+    BookSummary {
+        id: &book.id,
+        title: &book.title,
+        authors: book.authors.iter().map(|a| a.name.as_str()).collect(),
+        series: (book.series.as_ref()).map(|s| format!("{} #{}", s.name, s.index)),
+        tags: &book.tags,
+        collections: (collections.iter())
+            .filter(|(_, members)| members.contains(&book.id))
+            .map(|(name, _)| name.as_str())
+            .collect(),
+        format: book.format.to_extension(),
+        file_name: &book.file_name,
+        size: book.size,
+        added_date: &book.added_date,
+        progress: (book.total_pages > 0)
+            .then(|| (book.last_read_position * 100 / book.total_pages).min(100) as u8),
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+/// A book by its id, or the start of it when that's unambiguous
+fn find_book<'a>(library: &'a Library, id: &str) -> Result<&'a BookMetadata, String> {
+    let found: Vec<&BookMetadata> = (library.books.iter())
+        .filter(|b| b.id.starts_with(id))
+        .collect();
+    match found.as_slice() {
+        [book] => Ok(book),
+        [] => Err(format!("No book with id {id}")),
+        _ => Err(format!("More than one book has an id starting with {id}")),
+    }
+}
+
+/* =========================
+   REMOVE / EXPORT
+========================= */
+
+fn remove(library: &mut Library, ids: &[String]) -> Result<(), String> {
+    // Cek semua dulu, supaya id yang salah tidak menyisakan setengah pekerjaan
+    let books: Vec<(String, String)> = ids
+        .iter()
+        .map(|id| find_book(library, id).map(|b| (b.id.clone(), b.title.clone())))
+        .collect::<Result<_, _>>()?;
+
+    for (id, title) in books {
+        library.remove_book(&id)?;
+        println!("removed {title}");
+    }
+    Ok(())
+}
+
+fn export(library: &Library, dir: PathBuf, ids: &[String]) -> Result<(), String> {
+    let book_ids: Vec<String> = if ids.is_empty() {
+        library.books.iter().map(|b| b.id.clone()).collect()
+    } else {
+        ids.iter()
+            .map(|id| find_book(library, id).map(|b| b.id.clone()))
+            .collect::<Result<_, _>>()?
+    };
+
+    let errors = library.export_books(&book_ids, &dir);
+    for e in &errors {
+        eprintln!("{e}");
+    }
+    println!(
+        "Exported {} books to {}",
+        book_ids.len() - errors.len(),
+        dir.display()
+    );
+    if !errors.is_empty() {
+        return Err(format!("{} books could not be exported", errors.len()));
+    }
+    Ok(())
+}
+
+/* =========================
+   BACKUP / RESTORE
+========================= */
+
+fn backup(library: &Library, file: PathBuf) -> Result<(), String> {
+    library.export_backup_to(&file)?;
+
+    let encrypted = if library.encryption_enabled() {
+        ", encrypted with your passphrase"
+    } else {
+        ""
+    };
+    println!(
+        "Backed up {} books to {}{}",
+        library.books.len(),
+        file.display(),
+        encrypted
+    );
+    Ok(())
+}
+
+fn restore(library: &mut Library, file: PathBuf, replace: bool) -> Result<(), String> {
+    let passphrase = if library.backup_file_needs_passphrase(&file)? {
+        Some(secret(
+            PASSPHRASE_VAR,
+            "Backup passphrase: ",
+            "This backup is encrypted",
+        )?)
+    } else {
+        None
+    };
+
+    let mode = if replace {
+        RestoreMode::Replace
+    } else {
+        RestoreMode::Merge
+    };
+    let report = library.restore_backup_from(&file, mode, passphrase.as_deref())?;
+    println!("{report}");
+    Ok(())
+}
+
+/* =========================
+   SYNC
+========================= */
+
+fn sync(
+    library: &mut Library,
+    server_url: &str,
+    username: &str,
+    register: bool,
+) -> Result<(), String> {
+    let password = secret(
+        SYNC_PASSWORD_VAR,
+        "Sync password: ",
+        "The server needs a password",
+    )?;
+    let client = SyncClient::new(SyncConfig::new(server_url, username, &password)?);
+
+    let before = library.sync.last_version;
+    runtime()?.block_on(async {
+        if register {
+            client.register().await?;
+            println!("Registered {username}");
+        }
+        sync_library(&client, library).await
+    })?;
+
+    println!(
+        "Synced with {} (version {} -> {}, {} changes still pending)",
+        server_url,
+        before,
+        library.sync.last_version,
+        library.sync.pending.len()
+    );
+    Ok(())
+}
+
+/// Passphrase/password dari environment atau ditanyakan di terminal; tidak
+/// pernah lewat argumen, supaya tidak terlihat di `ps` dan riwayat shell
+fn secret(var: &str, prompt: &str, needed: &str) -> Result<String, String> {
+    if let Ok(secret) = std::env::var(var) {
+        return Ok(secret);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(format!("{needed}; set {var}"));
+    }
+    rpassword::prompt_password(prompt).map_err(|e| format!("Failed to read it: {e}"))
+}
+
+/* =========================
+   STATS
+========================= */
+
+#[derive(Serialize)]
+struct Stats {
+    books: usize,
+    total_size: u64,
+    formats: BTreeMap<String, usize>,
+    authors: usize,
+    tags: usize,
+    collections: usize,
+    series: usize,
+    started: usize,
+    annotations: usize,
+    bookmarks: usize,
+}
+
+fn stats(library: &Library, json: bool) -> Result<(), String> {
+    let mut formats = BTreeMap::new();
+    for book in &library.books {
+        *formats
+            .entry(book.format.to_extension().to_string())
+            .or_insert(0) += 1;
+    }
+    let tags: BTreeSet<String> = (library.books.iter())
+        .flat_map(|b| b.tags.iter().map(|t| t.to_lowercase()))
+        .collect();
+    let series: BTreeSet<&str> = (library.books.iter())
+        .filter_map(|b| b.series.as_ref().map(|s| s.name.as_str()))
+        .collect();
+
+    let stats = Stats {
+        books: library.books.len(),
+        total_size: library.books.iter().map(|b| b.size).sum(),
+        formats,
+        authors: library.authors_index().len(),
+        tags: tags.len(),
+        collections: library.collections().len(),
+        series: series.len(),
+        started: (library.books.iter())
+            .filter(|b| b.last_read_position > 0)
+            .count(),
+        annotations: library.annotations.len(),
+        bookmarks: library.bookmarks.len(),
+    };
+
+    if json {
+        let out = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+        println!("{out}");
+        return Ok(());
+    }
+
+    let formats: Vec<String> = (stats.formats.iter())
+        .map(|(format, count)| format!("{count} {format}"))
+        .collect();
+    println!(
+        "Books:        {} ({})",
+        stats.books,
+        format_size(stats.total_size)
+    );
+    println!("Formats:      {}", formats.join(", "));
+    println!("Authors:      {}", stats.authors);
+    println!("Series:       {}", stats.series);
+    println!("Tags:         {}", stats.tags);
+    println!("Collections:  {}", stats.collections);
+    println!("Started:      {}", stats.started);
+    println!("Annotations:  {}", stats.annotations);
+    println!("Bookmarks:    {}", stats.bookmarks);
+    Ok(())
+}
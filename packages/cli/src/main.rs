@@ -0,0 +1,163 @@
+mod commands;
+
+use commands::{Command, Filter};
+use hikmah_core::library::Library;
+use hikmah_core::storage::LibraryLock;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: hikmah <command> [options]
+
+Commands:
+  add <file|folder>...      Add books; folders are searched recursively
+  list [--json]             List books, optionally filtered with
+       [--author <name>] [--tag <tag>] [--format <epub|pdf|txt>] [--collection <name>]
+  search <query> [--json]   Find books by title or author
+  remove <book-id>...       Remove books (the start of an id is enough)
+  export <folder> [<book-id>...]
+                            Export books (all by default) as Calibre-style folders
+  backup <file>             Write a backup archive of the library
+  restore <file> [--replace]
+                            Restore a backup, merged into the library by default
+  stats [--json]            Show library statistics
+  sync <server-url> <username> [--register]
+                            Sync with a hikmah-sync-server, creating the
+                            account first with --register
+
+Works on the same library as the desktop app. Commands that change it
+(add, remove, restore, sync) can't run while the app is open.
+
+Encrypted backups ask for their passphrase and sync for the account
+password, or read them from HIKMAH_PASSPHRASE and HIKMAH_SYNC_PASSWORD
+when there is no terminal.";
+
+/// Lama menunggu proses lain (misalnya `hikmah` lain dari cron) melepas lock
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+fn main() {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    // Lock dipegang sampai perintah selesai
+    let lock = if command.changes_library() {
+        match LibraryLock::acquire(LOCK_WAIT) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                eprintln!("{e}. Close the desktop app and try again.");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Tanpa lock library hanya dibaca; file lama tidak di-upgrade di disk
+    let mut library = Library::open(lock.as_ref());
+    if let Some(e) = &library.err {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+
+    if let Err(e) = commands::run(command, &mut library) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let name = args.next().ok_or("Missing command")?;
+    if matches!(name.as_str(), "-h" | "--help" | "help") {
+        println!("{USAGE}");
+        std::process::exit(0);
+    }
+
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut filter = Filter::default();
+    let mut json = false;
+    let mut replace = false;
+    let mut register = false;
+
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| args.next().ok_or(format!("{option} needs a value"));
+        match arg.as_str() {
+            "--json" => json = true,
+            "--replace" => replace = true,
+            "--register" => register = true,
+            "--author" => filter.author = Some(value("--author")?),
+            "--tag" => filter.tag = Some(value("--tag")?),
+            "--collection" => filter.collection = Some(value("--collection")?),
+            "--format" => filter.format = Some(value("--format")?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            other if other.starts_with("--") => return Err(format!("Unknown option: {other}")),
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        }
+        options.push(arg);
+    }
+
+    let (command, allowed): (Command, &[&str]) = match name.as_str() {
+        "add" if !positional.is_empty() => (
+            Command::Add(positional.into_iter().map(PathBuf::from).collect()),
+            &[],
+        ),
+        "list" if positional.is_empty() => (
+            Command::List { filter, json },
+            &["--json", "--author", "--tag", "--collection", "--format"],
+        ),
+        "search" if !positional.is_empty() => (
+            Command::Search {
+                query: positional.join(" "),
+                json,
+            },
+            &["--json"],
+        ),
+        "remove" if !positional.is_empty() => (Command::Remove(positional), &[]),
+        "export" if !positional.is_empty() => {
+            let dir = PathBuf::from(positional.remove(0));
+            (
+                Command::Export {
+                    dir,
+                    book_ids: positional,
+                },
+                &[],
+            )
+        }
+        "backup" if positional.len() == 1 => (Command::Backup(PathBuf::from(&positional[0])), &[]),
+        "restore" if positional.len() == 1 => (
+            Command::Restore {
+                file: PathBuf::from(&positional[0]),
+                replace,
+            },
+            &["--replace"],
+        ),
+        "stats" if positional.is_empty() => (Command::Stats { json }, &["--json"]),
+        "sync" if positional.len() == 2 => (
+            Command::Sync {
+                server_url: positional[0].clone(),
+                username: positional[1].clone(),
+                register,
+            },
+            &["--register"],
+        ),
+        "add" | "list" | "search" | "remove" | "export" | "backup" | "restore" | "stats"
+        | "sync" => return Err(format!("Wrong arguments for '{name}'")),
+        other => return Err(format!("Unknown command: {other}")),
+    };
+
+    if let Some(option) = options.iter().find(|o| !allowed.contains(&o.as_str())) {
+        return Err(format!("'{name}' doesn't take {option}"));
+    }
+    Ok(command)
+}
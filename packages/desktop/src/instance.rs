@@ -0,0 +1,236 @@
+use hikmah_core::storage::data_dir;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+const INSTANCE_FILE: &str = "instance";
+/// Sent back once the paths are queued; without it the launch opens its own
+/// window rather than assume a stale or foreign port took them
+const ACK: &str = "ok";
+const ACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// =======================
+/// Single Instance
+/// =======================
+///
+/// Jendela pertama mendengarkan di port loopback dan menulis port beserta
+/// token acak ke `<data dir>/instance`. Peluncuran berikutnya (`desktop
+/// buku.epub`, "Open with" dari file manager) mengirim path file-nya ke
+/// sana lalu keluar, jadi tidak ada dua jendela yang menulis library yang
+/// sama. Token mencegah proses lain menyuntikkan path lewat port itu.
+///
+/// Files to open, shared with the UI; the listener thread pushes paths and
+/// wakes `opened`
+#[derive(Clone, Default)]
+pub struct OpenRequests {
+    paths: Arc<Mutex<Vec<PathBuf>>>,
+    pub opened: Arc<Notify>,
+}
+
+impl OpenRequests {
+    fn push(&self, paths: Vec<PathBuf>) {
+        if paths.is_empty() {
+            return;
+        }
+        if let Ok(mut queue) = self.paths.lock() {
+            queue.extend(paths);
+        }
+        self.opened.notify_one();
+    }
+
+    /// Paths received since the last call
+    pub fn take(&self) -> Vec<PathBuf> {
+        self.paths
+            .lock()
+            .map(|mut queue| std::mem::take(&mut *queue))
+            .unwrap_or_default()
+    }
+}
+
+pub enum Claim {
+    /// This is the only window; the paths are queued in the requests
+    Primary(OpenRequests),
+    /// Another window took the paths
+    Forwarded,
+}
+
+/// Hand `paths` to a window that is already open, or become that window
+pub fn claim(paths: Vec<PathBuf>) -> Claim {
+    let Some(file) = data_dir().map(|dir| dir.join(INSTANCE_FILE)) else {
+        let requests = OpenRequests::default();
+        requests.push(paths);
+        return Claim::Primary(requests);
+    };
+
+    match forward(&file, &paths) {
+        Ok(()) => {
+            info!("Opened in the running window");
+            return Claim::Forwarded;
+        }
+        // Tidak ada jendela lain, atau file instance sisa proses yang crash
+        Err(e) => info!("No running window to hand over to: {}", e),
+    }
+
+    let requests = OpenRequests::default();
+    requests.push(paths);
+    if let Err(e) = listen(&file, requests.clone()) {
+        warn!("Later launches will open another window: {}", e);
+    }
+    Claim::Primary(requests)
+}
+
+/// Protocol: token on the first line, then one absolute path per line until
+/// the write side is closed; the window answers with [`ACK`]
+fn forward(file: &Path, paths: &[PathBuf]) -> Result<(), String> {
+    let content = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let (port, token) = content
+        .trim()
+        .split_once(' ')
+        .ok_or("Invalid instance file")?;
+    let port: u16 = port.parse().map_err(|_| "Invalid instance file")?;
+
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream =
+        TcpStream::connect_timeout(&address, Duration::from_secs(1)).map_err(|e| e.to_string())?;
+    let mut message = format!("{token}\n");
+    for path in paths {
+        // Jendela yang menerima bisa punya working directory lain
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        message.push_str(&format!("{}\n", path.display()));
+    }
+    stream
+        .write_all(message.as_bytes())
+        .and_then(|()| stream.shutdown(Shutdown::Write))
+        .and_then(|()| stream.set_read_timeout(Some(ACK_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("No answer from the running window: {e}"))?;
+    match reply.trim() == ACK {
+        true => Ok(()),
+        false => Err("The running window refused the request".to_string()),
+    }
+}
+
+fn listen(file: &Path, requests: OpenRequests) -> Result<(), String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).map_err(|e| e.to_string())?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let token = uuid::Uuid::new_v4().simple().to_string();
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(file, format!("{port} {token}")).map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(file, fs::Permissions::from_mode(0o600));
+    }
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            match read_request(stream, &token) {
+                Ok(paths) => requests.push(paths),
+                Err(e) => warn!("Ignored open request: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn read_request(stream: TcpStream, token: &str) -> Result<Vec<PathBuf>, String> {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .map_err(|e| e.to_string())?;
+    let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+    let mut lines = BufReader::new(stream).lines();
+
+    match lines.next() {
+        Some(Ok(line)) if line == token => {}
+        _ => return Err("wrong token".to_string()),
+    }
+    let mut paths = Vec::new();
+    for line in lines {
+        let line = line.map_err(|e| e.to_string())?;
+        if !line.is_empty() {
+            paths.push(PathBuf::from(line));
+        }
+    }
+
+    writeln!(writer, "{ACK}").map_err(|e| e.to_string())?;
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn instance_file() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hikmah-instance-{}", uuid::Uuid::new_v4()));
+        dir.join(INSTANCE_FILE)
+    }
+
+    /// Tunggu path sampai di antrean; listener menerimanya di thread lain
+    fn received(requests: &OpenRequests) -> Vec<PathBuf> {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        loop {
+            let paths = requests.take();
+            if !paths.is_empty() || Instant::now() > deadline {
+                return paths;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn paths_are_forwarded_to_the_running_window() {
+        let file = instance_file();
+        let requests = OpenRequests::default();
+        listen(&file, requests.clone()).unwrap();
+
+        let book = PathBuf::from("/nowhere/kitab.epub");
+        assert_eq!(forward(&file, std::slice::from_ref(&book)), Ok(()));
+        assert_eq!(received(&requests), [book]);
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn a_wrong_token_is_refused() {
+        let file = instance_file();
+        let requests = OpenRequests::default();
+        listen(&file, requests.clone()).unwrap();
+        let port = fs::read_to_string(&file).unwrap();
+        let port = port.split_once(' ').unwrap().0;
+        fs::write(&file, format!("{port} not-the-token")).unwrap();
+
+        assert!(forward(&file, &[PathBuf::from("/nowhere/kitab.epub")]).is_err());
+        assert!(requests.take().is_empty());
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+
+    #[test]
+    fn a_listener_that_never_answers_is_not_trusted() {
+        let file = instance_file();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, format!("{port} token")).unwrap();
+        // Menerima koneksi tapi tidak pernah menjawab
+        std::thread::spawn(move || {
+            let _streams: Vec<_> = listener.incoming().collect();
+        });
+
+        let started = Instant::now();
+        assert!(forward(&file, &[PathBuf::from("/nowhere/kitab.epub")]).is_err());
+        assert!(started.elapsed() >= ACK_TIMEOUT);
+        let _ = fs::remove_dir_all(file.parent().unwrap());
+    }
+}
@@ -1,16 +1,247 @@
+mod instance;
+mod opds_server;
+mod watcher;
+
 use dioxus::prelude::*;
-use hikmah_core::library::Library;
-use ui::component::library_view::LibraryView;
+use hikmah_core::import::ImportItem;
+use hikmah_core::library::{find_same_file, Library};
+use hikmah_core::storage::LibraryLock;
+use hikmah_core::watch::scan_folders;
+use instance::{Claim, OpenRequests};
+use opds_server::{Catalog, OpdsServer, SharedCatalog};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use ui::component::drop_zone::import_in_background;
+use ui::component::SharingStatus;
+use ui::route::{PlatformActions, Route};
 use ui::MAIN_CSS;
+use watcher::FolderWatcher;
 
 fn main() {
     dioxus_logger::initialize_default();
-    dioxus::launch(App);
+
+    // `desktop buku.epub`, atau "Open with" dari file manager. Opsi seperti
+    // `-psn_...` dari macOS bukan file.
+    let files: Vec<PathBuf> = std::env::args_os()
+        .skip(1)
+        .map(PathBuf::from)
+        .filter(|p| !p.to_string_lossy().starts_with('-'))
+        .collect();
+
+    match instance::claim(files) {
+        Claim::Forwarded => {}
+        Claim::Primary(requests) => LaunchBuilder::new().with_context(requests).launch(App),
+    }
 }
 
 #[component]
 fn App() -> Element {
-    let library = use_signal(Library::new);
+    // Lock dipegang selama aplikasi terbuka, supaya `hikmah` (CLI) tidak
+    // menulis library yang sama; CLI yang sedang berjalan ditunggu sebentar
+    let lock = use_hook(|| Rc::new(LibraryLock::acquire(Duration::from_secs(10))));
+    let library = use_signal(|| {
+        let mut library = Library::open(lock.as_ref().as_ref().ok());
+        match lock.as_ref() {
+            Err(e) => library.set_read_only(e),
+            // Buku lama yang masih berupa data URL dipindah ke blob store,
+            // supaya katalog OPDS cukup memegang metadata
+            Ok(_) => {
+                if let Err(e) = library.move_files_to_blob_store() {
+                    tracing::warn!("{}", e);
+                }
+            }
+        }
+        library
+    });
+    let catalog = use_hook(SharedCatalog::default);
+    let mut open_book = use_signal(|| None::<String>);
+    let mut server = use_signal(|| None::<OpdsServer>);
+    let mut sharing = use_signal(|| SharingStatus::Off);
+
+    // Server OPDS mengikuti setting di library; snapshot katalog diperbarui
+    // setiap kali library berubah
+    use_effect(move || {
+        let lib = library.read();
+        let settings = lib.opds_server_settings();
+        if !settings.enabled {
+            server.set(None);
+            sharing.set(SharingStatus::Off);
+            return;
+        }
+
+        if let Ok(mut snapshot) = catalog.write() {
+            *snapshot = Catalog::from_library(&lib);
+        }
+        if server.peek().as_ref().map(OpdsServer::port) == Some(settings.port) {
+            return;
+        }
+
+        // Matikan server lama dulu kalau port berubah
+        server.set(None);
+        match OpdsServer::start(settings.port, catalog.clone()) {
+            Ok(started) => {
+                sharing.set(SharingStatus::Serving(started.url()));
+                server.set(Some(started));
+            }
+            Err(e) => sharing.set(SharingStatus::Failed(e)),
+        }
+    });
+
+    // Folder yang dipantau: watcher dibuat ulang setiap daftar folder berubah,
+    // lalu satu rescan dijalankan (juga saat aplikasi dibuka) untuk
+    // menyusul perubahan selama aplikasi tertutup
+    let changes = use_hook(|| Arc::new(Notify::new()));
+    let mut watcher = use_signal(|| None::<FolderWatcher>);
+    let watched = use_memo(move || library.read().watched_folders().to_vec());
+    use_effect({
+        let changes = changes.clone();
+        move || {
+            let folders = watched();
+            watcher.set(None);
+            if folders.is_empty() {
+                return;
+            }
+            match FolderWatcher::start(&folders, changes.clone()) {
+                Ok(started) => watcher.set(Some(started)),
+                Err(e) => tracing::warn!("{}", e),
+            }
+            changes.notify_one();
+        }
+    });
+    use_future(move || {
+        let changes = changes.clone();
+        async move {
+            loop {
+                changes.notified().await;
+                // Tunggu sampai file selesai disalin/diunduh sebelum dibaca
+                tokio::time::sleep(Duration::from_secs(2)).await;
+
+                // Folder dibaca di thread lain; file baru dan yang berubah
+                // diimpor lewat antrean, setelah import yang sedang berjalan
+                wait_for_import(library).await;
+                let folders = library.read().watched_folders().to_vec();
+                let scan = tokio::task::spawn_blocking(move || scan_folders(&folders)).await;
+                let Ok(scan) = scan else { continue };
+
+                let mut library = library;
+                let applied = library.write().apply_folder_scan(scan);
+                match applied {
+                    Ok((_, items)) if !items.is_empty() => {
+                        import_in_background(library, items).await
+                    }
+                    Ok(_) => {}
+                    Err(e) => library.write().err = Some(e),
+                }
+            }
+        }
+    });
+
+    // File dari command line dan dari peluncuran berikutnya: buku yang sudah
+    // ada dicari lewat isinya, semua yang baru diimpor, lalu yang terakhir
+    // dibuka di reader
+    let requests = use_context::<OpenRequests>();
+    use_future(move || {
+        let requests = requests.clone();
+        async move {
+            loop {
+                let paths = requests.take();
+                if !paths.is_empty() {
+                    if let Some(book_id) = open_paths(library, paths).await {
+                        open_book.set(Some(book_id));
+                    }
+                    dioxus::desktop::window().set_focus();
+                }
+                requests.opened.notified().await;
+            }
+        }
+    });
+
+    // Folder dipilih lewat dialog native; isinya diimpor oleh rescan berikutnya
+    let add_watch_folder = move |_| {
+        spawn(async move {
+            let Some(folder) = rfd::AsyncFileDialog::new()
+                .set_title("Watch folder for new books")
+                .pick_folder()
+                .await
+            else {
+                return;
+            };
+
+            let mut library = library;
+            let folder = folder.path().to_string_lossy().into_owned();
+            let added = library.write().add_watched_folder(&folder);
+            if let Err(e) = added {
+                library.write().err = Some(e);
+            }
+        });
+    };
+
+    // Export buku terpilih sebagai folder Calibre (book + metadata.opf + cover)
+    let export_selection = move |book_ids: Vec<String>| {
+        spawn(async move {
+            let Some(folder) = rfd::AsyncFileDialog::new()
+                .set_title("Export books to folder")
+                .pick_folder()
+                .await
+            else {
+                return;
+            };
+
+            let errors = library.read().export_books(&book_ids, folder.path());
+            if !errors.is_empty() {
+                let mut library = library;
+                library.write().err = Some(errors.join("\n"));
+            }
+        });
+    };
+
+    // Dialog native: file disalin langsung ke blob store, tanpa base64 lewat webview
+    let import_files = move |_| {
+        spawn(async move {
+            let Some(files) = rfd::AsyncFileDialog::new()
+                .set_title("Add books")
+                .add_filter("Ebooks", &["epub", "pdf", "txt"])
+                .pick_files()
+                .await
+            else {
+                return;
+            };
+
+            let items = (files.iter())
+                .map(|f| ImportItem::Path(f.path().to_path_buf()))
+                .collect();
+            import_in_background(library, items).await;
+        });
+    };
+
+    // Satu folder (beserta subfolder-nya) sekaligus, dengan progress bar
+    let import_folder = move |_| {
+        spawn(async move {
+            let Some(folder) = rfd::AsyncFileDialog::new()
+                .set_title("Add books from folder")
+                .pick_folder()
+                .await
+            else {
+                return;
+            };
+
+            let item = ImportItem::Path(folder.path().to_path_buf());
+            import_in_background(library, vec![item]).await;
+        });
+    };
+
+    use_context_provider(|| library);
+    use_context_provider(|| PlatformActions {
+        on_export: Some(EventHandler::new(export_selection)),
+        on_import: Some(EventHandler::new(import_files)),
+        on_import_folder: Some(EventHandler::new(import_folder)),
+        on_add_watch_folder: Some(EventHandler::new(add_watch_folder)),
+        sharing: Some(sharing),
+        open_book: Some(open_book),
+    });
 
     rsx! {
         link {
@@ -18,6 +249,88 @@ fn App() -> Element {
             href: MAIN_CSS
         }
 
-        LibraryView{library}
+        Router::<Route> {}
     }
 }
+
+/// Let a running import finish first; the queue takes one import at a time
+async fn wait_for_import(library: Signal<Library>) {
+    while library.peek().import_progress.is_some() {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Files opened from outside the app: the ones already in the library are
+/// found by content, all the others are imported through the queue.
+/// Returns the book of the last file, the one to open; files that couldn't
+/// be read are reported through `err`, failed imports in the import results.
+async fn open_paths(mut library: Signal<Library>, paths: Vec<PathBuf>) -> Option<String> {
+    let mut errors = Vec::new();
+    let mut new = Vec::new();
+    let mut last = None;
+    for path in paths {
+        match find_opened(library, &path).await {
+            Ok(opened) => {
+                if opened.book_id.is_none() {
+                    new.push(ImportItem::Path(path));
+                }
+                last = Some(opened);
+            }
+            Err(e) => {
+                errors.push(e);
+                last = None;
+            }
+        }
+    }
+    if !errors.is_empty() {
+        library.write().err = Some(errors.join("\n"));
+    }
+
+    if !new.is_empty() {
+        wait_for_import(library).await;
+        import_in_background(library, new).await;
+    }
+    let last = last?;
+    last.book_id.or_else(|| {
+        (library.peek())
+            .find_duplicate(&last.name, last.size)
+            .map(|b| b.id.clone())
+    })
+}
+
+struct Opened {
+    name: String,
+    size: u64,
+    /// The book already in the library with this file
+    book_id: Option<String>,
+}
+
+async fn find_opened(library: Signal<Library>, path: &Path) -> Result<Opened, String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+
+    // Hanya buku dengan ukuran yang sama yang perlu di-hash ulang
+    let candidates = library.peek().books_with_size(size);
+    let same = tokio::task::spawn_blocking({
+        let path = path.to_path_buf();
+        move || find_same_file(&path, &candidates)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let book_id = same.or_else(|| {
+        (library.peek())
+            .find_duplicate(&name, size)
+            .map(|b| b.id.clone())
+    });
+    Ok(Opened {
+        name,
+        size,
+        book_id,
+    })
+}
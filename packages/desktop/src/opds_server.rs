@@ -0,0 +1,778 @@
+use bytes::Bytes;
+use hikmah_core::library::Library;
+use hikmah_core::models::BookMetadata;
+use hikmah_core::opds::{
+    decode_query, encode_query, write_atom_feed, write_opensearch, Acquisition, AcquisitionKind,
+    NavigationLink, OpdsEntry, OpdsFeed, SearchLink,
+};
+use hikmah_core::utils::decode_data_url;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Body, Frame, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, TcpListener as StdListener, UdpSocket};
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
+
+const ROOT: &str = "/opds";
+const PAGE_SIZE: usize = 50;
+const CATALOG_TITLE: &str = "Bayt Alhikmah";
+const FEED_TYPE: &str = "application/atom+xml;profile=opds-catalog;charset=utf-8";
+/// Book files are sent in pieces of this size
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type HttpResponse = Response<BoxBody<Bytes, io::Error>>;
+
+/// =======================
+/// OPDS Server
+/// =======================
+///
+/// Menyajikan `Library.books` sebagai katalog OPDS 1.2 di jaringan lokal,
+/// supaya e-reader (KOReader, Moon+ Reader, ...) bisa mengunduh langsung.
+/// Tanpa autentikasi: hanya aktif kalau pengguna menyalakannya di Settings.
+///
+/// The library itself lives in a Dioxus signal on the UI thread, so the
+/// server reads a [`Catalog`] snapshot that the app refreshes on change.
+/// The snapshot holds metadata only; book files are streamed from the blob
+/// store when requested.
+#[derive(Default)]
+pub struct Catalog {
+    books: Vec<BookMetadata>,
+    collections: BTreeMap<String, Vec<String>>,
+    updated: String,
+}
+
+pub type SharedCatalog = Arc<RwLock<Catalog>>;
+
+impl Catalog {
+    pub fn from_library(library: &Library) -> Self {
+        // Buku terbaru = perubahan terakhir katalog
+        let updated = library
+            .books
+            .iter()
+            .map(|b| b.added_date.as_str())
+            .max()
+            .unwrap_or("1970-01-01T00:00:00Z")
+            .to_string();
+        Self {
+            books: library
+                .books
+                .iter()
+                .map(BookMetadata::metadata_only)
+                .collect(),
+            collections: library.collections(),
+            updated,
+        }
+    }
+}
+
+/// Runs until dropped
+pub struct OpdsServer {
+    port: u16,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl OpdsServer {
+    /// Listen on every interface on `port`, serving from a background thread
+    pub fn start(port: u16, catalog: SharedCatalog) -> Result<Self, String> {
+        let listener = StdListener::bind((Ipv4Addr::UNSPECIFIED, port))
+            .map_err(|e| format!("Cannot listen on port {port}: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Cannot listen on port {port}: {e}"))?;
+
+        let (shutdown, stopped) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("opds-server".to_string())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build();
+                match runtime {
+                    Ok(runtime) => runtime.block_on(serve(listener, catalog, stopped)),
+                    Err(e) => error!("OPDS server runtime failed: {}", e),
+                }
+            })
+            .map_err(|e| format!("Failed to start the OPDS server: {e}"))?;
+
+        let server = Self {
+            port,
+            shutdown: Some(shutdown),
+        };
+        info!("OPDS server listening on {}", server.url());
+        Ok(server)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Catalog address for e-readers on the same network
+    pub fn url(&self) -> String {
+        format!("http://{}:{}{}", local_ip(), self.port, ROOT)
+    }
+}
+
+impl Drop for OpdsServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn serve(listener: StdListener, catalog: SharedCatalog, mut stopped: oneshot::Receiver<()>) {
+    let listener = match TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("OPDS server failed: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = &mut stopped => {
+                info!("OPDS server stopped");
+                return;
+            }
+            accepted = listener.accept() => match accepted {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Accept failed: {}", e);
+                    continue;
+                }
+            },
+        };
+
+        let catalog = catalog.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle(req, catalog.clone()));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("OPDS connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// =======================
+/// Router
+/// =======================
+///
+/// - `/opds`: navigation (all, newest, authors, collections)
+/// - `/opds/books?page=N`, `/opds/new?page=N`
+/// - `/opds/authors`, `/opds/author?name=...`
+/// - `/opds/collections`, `/opds/collection?name=...`
+/// - `/opds/search?q=...`, `/opds/opensearch.xml`
+/// - `/opds/books/<id>/file`, `/opds/books/<id>/cover`
+async fn handle(
+    req: Request<Incoming>,
+    catalog: SharedCatalog,
+) -> Result<HttpResponse, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let query = req.uri().query().unwrap_or_default().to_string();
+
+    let response = if method != Method::GET && method != Method::HEAD {
+        text(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed")
+    } else {
+        match catalog.read() {
+            Ok(catalog) => route(&catalog, &path, &query),
+            Err(_) => text(StatusCode::INTERNAL_SERVER_ERROR, "Catalog unavailable"),
+        }
+    };
+
+    info!("OPDS {} {} -> {}", method, path, response.status());
+    Ok(response)
+}
+
+fn route(catalog: &Catalog, path: &str, query: &str) -> HttpResponse {
+    let page = param(query, "page")
+        .and_then(|p| p.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let Some(rest) = path.strip_prefix(ROOT) else {
+        return match path {
+            "/" => redirect(ROOT),
+            _ => not_found(),
+        };
+    };
+
+    match rest {
+        "" | "/" => feed(catalog, root_feed(catalog)),
+        "/books" => {
+            let mut books: Vec<&BookMetadata> = catalog.books.iter().collect();
+            books.sort_by_key(|b| b.title.to_lowercase());
+            feed(
+                catalog,
+                book_feed("All books", "/books", query, books, page),
+            )
+        }
+        "/new" => {
+            let mut books: Vec<&BookMetadata> = catalog.books.iter().collect();
+            books.sort_by(|a, b| b.added_date.cmp(&a.added_date));
+            feed(
+                catalog,
+                book_feed("Recently added", "/new", query, books, page),
+            )
+        }
+        "/authors" => feed(catalog, authors_feed(catalog)),
+        "/author" => {
+            let Some(name) = param(query, "name") else {
+                return not_found();
+            };
+            let books = catalog
+                .books
+                .iter()
+                .filter(|b| b.authors.iter().any(|a| a.name == name))
+                .collect();
+            feed(catalog, book_feed(&name, "/author", query, books, page))
+        }
+        "/collections" => feed(catalog, collections_feed(catalog)),
+        "/collection" => {
+            let Some(name) = param(query, "name") else {
+                return not_found();
+            };
+            let Some(ids) = catalog.collections.get(&name) else {
+                return not_found();
+            };
+            let books = catalog
+                .books
+                .iter()
+                .filter(|b| ids.contains(&b.id))
+                .collect();
+            feed(catalog, book_feed(&name, "/collection", query, books, page))
+        }
+        "/search" => {
+            let terms = param(query, "q").unwrap_or_default();
+            let books = catalog.books.iter().filter(|b| b.matches(&terms)).collect();
+            let title = format!("Search: {terms}");
+            feed(catalog, book_feed(&title, "/search", query, books, page))
+        }
+        "/opensearch.xml" => respond(
+            StatusCode::OK,
+            "application/opensearchdescription+xml",
+            write_opensearch(CATALOG_TITLE, &format!("{ROOT}/search?q={{searchTerms}}")).into(),
+        ),
+        _ => match rest.strip_prefix("/books/").and_then(|r| r.split_once('/')) {
+            Some((id, "file")) => book_file(catalog, id),
+            Some((id, "cover")) => book_cover(catalog, id),
+            _ => not_found(),
+        },
+    }
+}
+
+/* =========================
+   FEEDS
+========================= */
+
+fn root_feed(catalog: &Catalog) -> OpdsFeed {
+    let nav = |title: &str, path: &str, summary: String| NavigationLink {
+        title: title.to_string(),
+        href: format!("{ROOT}{path}"),
+        summary: Some(summary),
+    };
+    let mut navigation = vec![
+        nav(
+            "All books",
+            "/books",
+            format!("{} books, by title", catalog.books.len()),
+        ),
+        nav("Recently added", "/new", "Newest first".to_string()),
+        nav("Authors", "/authors", "Browse by author".to_string()),
+    ];
+    if !catalog.collections.is_empty() {
+        navigation.push(nav(
+            "Collections",
+            "/collections",
+            format!("{} collections", catalog.collections.len()),
+        ));
+    }
+
+    OpdsFeed {
+        title: CATALOG_TITLE.to_string(),
+        url: ROOT.to_string(),
+        navigation,
+        ..OpdsFeed::default()
+    }
+}
+
+fn authors_feed(catalog: &Catalog) -> OpdsFeed {
+    // sort name -> (nama, jumlah buku), seperti Library::authors_index
+    let mut authors: BTreeMap<String, (String, usize)> = BTreeMap::new();
+    for book in &catalog.books {
+        for author in &book.authors {
+            authors
+                .entry(author.sort_name.to_lowercase())
+                .or_insert_with(|| (author.name.clone(), 0))
+                .1 += 1;
+        }
+    }
+
+    OpdsFeed {
+        title: "Authors".to_string(),
+        url: format!("{ROOT}/authors"),
+        navigation: authors
+            .into_values()
+            .map(|(name, count)| NavigationLink {
+                href: format!("{ROOT}/author?name={}", encode_query(&name)),
+                title: name,
+                summary: Some(books_summary(count)),
+            })
+            .collect(),
+        ..OpdsFeed::default()
+    }
+}
+
+fn collections_feed(catalog: &Catalog) -> OpdsFeed {
+    OpdsFeed {
+        title: "Collections".to_string(),
+        url: format!("{ROOT}/collections"),
+        navigation: catalog
+            .collections
+            .iter()
+            .map(|(name, ids)| NavigationLink {
+                title: name.clone(),
+                href: format!("{ROOT}/collection?name={}", encode_query(name)),
+                summary: Some(books_summary(ids.len())),
+            })
+            .collect(),
+        ..OpdsFeed::default()
+    }
+}
+
+/// One page of books; `query` is the request's, minus `page`. A page past
+/// the end gives the last one.
+fn book_feed(
+    title: &str,
+    path: &str,
+    query: &str,
+    books: Vec<&BookMetadata>,
+    page: usize,
+) -> OpdsFeed {
+    let pages = books.len().div_ceil(PAGE_SIZE).max(1);
+    let page = page.min(pages);
+    let others: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("page="))
+        .collect();
+    let page_url = |page: usize| {
+        let mut pairs = others.clone();
+        let page = format!("page={page}");
+        if page != "page=1" {
+            pairs.push(&page);
+        }
+        if pairs.is_empty() {
+            format!("{ROOT}{path}")
+        } else {
+            format!("{ROOT}{path}?{}", pairs.join("&"))
+        }
+    };
+
+    OpdsFeed {
+        title: title.to_string(),
+        url: page_url(page),
+        total: Some(books.len() as u64),
+        entries: books
+            .iter()
+            .skip((page - 1) * PAGE_SIZE)
+            .take(PAGE_SIZE)
+            .map(|book| book_entry(book))
+            .collect(),
+        first: (page > 1).then(|| page_url(1)),
+        previous: (page > 1).then(|| page_url(page - 1)),
+        next: (page < pages).then(|| page_url(page + 1)),
+        last: (page < pages).then(|| page_url(pages)),
+        ..OpdsFeed::default()
+    }
+}
+
+fn book_entry(book: &BookMetadata) -> OpdsEntry {
+    let cover = book
+        .cover_image
+        .as_ref()
+        .map(|_| format!("{ROOT}/books/{}/cover", book.id));
+    OpdsEntry {
+        id: format!("urn:uuid:{}", book.id),
+        title: book.title.clone(),
+        authors: book.authors.iter().map(|a| a.name.clone()).collect(),
+        summary: book
+            .series
+            .as_ref()
+            .map(|s| format!("{} #{}", s.name, s.index)),
+        language: None,
+        thumbnail: cover.clone(),
+        cover,
+        acquisitions: vec![Acquisition {
+            href: format!("{ROOT}/books/{}/file", book.id),
+            mime_type: book.format.mime_type().to_string(),
+            kind: AcquisitionKind::Download,
+        }],
+    }
+}
+
+fn books_summary(count: usize) -> String {
+    match count {
+        1 => "1 book".to_string(),
+        n => format!("{n} books"),
+    }
+}
+
+/* =========================
+   DOWNLOADS
+========================= */
+
+fn book_file(catalog: &Catalog, id: &str) -> HttpResponse {
+    let Some(book) = catalog.books.iter().find(|b| b.id == id) else {
+        return not_found();
+    };
+    // Buku tanpa blob belum punya file di perangkat ini
+    let opened = match &book.blob {
+        Some(_) => book.open_file(),
+        None => Err("No file on this device".to_string()),
+    };
+    let file = match opened {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("Cannot serve '{}': {}", book.title, e);
+            return text(StatusCode::INTERNAL_SERVER_ERROR, "Book file unavailable");
+        }
+    };
+
+    let name = book.file_name.replace(['"', '\\', '\r', '\n'], "_");
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", book.format.mime_type())
+        .header("content-length", book.size)
+        .header(
+            "content-disposition",
+            format!("attachment; filename=\"{name}\""),
+        )
+        .body(FileBody::stream(file).boxed())
+        .unwrap_or_else(|_| empty_response())
+}
+
+/// A book file read on a blocking thread and sent as it comes, so large
+/// PDFs are never held in memory whole
+struct FileBody {
+    chunks: mpsc::Receiver<io::Result<Bytes>>,
+}
+
+impl FileBody {
+    fn stream(mut file: Box<dyn Read + Send>) -> Self {
+        let (sender, chunks) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || loop {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let read = match file.read(&mut chunk) {
+                Ok(0) => return,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    let _ = sender.blocking_send(Err(e));
+                    return;
+                }
+            };
+            chunk.truncate(read);
+            // Klien memutus unduhan: berhenti membaca
+            if sender.blocking_send(Ok(chunk.into())).is_err() {
+                return;
+            }
+        });
+        Self { chunks }
+    }
+}
+
+impl Body for FileBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        (self.chunks.poll_recv(cx)).map(|chunk| chunk.map(|chunk| chunk.map(Frame::data)))
+    }
+}
+
+fn book_cover(catalog: &Catalog, id: &str) -> HttpResponse {
+    let cover = catalog
+        .books
+        .iter()
+        .find(|b| b.id == id)
+        .and_then(|b| b.cover_image.as_deref());
+    let Some(cover) = cover else {
+        return not_found();
+    };
+    // data:image/jpeg;base64,...
+    let mime = cover
+        .strip_prefix("data:")
+        .and_then(|c| c.split([';', ',']).next())
+        .unwrap_or("image/jpeg")
+        .to_string();
+    match decode_data_url(cover) {
+        Ok(bytes) => respond(StatusCode::OK, &mime, bytes),
+        Err(_) => not_found(),
+    }
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+fn feed(catalog: &Catalog, feed: OpdsFeed) -> HttpResponse {
+    let feed = OpdsFeed {
+        search: Some(SearchLink::Description(format!("{ROOT}/opensearch.xml"))),
+        ..feed
+    };
+    let xml = write_atom_feed(&feed, ROOT, &catalog.updated);
+    respond(StatusCode::OK, FEED_TYPE, xml.into())
+}
+
+fn param(query: &str, name: &str) -> Option<String> {
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then(|| decode_query(value))
+    })
+}
+
+/// IP address of the interface used for the LAN; no packet is sent
+fn local_ip() -> String {
+    UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 80))?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+fn respond(status: StatusCode, content_type: &str, body: Vec<u8>) -> HttpResponse {
+    Response::builder()
+        .status(status)
+        .header("content-type", content_type)
+        .body(full(body))
+        .unwrap_or_else(|_| empty_response())
+}
+
+fn full(body: impl Into<Bytes>) -> BoxBody<Bytes, io::Error> {
+    Full::new(body.into())
+        .map_err(|never| match never {})
+        .boxed()
+}
+
+fn empty_response() -> HttpResponse {
+    Response::new(full(Bytes::new()))
+}
+
+fn text(status: StatusCode, message: &str) -> HttpResponse {
+    respond(
+        status,
+        "text/plain; charset=utf-8",
+        message.as_bytes().to_vec(),
+    )
+}
+
+fn not_found() -> HttpResponse {
+    text(StatusCode::NOT_FOUND, "Not found")
+}
+
+fn redirect(location: &str) -> HttpResponse {
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header("location", location)
+        .body(full(Bytes::new()))
+        .unwrap_or_else(|_| empty_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hikmah_core::models::{Author, AuthorRole, BookFormat};
+
+    fn book(id: &str, title: &str, author: &str) -> BookMetadata {
+        BookMetadata {
+            id: id.to_string(),
+            title: title.to_string(),
+            authors: vec![Author::new(author, AuthorRole::Author)],
+            series: None,
+            tags: vec![],
+            rating: None,
+            identifiers: BTreeMap::new(),
+            file_name: format!("{title}.epub"),
+            format: BookFormat::EPUB,
+            size: 5,
+            file_data: String::new(),
+            blob: None,
+            cover_image: None,
+            last_read_position: 0,
+            total_pages: 0,
+            added_date: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn catalog() -> Catalog {
+        Catalog {
+            books: vec![
+                book("riyad", "Riyad as-Salihin", "Yahya ibn Sharaf al-Nawawi"),
+                book("arbain", "Al-Arbain", "Yahya ibn Sharaf al-Nawawi"),
+                book("tafsir", "Tafsir al-Quran al-Azim", "Ismail ibn Kathir"),
+            ],
+            collections: BTreeMap::from([("Hadis".to_string(), vec!["riyad".to_string()])]),
+            updated: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn numbered(count: usize) -> Catalog {
+        Catalog {
+            books: (0..count)
+                .map(|i| {
+                    book(
+                        &format!("b{i}"),
+                        &format!("Jilid {i:03}"),
+                        "Ibn Hajar al-Asqalani",
+                    )
+                })
+                .collect(),
+            ..Catalog::default()
+        }
+    }
+
+    async fn body(response: HttpResponse) -> String {
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn params_are_decoded() {
+        let query = "q=ibn+kathir&page=2&name=Ibn%20Hajar&empty=";
+        assert_eq!(param(query, "q").as_deref(), Some("ibn kathir"));
+        assert_eq!(param(query, "page").as_deref(), Some("2"));
+        assert_eq!(param(query, "name").as_deref(), Some("Ibn Hajar"));
+        assert_eq!(param(query, "empty").as_deref(), Some(""));
+        assert_eq!(param(query, "missing"), None);
+        assert_eq!(param("", "q"), None);
+    }
+
+    #[test]
+    fn routes() {
+        let catalog = catalog();
+        let status = |path: &str, query: &str| route(&catalog, path, query).status();
+
+        let redirected = route(&catalog, "/", "");
+        assert_eq!(redirected.status(), StatusCode::FOUND);
+        assert_eq!(redirected.headers()["location"], ROOT);
+
+        for path in [
+            "/opds",
+            "/opds/",
+            "/opds/books",
+            "/opds/new",
+            "/opds/authors",
+        ] {
+            assert_eq!(status(path, ""), StatusCode::OK, "{path}");
+        }
+        assert_eq!(
+            route(&catalog, "/opds", "").headers()["content-type"],
+            FEED_TYPE
+        );
+        assert_eq!(status("/opds/collections", ""), StatusCode::OK);
+        assert_eq!(status("/opds/search", "q=nawawi"), StatusCode::OK);
+        assert_eq!(status("/opds/opensearch.xml", ""), StatusCode::OK);
+
+        assert_eq!(status("/elsewhere", ""), StatusCode::NOT_FOUND);
+        assert_eq!(status("/opds/unknown", ""), StatusCode::NOT_FOUND);
+        assert_eq!(status("/opds/author", ""), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status("/opds/collection", "name=Fiqh"),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status("/opds/books/riyad/other", ""), StatusCode::NOT_FOUND);
+        assert_eq!(status("/opds/books/riyad/cover", ""), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn authors_and_collections_list_their_books() {
+        let catalog = catalog();
+
+        let authors = body(route(&catalog, "/opds/authors", "")).await;
+        assert!(authors.contains("/opds/author?name=Yahya+ibn+Sharaf+al-Nawawi"));
+        assert!(authors.contains("2 books"));
+        assert!(authors.contains("1 book<"));
+
+        let nawawi = body(route(
+            &catalog,
+            "/opds/author",
+            "name=Yahya+ibn+Sharaf+al-Nawawi",
+        ))
+        .await;
+        assert!(nawawi.contains("Riyad as-Salihin") && nawawi.contains("Al-Arbain"));
+        assert!(!nawawi.contains("Tafsir"));
+
+        let hadis = body(route(&catalog, "/opds/collection", "name=Hadis")).await;
+        assert!(hadis.contains("Riyad as-Salihin"));
+        assert!(!hadis.contains("Al-Arbain"));
+    }
+
+    #[test]
+    fn book_feeds_are_paged() {
+        let catalog = numbered(PAGE_SIZE * 2 + 20);
+        let books: Vec<&BookMetadata> = catalog.books.iter().collect();
+        let page =
+            |query: &str, page: usize| book_feed("Search", "/search", query, books.clone(), page);
+
+        let first = page("q=jilid", 1);
+        assert_eq!(first.entries.len(), PAGE_SIZE);
+        assert_eq!(first.total, Some(books.len() as u64));
+        assert_eq!(first.url, "/opds/search?q=jilid");
+        assert_eq!(first.first, None);
+        assert_eq!(first.next.as_deref(), Some("/opds/search?q=jilid&page=2"));
+        assert_eq!(first.last.as_deref(), Some("/opds/search?q=jilid&page=3"));
+
+        let last = page("q=jilid&page=3", 3);
+        assert_eq!(last.entries.len(), 20);
+        assert_eq!(last.entries[0].title, "Jilid 100");
+        assert_eq!(
+            last.previous.as_deref(),
+            Some("/opds/search?q=jilid&page=2")
+        );
+        assert_eq!(last.first.as_deref(), Some("/opds/search?q=jilid"));
+        assert_eq!((last.next, last.last), (None, None));
+
+        // Halaman di luar jangkauan menampilkan halaman terakhir
+        let beyond = page("q=jilid&page=9", 9);
+        assert_eq!(beyond.url, "/opds/search?q=jilid&page=3");
+        assert_eq!(beyond.entries.len(), 20);
+        assert_eq!(beyond.next, None);
+
+        let empty = book_feed("Kosong", "/books", "", vec![], 4);
+        assert_eq!(empty.url, "/opds/books");
+        assert!(empty.entries.is_empty());
+        assert_eq!((empty.previous, empty.next), (None, None));
+    }
+
+    #[tokio::test]
+    async fn book_files_need_a_file_on_this_device() {
+        let catalog = catalog();
+
+        let unknown = route(&catalog, "/opds/books/nope/file", "");
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+        // Hanya metadata (mis. dari sync) tanpa file di perangkat ini
+        let metadata_only = route(&catalog, "/opds/books/riyad/file", "");
+        assert_eq!(metadata_only.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body(metadata_only).await, "Book file unavailable");
+    }
+}
@@ -0,0 +1,42 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// =======================
+/// Folder Watcher
+/// =======================
+///
+/// Memantau folder yang dipilih di Settings dan memberi sinyal lewat `changes`
+/// setiap kali ada file yang dibuat, diubah, dipindah, atau dihapus. Isinya
+/// tidak dibaca di sini: `scan_folders` dan `Library::apply_folder_scan` yang
+/// membandingkan folder dengan scan sebelumnya. Watcher berhenti saat di-drop.
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl FolderWatcher {
+    /// Watch `folders` recursively. Folders that can't be watched (not
+    /// mounted right now, no permission) are skipped with a warning.
+    pub fn start(folders: &[String], changes: Arc<Notify>) -> Result<Self, String> {
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            match event {
+                // Membaca file tidak mengubah apa pun
+                Ok(event) if event.kind.is_access() => {}
+                Ok(_) => changes.notify_one(),
+                Err(e) => warn!("Folder watcher error: {}", e),
+            }
+        })
+        .map_err(|e| format!("Could not watch folders: {e}"))?;
+
+        for folder in folders {
+            match watcher.watch(Path::new(folder), RecursiveMode::Recursive) {
+                Ok(()) => info!("Watching {} for new books", folder),
+                Err(e) => warn!("Could not watch {}: {}", folder, e),
+            }
+        }
+
+        Ok(Self { _watcher: watcher })
+    }
+}
@@ -0,0 +1,558 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::blobs;
+use crate::crdt::ReadingState;
+use crate::models::{Annotation, BookMetadata, Bookmark};
+use crate::storage::{migrate, StoredLibrary, SCHEMA_VERSION};
+use crate::sync::SyncState;
+#[cfg(target_arch = "wasm32")]
+use crate::utils::encode_data_url;
+use crate::watch::WatchState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Versi format arsip backup. Naikkan jika struktur arsip berubah.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const BACKUP_FORMAT_NAME: &str = "hikmah-backup";
+const MANIFEST_ENTRY: &str = "manifest.json";
+const LIBRARY_ENTRY: &str = "library.json";
+const ANNOTATIONS_ENTRY: &str = "annotations.json";
+const BOOKMARKS_ENTRY: &str = "bookmarks.json";
+const PREFERENCES_ENTRY: &str = "preferences.json";
+const BOOKS_DIR: &str = "books";
+
+/// =======================
+/// Data Model
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_date: String,
+    pub book_count: usize,
+    pub annotation_count: usize,
+    /// Storage schema of library.json, so old backups go through the same migrations
+    #[serde(default = "first_backup_schema_version")]
+    pub schema_version: u32,
+    /// Books that had no file on the device the backup came from (metadata
+    /// pulled from another device); the archive has no `books/` entry for them
+    #[serde(default)]
+    pub books_without_file: Vec<String>,
+}
+
+/// Backups from before `schema_version` was recorded already used schema 2
+fn first_backup_schema_version() -> u32 {
+    2
+}
+
+/// Isi arsip backup setelah dibaca: metadata buku, annotations dan
+/// preferences. File buku tetap di dalam arsip sampai diambil dengan
+/// [`LibraryBackup::restore_file`], satu per satu.
+pub struct LibraryBackup<R> {
+    pub manifest: BackupManifest,
+    pub books: Vec<BookMetadata>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<Bookmark>,
+    pub preferences: BTreeMap<String, String>,
+    archive: ZipArchive<R>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Keep the current library and add what is missing
+    Merge,
+    /// Throw the current library away and use the backup as-is
+    Replace,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestoreReport {
+    pub books_added: usize,
+    pub annotations_added: usize,
+    pub bookmarks_added: usize,
+    pub conflicts: Vec<RestoreConflict>,
+}
+
+/// A backup entry that was skipped because it clashes with the current library
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestoreConflict {
+    pub book_id: String,
+    pub title: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for RestoreReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Restored {} books, {} annotations and {} bookmarks",
+            self.books_added, self.annotations_added, self.bookmarks_added
+        )?;
+        if !self.conflicts.is_empty() {
+            write!(f, ", {} skipped:", self.conflicts.len())?;
+            for conflict in &self.conflicts {
+                write!(f, "\n{}: {}", conflict.title, conflict.reason)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// =======================
+/// Export
+/// =======================
+///
+/// Write the library into a single zip archive:
+/// - manifest.json      format name + version
+/// - library.json       book metadata without the file blobs
+/// - books/<id>.<ext>   raw book files, for the books that have one
+/// - annotations.json
+/// - bookmarks.json
+/// - preferences.json
+///
+/// Book files are streamed from the blob store into `out`, one at a time.
+/// `preferences` should already leave out anything private to this device.
+pub fn export_backup<W: Write + Seek>(
+    out: W,
+    books: &[BookMetadata],
+    annotations: &[Annotation],
+    bookmarks: &[Bookmark],
+    preferences: &BTreeMap<String, String>,
+) -> Result<W, String> {
+    let mut zip = ZipWriter::new(out);
+    let json_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // EPUB/PDF sudah terkompresi, tidak perlu dikompres ulang
+    let blob_options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    let manifest = BackupManifest {
+        format: BACKUP_FORMAT_NAME.to_string(),
+        version: BACKUP_FORMAT_VERSION,
+        created_date: chrono::Utc::now().to_rfc3339(),
+        book_count: books.len(),
+        annotation_count: annotations.len(),
+        schema_version: SCHEMA_VERSION,
+        books_without_file: (books.iter())
+            .filter(|b| !b.has_file())
+            .map(|b| b.id.clone())
+            .collect(),
+    };
+    write_json(&mut zip, MANIFEST_ENTRY, &manifest, json_options)?;
+
+    let index: Vec<BookMetadata> = books
+        .iter()
+        .map(|book| BookMetadata {
+            file_data: String::new(),
+            blob: None,
+            ..book.clone()
+        })
+        .collect();
+    write_json(&mut zip, LIBRARY_ENTRY, &index, json_options)?;
+
+    for book in books.iter().filter(|b| b.has_file()) {
+        let mut file = book
+            .open_file()
+            .map_err(|e| format!("Failed to read '{}': {}", book.title, e))?;
+        zip.start_file(blob_path(book), blob_options)
+            .map_err(|e| e.to_string())
+            .and_then(|_| io::copy(&mut file, &mut zip).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to write '{}': {}", book.title, e))?;
+    }
+
+    write_json(&mut zip, ANNOTATIONS_ENTRY, &annotations, json_options)?;
+    write_json(&mut zip, BOOKMARKS_ENTRY, &bookmarks, json_options)?;
+    write_json(&mut zip, PREFERENCES_ENTRY, preferences, json_options)?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish backup: {e}"))
+}
+
+/// Suggested file name, e.g. `bayt-alhikmah-backup-2025-01-31.zip`
+/// (`.zip.enc` when the archive is encrypted)
+pub fn backup_file_name(encrypted: bool) -> String {
+    format!(
+        "bayt-alhikmah-backup-{}.zip{}",
+        chrono::Utc::now().format("%Y-%m-%d"),
+        if encrypted { ".enc" } else { "" }
+    )
+}
+
+/// =======================
+/// Import
+/// =======================
+///
+/// Read the index of an archive written by [`export_backup`]. The books
+/// come without their files; see [`LibraryBackup::restore_file`].
+pub fn read_backup<R: Read + Seek>(source: R) -> Result<LibraryBackup<R>, String> {
+    let mut zip = ZipArchive::new(source).map_err(|e| format!("Invalid backup archive: {e}"))?;
+
+    let manifest: BackupManifest = read_json(&mut zip, MANIFEST_ENTRY)?;
+    if manifest.format != BACKUP_FORMAT_NAME {
+        return Err("Not a Bayt Alhikmah backup".to_string());
+    }
+    if manifest.version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "Backup was made by a newer version (format {}, supported {})",
+            manifest.version, BACKUP_FORMAT_VERSION
+        ));
+    }
+
+    let mut doc = json!({
+        "schema_version": manifest.schema_version,
+        "books": read_json::<Value, _>(&mut zip, LIBRARY_ENTRY)?,
+        "annotations": read_json::<Value, _>(&mut zip, ANNOTATIONS_ENTRY)?,
+        "preferences": read_json::<Value, _>(&mut zip, PREFERENCES_ENTRY)?,
+        // Status sync & CRDT milik perangkat asal; dibangun ulang saat restore
+        "sync": SyncState::default(),
+        "reading_state": ReadingState::default(),
+        "watch": WatchState::default(),
+    });
+    // bookmarks.json belum ada di backup lama; migrasi v4 -> v5 yang menambahkannya
+    if zip.index_for_name(BOOKMARKS_ENTRY).is_some() {
+        doc["bookmarks"] = read_json(&mut zip, BOOKMARKS_ENTRY)?;
+    }
+    let StoredLibrary {
+        books,
+        annotations,
+        bookmarks,
+        preferences,
+        ..
+    } = migrate(doc).map_err(|e| format!("Could not upgrade backup: {e}"))?;
+
+    Ok(LibraryBackup {
+        manifest,
+        books,
+        annotations,
+        bookmarks,
+        preferences,
+        archive: zip,
+    })
+}
+
+impl<R: Read + Seek> LibraryBackup<R> {
+    /// Take a book's file out of the archive: streamed into the blob store
+    /// on desktop, a data URL on web (which has no blob store). Books that
+    /// were backed up without a file stay without one.
+    pub fn restore_file(&mut self, book: &mut BookMetadata) -> Result<(), String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let blob = blobs::blob_name(&book.id, book.format.to_extension());
+        #[cfg(target_arch = "wasm32")]
+        let blob = String::new();
+        self.restore_file_as(book, blob)
+    }
+
+    /// Like [`LibraryBackup::restore_file`], under a temporary blob name
+    /// that [`commit_staged`] turns into the real one. Replacing the
+    /// library goes through here, so its files are only touched once the
+    /// whole archive could be read.
+    pub fn restore_staged(&mut self, book: &mut BookMetadata) -> Result<(), String> {
+        let blob = format!("{}.restoring.{}", book.id, book.format.to_extension());
+        self.restore_file_as(book, blob)
+    }
+
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn restore_file_as(&mut self, book: &mut BookMetadata, blob: String) -> Result<(), String> {
+        book.blob = None;
+        book.file_data = String::new();
+        if self.manifest.books_without_file.contains(&book.id) {
+            return Ok(());
+        }
+
+        let name = blob_path(book);
+        let mut entry = self
+            .archive
+            .by_name(&name)
+            .map_err(|e| format!("Backup is missing {name}: {e}"))?;
+        // Backup lama menulis entri kosong untuk buku tanpa file
+        if entry.size() == 0 {
+            return Ok(());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            blobs::store_blob(&blob, &mut entry)?;
+            book.blob = Some(blob);
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let mut bytes = Vec::new();
+            entry
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to read {name}: {e}"))?;
+            book.file_data = encode_data_url(book.format.mime_type(), &bytes);
+        }
+        Ok(())
+    }
+}
+
+/// Move a file from [`LibraryBackup::restore_staged`] to the book's own
+/// blob name, once the books it replaces are gone
+pub fn commit_staged(book: &mut BookMetadata) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(staged) = book.blob.take() {
+        let blob = blobs::blob_name(&book.id, book.format.to_extension());
+        // Gagal diganti nama: nama sementara tetap sah sebagai blob
+        book.blob = Some(match blobs::rename_blob(&staged, &blob) {
+            Ok(()) => blob,
+            Err(e) => {
+                tracing::warn!("{}", e);
+                staged
+            }
+        });
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = book;
+}
+
+/// Drop the files of a restore that was given up
+pub fn discard_staged(books: &[BookMetadata]) {
+    #[cfg(not(target_arch = "wasm32"))]
+    for blob in books.iter().filter_map(|b| b.blob.as_deref()) {
+        let _ = blobs::remove_blob(blob);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = books;
+}
+
+/* =========================
+   ZIP HELPERS
+========================= */
+
+fn blob_path(book: &BookMetadata) -> String {
+    format!("{}/{}.{}", BOOKS_DIR, book.id, book.format.to_extension())
+}
+
+fn write_json<T: Serialize + ?Sized, W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    value: &T,
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let json =
+        serde_json::to_vec_pretty(value).map_err(|e| format!("Failed to encode {name}: {e}"))?;
+    zip.start_file(name, options)
+        .and_then(|_| zip.write_all(&json).map_err(Into::into))
+        .map_err(|e| format!("Failed to write {name}: {e}"))
+}
+
+fn read_entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|e| format!("Backup is missing {name}: {e}"))?;
+    let mut buf = Vec::new();
+    entry
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("Failed to read {name}: {e}"))?;
+    Ok(buf)
+}
+
+fn read_json<T: for<'de> Deserialize<'de>, R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<T, String> {
+    let bytes = read_entry(zip, name)?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Invalid {name}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_picker::FileSelection;
+    use crate::library::Library;
+    use crate::storage::use_test_data_dir;
+    use crate::utils::encode_data_url;
+    use crate::webdav::WebDavConfig;
+    use std::io::Cursor;
+
+    fn add_text_book(library: &mut Library, name: &str, text: &str) -> String {
+        library
+            .add_book_from_file(FileSelection {
+                name: name.to_string(),
+                r#type: "text/plain".to_string(),
+                size: text.len() as u64,
+                data: encode_data_url("text/plain", text.as_bytes()),
+            })
+            .unwrap();
+        library.books.last().unwrap().id.clone()
+    }
+
+    fn use_webdav(library: &mut Library, password: &str) {
+        library
+            .set_webdav_config(Some(WebDavConfig {
+                url: "https://dav.example.com/Hikmah".to_string(),
+                username: "me".to_string(),
+                password: password.to_string(),
+            }))
+            .unwrap();
+    }
+
+    /// Library dengan dua buku, satu anotasi, satu bookmark dan kredensial WebDAV
+    /// serta server sync
+    fn backed_up_library() -> (Vec<u8>, String, String) {
+        use_test_data_dir();
+        let mut library = Library::new();
+        let arbain = add_text_book(&mut library, "arbain.txt", "Innamal a'malu binniyat");
+        let bulugh = add_text_book(&mut library, "bulugh.txt", "Kitab ath-thaharah");
+        library
+            .add_annotation(&arbain, 1, "binniyat".to_string(), Some("niat".to_string()))
+            .unwrap();
+        library.add_bookmark(&bulugh, 3, None).unwrap();
+        library
+            .set_app_preferences(crate::preferences::Preferences {
+                max_import_mb: 20,
+                ..Default::default()
+            })
+            .unwrap();
+        use_webdav(&mut library, "old-secret");
+        library
+            .set_sync_server_config(Some(
+                crate::sync::SyncConfig::new("https://sync.example.com", "me", "secret").unwrap(),
+            ))
+            .unwrap();
+        (library.export_backup().unwrap(), arbain, bulugh)
+    }
+
+    #[test]
+    fn backups_leave_out_sync_credentials() {
+        let (archive, _, _) = backed_up_library();
+        let backup = read_backup(Cursor::new(&archive)).unwrap();
+        assert_eq!(backup.books.len(), 2);
+        assert!(backup.books.iter().all(|b| b.file_data.is_empty()));
+        assert!(backup.preferences.contains_key("app.preferences"));
+        assert!(!backup
+            .preferences
+            .keys()
+            .any(|k| k.starts_with("webdav.") || k.starts_with("sync.")));
+    }
+
+    #[test]
+    fn replace_restores_the_backup_and_keeps_this_devices_credentials() {
+        let (archive, arbain, bulugh) = backed_up_library();
+
+        use_test_data_dir();
+        let mut library = Library::new();
+        let other = add_text_book(&mut library, "other.txt", "Buku lain");
+        use_webdav(&mut library, "new-secret");
+
+        let report = library
+            .restore_backup(&archive, RestoreMode::Replace, None)
+            .unwrap();
+        assert_eq!(report.books_added, 2);
+        assert_eq!(report.annotations_added, 1);
+        assert_eq!(report.bookmarks_added, 1);
+        assert!(report.conflicts.is_empty());
+
+        assert!(library.get_book(&other).is_none());
+        let book = library.get_book(&arbain).unwrap();
+        assert!(book.blob.is_some() && book.file_data.is_empty());
+        assert_eq!(book.read_file().unwrap(), b"Innamal a'malu binniyat");
+        assert_eq!(library.annotations[0].note.as_deref(), Some("niat"));
+        assert_eq!(library.bookmarks[0].book_id, bulugh);
+        assert_eq!(library.app_preferences().max_import_mb, 20);
+        assert_eq!(library.webdav_config().unwrap().password, "new-secret");
+    }
+
+    #[test]
+    fn merge_adds_what_is_missing_and_reports_duplicates() {
+        let (archive, arbain, bulugh) = backed_up_library();
+
+        use_test_data_dir();
+        let mut library = Library::new();
+        // Nama & ukuran sama dengan buku di backup
+        let local = add_text_book(&mut library, "arbain.txt", "Innamal a'malu binniyat");
+
+        let report = library
+            .restore_backup(&archive, RestoreMode::Merge, None)
+            .unwrap();
+        assert_eq!(report.books_added, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].book_id, arbain);
+        // Anotasi untuk buku yang tidak dipulihkan ikut dilewati
+        assert_eq!(report.annotations_added, 0);
+        assert_eq!(report.bookmarks_added, 1);
+
+        assert!(library.get_book(&local).is_some());
+        let restored = library.get_book(&bulugh).unwrap();
+        assert_eq!(restored.read_file().unwrap(), b"Kitab ath-thaharah");
+        assert_eq!(library.books.len(), 2);
+        assert_eq!(library.webdav_config(), None);
+    }
+
+    /// The same archive without one of its entries
+    fn without_entry(archive: &[u8], name: &str) -> Vec<u8> {
+        let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut out = ZipWriter::new(Cursor::new(Vec::new()));
+        for i in 0..zip.len() {
+            let entry = zip.by_index_raw(i).unwrap();
+            if entry.name() != name {
+                out.raw_copy_file(entry).unwrap();
+            }
+        }
+        out.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn incomplete_backup_leaves_the_library_alone() {
+        let (archive, _, bulugh) = backed_up_library();
+        let archive = without_entry(&archive, &format!("{BOOKS_DIR}/{bulugh}.txt"));
+
+        let data_dir = use_test_data_dir();
+        let mut library = Library::new();
+        let other = add_text_book(&mut library, "other.txt", "Buku lain");
+        library.add_bookmark(&other, 1, None).unwrap();
+        let before = library.books.clone();
+
+        let error = library
+            .restore_backup(&archive, RestoreMode::Replace, None)
+            .unwrap_err();
+        assert!(error.contains("nothing was restored"), "{error}");
+
+        assert_eq!(library.books, before);
+        assert_eq!(library.bookmarks.len(), 1);
+        assert_eq!(
+            library.get_book(&other).unwrap().read_file().unwrap(),
+            b"Buku lain"
+        );
+        // File sementara dari restore yang batal ikut dibuang
+        let leftovers = std::fs::read_dir(data_dir.join("books"))
+            .map(|dir| dir.count())
+            .unwrap_or(0);
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn books_without_a_file_are_restored_without_one() {
+        use_test_data_dir();
+        let mut library = Library::new();
+        let arbain = add_text_book(&mut library, "arbain.txt", "Innamal a'malu binniyat");
+        // Metadata dari perangkat lain, filenya belum diunduh
+        library.books.push(BookMetadata {
+            id: "remote-book".to_string(),
+            blob: None,
+            file_data: String::new(),
+            file_name: "remote.txt".to_string(),
+            ..library.books[0].clone()
+        });
+
+        let archive = library.export_backup().unwrap();
+        let backup = read_backup(Cursor::new(&archive)).unwrap();
+        assert_eq!(backup.manifest.books_without_file, vec!["remote-book"]);
+        let mut zip = ZipArchive::new(Cursor::new(&archive)).unwrap();
+        assert!(zip.by_name("books/remote-book.txt").is_err());
+
+        use_test_data_dir();
+        let mut restored = Library::new();
+        let report = restored
+            .restore_backup(&archive, RestoreMode::Replace, None)
+            .unwrap();
+        assert_eq!(report.books_added, 2);
+        assert!(!restored.get_book("remote-book").unwrap().has_file());
+        assert!(restored.get_book(&arbain).unwrap().has_file());
+    }
+}
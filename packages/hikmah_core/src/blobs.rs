@@ -0,0 +1,147 @@
+use crate::storage;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const BLOB_DIR: &str = "books";
+
+/// =======================
+/// Blob Store
+/// =======================
+///
+/// File buku disimpan apa adanya di `<data dir>/books/<id>.<ext>`, bukan
+/// sebagai data URL base64 di `library.json`. Dipakai oleh import native di
+/// desktop, jadi PDF hasil scan ratusan MB tidak perlu dimuat ke memori.
+/// Web tidak punya data dir; di sana semua fungsi ini mengembalikan error.
+///
+/// Directory holding the book files
+pub fn blob_dir() -> Option<PathBuf> {
+    storage::data_dir().map(|dir| dir.join(BLOB_DIR))
+}
+
+/// `<id>.<ext>`, the name stored in [`BookMetadata::blob`](crate::models::BookMetadata::blob)
+pub fn blob_name(book_id: &str, extension: &str) -> String {
+    format!("{book_id}.{extension}")
+}
+
+/// Full path of a stored blob
+pub fn blob_path(name: &str) -> Result<PathBuf, String> {
+    // Nama berasal dari library.json; jangan sampai keluar dari folder
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid book file name: {name}"));
+    }
+    Ok(blob_dir()
+        .ok_or("No data directory to store book files in")?
+        .join(name))
+}
+
+/// Copy a file into the store without loading it into memory, hashing it
+/// on the way. Returns the number of bytes copied and their SHA-256.
+pub fn import_file(source: &Path, name: &str) -> Result<(u64, [u8; 32]), String> {
+    let file = fs::File::open(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    store_blob(name, file)
+}
+
+pub fn write_blob(name: &str, bytes: &[u8]) -> Result<(), String> {
+    store_blob(name, bytes).map(|_| ())
+}
+
+/// Stream `source` into the store (a file, a zip entry, a download), in one
+/// pass that also hashes it. Returns the number of bytes and their SHA-256.
+pub fn store_blob(name: &str, mut source: impl Read) -> Result<(u64, [u8; 32]), String> {
+    let target = blob_path(name)?;
+    create_dir(&target)?;
+
+    // Tulis ke file sementara dulu, supaya import yang gagal tidak
+    // meninggalkan file setengah jadi dengan nama yang sah
+    let tmp = target.with_extension("part");
+    let written = fs::File::create(&tmp).and_then(|file| {
+        let mut writer = HashingWriter {
+            inner: io::BufWriter::new(file),
+            hasher: Sha256::new(),
+        };
+        let size = io::copy(&mut source, &mut writer)?;
+        writer
+            .inner
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        Ok((size, writer.hasher.finalize().into()))
+    });
+    match written {
+        Ok(written) => {
+            fs::rename(&tmp, &target).map_err(|e| format!("Failed to store {name}: {e}"))?;
+            Ok(written)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            Err(format!("Failed to store {name}: {e}"))
+        }
+    }
+}
+
+/// Passes writes through while hashing them
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A stored blob opened for reading, so it can be streamed instead of
+/// loaded into memory
+pub fn open_blob(name: &str) -> Result<fs::File, String> {
+    let path = blob_path(name)?;
+    fs::File::open(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+pub fn read_blob(name: &str) -> Result<Vec<u8>, String> {
+    let path = blob_path(name)?;
+    fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+}
+
+/// SHA-256 of a file, read in chunks so large PDFs aren't loaded at once
+pub fn hash_file(path: &Path) -> Result<[u8; 32], String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(hasher.finalize().into())
+}
+
+/// Give a stored blob a new name, replacing the blob that had it
+pub fn rename_blob(from: &str, to: &str) -> Result<(), String> {
+    fs::rename(blob_path(from)?, blob_path(to)?)
+        .map_err(|e| format!("Failed to rename {from} to {to}: {e}"))
+}
+
+/// Missing files are not an error; the book is gone either way
+pub fn remove_blob(name: &str) -> Result<(), String> {
+    match fs::remove_file(blob_path(name)?) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to delete {name}: {e}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn create_dir(target: &Path) -> Result<(), String> {
+    match target.parent() {
+        Some(dir) => fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,566 @@
+use crate::blobs::blob_path;
+use crate::covers::make_thumbnail;
+use crate::formats::opf::{parse_package, write_package};
+use crate::models::{Author, AuthorRole, BookFormat, BookMetadata, Series};
+use crate::utils::decode_data_url;
+use rusqlite::{Connection, OpenFlags};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const METADATA_DB: &str = "metadata.db";
+const METADATA_OPF: &str = "metadata.opf";
+const COVER_FILE: &str = "cover.jpg";
+
+/// =======================
+/// Data Model
+/// =======================
+///
+/// Satu buku dari library Calibre, sebelum diimpor ke `Library`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibreBook {
+    pub title: String,
+    pub authors: Vec<Author>,
+    pub series: Option<Series>,
+    pub tags: Vec<String>,
+    pub rating: Option<u8>,
+    pub identifiers: BTreeMap<String, String>,
+    pub cover_path: Option<PathBuf>,
+    /// Every format Calibre has for this book (EPUB, PDF, MOBI, ...)
+    pub formats: Vec<PathBuf>,
+}
+
+impl CalibreBook {
+    /// Calibre may keep several formats per book; we import one, EPUB first
+    pub fn preferred_format(&self) -> Option<(&PathBuf, BookFormat)> {
+        [BookFormat::EPUB, BookFormat::PDF, BookFormat::TXT]
+            .into_iter()
+            .find_map(|wanted| {
+                self.formats.iter().find_map(|path| {
+                    let format = path
+                        .extension()
+                        .and_then(|e| BookFormat::from_extension(&e.to_string_lossy()))?;
+                    (format == wanted).then_some((path, format))
+                })
+            })
+    }
+
+    /// Calibre's metadata over what was read from the file itself, which
+    /// is less reliable
+    pub fn apply_to(&self, metadata: &mut BookMetadata) {
+        metadata.title = self.title.clone();
+        if !self.authors.is_empty() {
+            metadata.authors = self.authors.clone();
+        }
+        if self.series.is_some() {
+            metadata.series = self.series.clone();
+        }
+        if !self.tags.is_empty() {
+            metadata.tags = self.tags.clone();
+        }
+        metadata.rating = self.rating;
+        metadata.identifiers = self.identifiers.clone();
+        if let Some(cover) = (self.cover_path.as_ref())
+            .and_then(|p| fs::read(p).ok())
+            .and_then(|bytes| make_thumbnail(&bytes).ok())
+        {
+            metadata.cover_image = Some(cover);
+        }
+    }
+}
+
+/// =======================
+/// Reader
+/// =======================
+///
+/// Read a Calibre library folder. `metadata.db` is preferred; if it is missing
+/// or unreadable, fall back to the `metadata.opf` file Calibre writes next to
+/// every book.
+pub fn read_calibre_library(dir: &Path) -> Result<Vec<CalibreBook>, String> {
+    let db = dir.join(METADATA_DB);
+    if db.exists() {
+        match read_metadata_db(dir, &db) {
+            Ok(books) => return Ok(books),
+            Err(e) => tracing::warn!("Could not read {}: {}, trying OPF files", db.display(), e),
+        }
+    }
+
+    read_opf_files(dir)
+}
+
+/// A folder Calibre manages, recognised by its `metadata.db`
+pub fn is_calibre_library(dir: &Path) -> bool {
+    dir.join(METADATA_DB).is_file()
+}
+
+/* =========================
+   metadata.db (SQLite)
+========================= */
+
+fn read_metadata_db(dir: &Path, db: &Path) -> Result<Vec<CalibreBook>, String> {
+    // Read-only: Calibre mungkin sedang terbuka
+    let conn = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open Calibre database: {e}"))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.id, b.title, b.path, b.has_cover, b.series_index,
+                    (SELECT s.name FROM books_series_link l JOIN series s ON s.id = l.series
+                     WHERE l.book = b.id),
+                    (SELECT r.rating FROM books_ratings_link l JOIN ratings r ON r.id = l.rating
+                     WHERE l.book = b.id)
+             FROM books b ORDER BY b.id",
+        )
+        .map_err(db_error)?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<f64>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, Option<i64>>(6)?,
+            ))
+        })
+        .map_err(db_error)?;
+
+    let mut books = vec![];
+    for row in rows {
+        let (id, title, path, has_cover, series_index, series, rating) = row.map_err(db_error)?;
+        let book_dir = dir.join(&path);
+
+        books.push(CalibreBook {
+            title,
+            authors: book_authors(&conn, id)?,
+            series: series.map(|name| Series {
+                name,
+                index: series_index.unwrap_or(1.0),
+            }),
+            tags: book_tags(&conn, id)?,
+            rating: rating.map(|r| r.clamp(0, 10) as u8),
+            identifiers: book_identifiers(&conn, id)?,
+            cover_path: has_cover.then(|| book_dir.join(COVER_FILE)),
+            formats: book_formats(&conn, id, &book_dir)?,
+        });
+    }
+
+    Ok(books)
+}
+
+fn book_authors(conn: &Connection, book_id: i64) -> Result<Vec<Author>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.name, a.sort FROM books_authors_link l JOIN authors a ON a.id = l.author
+             WHERE l.book = ?1 ORDER BY l.id",
+        )
+        .map_err(db_error)?;
+
+    let authors = stmt
+        .query_map([book_id], |row| {
+            let mut author = Author::new(&row.get::<_, String>(0)?, AuthorRole::Author);
+            if let Some(sort) = row.get::<_, Option<String>>(1)?.filter(|s| !s.is_empty()) {
+                author.sort_name = sort;
+            }
+            Ok(author)
+        })
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+
+    Ok(authors)
+}
+
+fn book_tags(conn: &Connection, book_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name FROM books_tags_link l JOIN tags t ON t.id = l.tag
+             WHERE l.book = ?1 ORDER BY t.name",
+        )
+        .map_err(db_error)?;
+
+    let tags = stmt
+        .query_map([book_id], |row| row.get::<_, String>(0))
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+
+    Ok(tags)
+}
+
+fn book_identifiers(conn: &Connection, book_id: i64) -> Result<BTreeMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT type, val FROM identifiers WHERE book = ?1")
+        .map_err(db_error)?;
+
+    let identifiers = stmt
+        .query_map([book_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?.to_lowercase(),
+                row.get::<_, String>(1)?,
+            ))
+        })
+        .map_err(db_error)?
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .map_err(db_error)?;
+
+    Ok(identifiers)
+}
+
+/// Calibre stores files as `<book dir>/<data.name>.<format lowercase>`
+fn book_formats(conn: &Connection, book_id: i64, book_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut stmt = conn
+        .prepare("SELECT format, name FROM data WHERE book = ?1")
+        .map_err(db_error)?;
+
+    let formats = stmt
+        .query_map([book_id], |row| {
+            let format: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            Ok(book_dir.join(format!("{}.{}", name, format.to_lowercase())))
+        })
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+
+    Ok(formats)
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Calibre database error: {e}")
+}
+
+/* =========================
+   metadata.opf fallback
+========================= */
+
+/// Layout Calibre: `<library>/<Author>/<Title (id)>/metadata.opf`
+fn read_opf_files(dir: &Path) -> Result<Vec<CalibreBook>, String> {
+    let mut books = vec![];
+
+    for author_dir in sub_dirs(dir)? {
+        for book_dir in sub_dirs(&author_dir)? {
+            let opf_path = book_dir.join(METADATA_OPF);
+            if !opf_path.exists() {
+                continue;
+            }
+
+            let xml = fs::read_to_string(&opf_path)
+                .map_err(|e| format!("Failed to read {}: {}", opf_path.display(), e))?;
+            let package = parse_package(&xml)?;
+
+            let formats = fs::read_dir(&book_dir)
+                .map_err(|e| e.to_string())?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file() && path.file_name() != Some(METADATA_OPF.as_ref()))
+                .filter(|path| path.extension().is_some_and(|e| e != "jpg"))
+                .collect();
+
+            let cover_path = package
+                .cover_href
+                .map(|href| book_dir.join(href))
+                .or_else(|| Some(book_dir.join(COVER_FILE)))
+                .filter(|p| p.exists());
+
+            books.push(CalibreBook {
+                title: package.title.unwrap_or_else(|| {
+                    book_dir
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                }),
+                authors: package.authors,
+                series: package.series,
+                tags: package.tags,
+                rating: package.rating,
+                identifiers: package.identifiers,
+                cover_path,
+                formats,
+            });
+        }
+    }
+
+    if books.is_empty() {
+        return Err(format!(
+            "{} doesn't look like a Calibre library (no {} or {} files)",
+            dir.display(),
+            METADATA_DB,
+            METADATA_OPF
+        ));
+    }
+
+    Ok(books)
+}
+
+fn sub_dirs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+/// =======================
+/// Export
+/// =======================
+///
+/// Write a book the way Calibre lays out its library:
+/// `<dir>/<Author>/<Title>/{book file, metadata.opf, cover.jpg}`.
+/// The result can be added to Calibre ("Add books from folders") or read back
+/// with [`read_calibre_library`]. Returns the book's folder.
+pub fn export_book(book: &BookMetadata, dir: &Path) -> Result<PathBuf, String> {
+    let book_dir = dir
+        .join(safe_file_name(&book.author_display()))
+        .join(safe_file_name(&book.title));
+    fs::create_dir_all(&book_dir)
+        .map_err(|e| format!("Failed to create {}: {}", book_dir.display(), e))?;
+
+    let book_path = book_dir.join(safe_file_name(&book.file_name));
+    // File di blob store disalin langsung, tanpa dimuat ke memori
+    match &book.blob {
+        Some(blob) => fs::copy(blob_path(blob)?, &book_path).map(|_| ()),
+        None => fs::write(&book_path, decode_data_url(&book.file_data)?),
+    }
+    .map_err(|e| format!("Failed to write {}: {}", book_path.display(), e))?;
+
+    // Placeholder SVG tidak diekspor, Calibre membuat cover sendiri
+    let cover = book
+        .cover_image
+        .as_deref()
+        .filter(|c| c.starts_with("data:image/jpeg"))
+        .map(decode_data_url)
+        .transpose()?;
+    if let Some(cover) = &cover {
+        fs::write(book_dir.join(COVER_FILE), cover)
+            .map_err(|e| format!("Failed to write cover: {e}"))?;
+    }
+
+    let opf = write_package(book, cover.as_ref().map(|_| COVER_FILE));
+    fs::write(book_dir.join(METADATA_OPF), opf)
+        .map_err(|e| format!("Failed to write {METADATA_OPF}: {e}"))?;
+
+    Ok(book_dir)
+}
+
+/// Ganti karakter yang tidak boleh dipakai di nama file (Windows paling ketat)
+fn safe_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let cleaned = cleaned.trim().trim_end_matches('.');
+    if cleaned.is_empty() {
+        "Untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Just enough of Calibre's schema for the queries above
+    const SCHEMA: &str = "
+        CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT, path TEXT,
+                            has_cover BOOL, series_index REAL);
+        CREATE TABLE authors (id INTEGER PRIMARY KEY, name TEXT, sort TEXT);
+        CREATE TABLE books_authors_link (id INTEGER PRIMARY KEY, book INTEGER, author INTEGER);
+        CREATE TABLE series (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE books_series_link (id INTEGER PRIMARY KEY, book INTEGER, series INTEGER);
+        CREATE TABLE ratings (id INTEGER PRIMARY KEY, rating INTEGER);
+        CREATE TABLE books_ratings_link (id INTEGER PRIMARY KEY, book INTEGER, rating INTEGER);
+        CREATE TABLE tags (id INTEGER PRIMARY KEY, name TEXT);
+        CREATE TABLE books_tags_link (id INTEGER PRIMARY KEY, book INTEGER, tag INTEGER);
+        CREATE TABLE identifiers (id INTEGER PRIMARY KEY, book INTEGER, type TEXT, val TEXT);
+        CREATE TABLE data (id INTEGER PRIMARY KEY, book INTEGER, format TEXT, name TEXT);
+    ";
+
+    fn calibre_dir() -> PathBuf {
+        crate::storage::use_test_data_dir().join("Calibre Library")
+    }
+
+    #[test]
+    fn reads_books_from_metadata_db() {
+        let dir = calibre_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let conn = Connection::open(dir.join(METADATA_DB)).unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        conn.execute_batch(
+            "INSERT INTO books VALUES
+                 (1, 'Riyadh as-Salihin', 'An-Nawawi/Riyadh as-Salihin (1)', 1, 2.0),
+                 (2, 'Tanpa Apa-apa', 'Unknown/Tanpa Apa-apa (2)', 0, 1.0);
+             INSERT INTO authors VALUES
+                 (1, 'Imam an-Nawawi', 'Nawawi, Imam an-'), (2, 'Salim al-Hilali', '');
+             INSERT INTO books_authors_link VALUES (1, 1, 1), (2, 1, 2);
+             INSERT INTO series VALUES (1, 'Kitab Hadis');
+             INSERT INTO books_series_link VALUES (1, 1, 1);
+             INSERT INTO ratings VALUES (1, 8), (2, 40);
+             INSERT INTO books_ratings_link VALUES (1, 1, 1), (2, 2, 2);
+             INSERT INTO tags VALUES (1, 'hadis'), (2, 'adab');
+             INSERT INTO books_tags_link VALUES (1, 1, 1), (2, 1, 2);
+             INSERT INTO identifiers VALUES (1, 1, 'ISBN', '9789795920000');
+             INSERT INTO data VALUES
+                 (1, 1, 'PDF', 'Riyadh as-Salihin - Imam an-Nawawi'),
+                 (2, 1, 'EPUB', 'Riyadh as-Salihin - Imam an-Nawawi'),
+                 (3, 2, 'MOBI', 'Tanpa Apa-apa');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let books = read_calibre_library(&dir).unwrap();
+        assert_eq!(books.len(), 2);
+
+        let book_dir = dir.join("An-Nawawi/Riyadh as-Salihin (1)");
+        let riyadh = &books[0];
+        assert_eq!(riyadh.title, "Riyadh as-Salihin");
+        assert_eq!(
+            riyadh.authors,
+            vec![
+                Author {
+                    name: "Imam an-Nawawi".to_string(),
+                    sort_name: "Nawawi, Imam an-".to_string(),
+                    role: AuthorRole::Author,
+                },
+                // Sort kosong: dibuat dari nama
+                Author::new("Salim al-Hilali", AuthorRole::Author),
+            ]
+        );
+        assert_eq!(
+            riyadh.series,
+            Some(Series {
+                name: "Kitab Hadis".to_string(),
+                index: 2.0,
+            })
+        );
+        assert_eq!(riyadh.tags, vec!["adab", "hadis"]);
+        assert_eq!(riyadh.rating, Some(8));
+        assert_eq!(riyadh.identifiers["isbn"], "9789795920000");
+        assert_eq!(riyadh.cover_path, Some(book_dir.join(COVER_FILE)));
+
+        // EPUB lebih dulu, meskipun PDF tercatat duluan
+        let (path, format) = riyadh.preferred_format().unwrap();
+        assert_eq!(format, BookFormat::EPUB);
+        assert_eq!(
+            path,
+            &book_dir.join("Riyadh as-Salihin - Imam an-Nawawi.epub")
+        );
+
+        let other = &books[1];
+        assert!(other.authors.is_empty() && other.series.is_none());
+        assert_eq!(other.rating, Some(10));
+        assert_eq!(other.cover_path, None);
+        assert!(other.preferred_format().is_none());
+    }
+
+    #[test]
+    fn falls_back_to_opf_files_without_metadata_db() {
+        let dir = calibre_dir();
+        let book_dir = dir.join("Ibn Kathir").join("Qasas al-Anbiya (3)");
+        fs::create_dir_all(&book_dir).unwrap();
+        fs::write(
+            book_dir.join(METADATA_OPF),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+              <metadata xmlns:dc="http://purl.org/dc/elements/1.1/"
+                        xmlns:opf="http://www.idpf.org/2007/opf">
+                <dc:title>Qasas al-Anbiya</dc:title>
+                <dc:creator opf:role="aut" opf:file-as="Kathir, Ibn">Ibn Kathir</dc:creator>
+                <dc:subject>sirah</dc:subject>
+                <meta name="calibre:rating" content="6"/>
+              </metadata>
+              <guide><reference type="cover" href="cover.jpg"/></guide>
+            </package>"#,
+        )
+        .unwrap();
+        fs::write(book_dir.join("Qasas al-Anbiya - Ibn Kathir.txt"), "kisah").unwrap();
+        fs::write(book_dir.join(COVER_FILE), "jpeg").unwrap();
+
+        let books = read_calibre_library(&dir).unwrap();
+        assert_eq!(books.len(), 1);
+        let book = &books[0];
+        assert_eq!(book.title, "Qasas al-Anbiya");
+        assert_eq!(book.authors[0].sort_name, "Kathir, Ibn");
+        assert_eq!(book.tags, vec!["sirah"]);
+        assert_eq!(book.rating, Some(6));
+        assert_eq!(book.cover_path, Some(book_dir.join(COVER_FILE)));
+        // metadata.opf dan cover bukan format buku
+        assert_eq!(
+            book.formats,
+            vec![book_dir.join("Qasas al-Anbiya - Ibn Kathir.txt")]
+        );
+    }
+
+    #[test]
+    fn a_folder_without_calibre_files_is_refused() {
+        let dir = calibre_dir();
+        fs::create_dir_all(dir.join("Random").join("Folder")).unwrap();
+        assert!(!is_calibre_library(&dir));
+        let error = read_calibre_library(&dir).unwrap_err();
+        assert!(
+            error.contains("doesn't look like a Calibre library"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn exported_books_read_back_as_a_calibre_library() {
+        use crate::utils::encode_data_url;
+
+        let dir = calibre_dir();
+        let book = BookMetadata {
+            id: "book-1".to_string(),
+            title: "Ihya: Ulum ad-Din?".to_string(),
+            authors: vec![Author::new("Al-Ghazali", AuthorRole::Author)],
+            series: Some(Series {
+                name: "Ihya".to_string(),
+                index: 1.0,
+            }),
+            tags: vec!["tasawuf".to_string()],
+            rating: Some(9),
+            identifiers: BTreeMap::from([("isbn".to_string(), "9790000000002".to_string())]),
+            file_name: "ihya.txt".to_string(),
+            format: BookFormat::TXT,
+            size: 4,
+            file_data: encode_data_url("text/plain", b"ihya"),
+            blob: None,
+            cover_image: Some(encode_data_url("image/jpeg", b"jpeg")),
+            last_read_position: 0,
+            total_pages: 0,
+            added_date: "2026-01-01T00:00:00+00:00".to_string(),
+        };
+
+        let book_dir = export_book(&book, &dir).unwrap();
+        // Karakter terlarang di nama folder diganti
+        assert_eq!(book_dir, dir.join("Al-Ghazali").join("Ihya_ Ulum ad-Din_"));
+        assert_eq!(fs::read(book_dir.join("ihya.txt")).unwrap(), b"ihya");
+        assert_eq!(fs::read(book_dir.join(COVER_FILE)).unwrap(), b"jpeg");
+
+        let books = read_calibre_library(&dir).unwrap();
+        assert_eq!(
+            books,
+            vec![CalibreBook {
+                title: book.title.clone(),
+                authors: book.authors.clone(),
+                series: book.series.clone(),
+                tags: book.tags.clone(),
+                rating: book.rating,
+                identifiers: BTreeMap::from([
+                    ("isbn".to_string(), "9790000000002".to_string()),
+                    ("uuid".to_string(), "book-1".to_string()),
+                ]),
+                cover_path: Some(book_dir.join(COVER_FILE)),
+                formats: vec![book_dir.join("ihya.txt")],
+            }]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::formats::extract_cover;
+use crate::models::BookFormat;
+use crate::utils::escape_xml;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::ImageFormat;
+use std::io::Cursor;
+use tracing::warn;
+
+/// Ukuran maksimum thumbnail (aspect ratio dipertahankan)
+pub const THUMBNAIL_WIDTH: u32 = 200;
+pub const THUMBNAIL_HEIGHT: u32 = 300;
+
+/// =======================
+/// Thumbnails
+/// =======================
+///
+/// Extract the cover from a book file and downscale it into a small JPEG data URL.
+/// The result is stored in `BookMetadata.cover_image`, so the full-size cover is
+/// only decoded once at import time.
+pub fn cover_thumbnail(format: &BookFormat, bytes: &[u8]) -> Option<String> {
+    let cover = match extract_cover(format, bytes) {
+        Ok(Some(cover)) => cover,
+        Ok(None) => return None,
+        Err(e) => {
+            warn!("Could not extract cover: {}", e);
+            return None;
+        }
+    };
+
+    make_thumbnail(&cover)
+        .inspect_err(|e| warn!("Could not create cover thumbnail: {}", e))
+        .ok()
+}
+
+pub fn make_thumbnail(image_bytes: &[u8]) -> Result<String, String> {
+    let image =
+        image::load_from_memory(image_bytes).map_err(|e| format!("Invalid cover image: {e}"))?;
+
+    let thumbnail = image.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT).to_rgb8();
+
+    let mut jpeg = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut jpeg, ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to encode thumbnail: {e}"))?;
+
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        STANDARD.encode(jpeg.into_inner())
+    ))
+}
+
+/// =======================
+/// Generated Placeholder
+/// =======================
+///
+/// SVG cover for books without one. The background colour is derived from the
+/// title, so the same book always gets the same cover on every device.
+pub fn placeholder_cover(title: &str, author: &str) -> String {
+    let hue = fnv1a(title.as_bytes()) % 360;
+
+    let title_lines: String = wrap_text(title, 16, 5)
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                r#"<text x="100" y="{}" font-size="18" font-weight="bold">{}</text>"#,
+                90 + i * 24,
+                escape_xml(line)
+            )
+        })
+        .collect();
+
+    let author_lines: String = wrap_text(author, 22, 2)
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                r#"<text x="100" y="{}" font-size="13" opacity="0.85">{}</text>"#,
+                250 + i * 18,
+                escape_xml(line)
+            )
+        })
+        .collect();
+
+    let svg = format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 200 300">"#,
+            r#"<rect width="200" height="300" fill="hsl({hue},45%,32%)"/>"#,
+            r#"<rect x="12" y="12" width="176" height="276" fill="none" stroke="hsl({hue},45%,70%)" stroke-width="2"/>"#,
+            r##"<g fill="#fff" font-family="serif" text-anchor="middle">{title}{author}</g>"##,
+            r#"</svg>"#
+        ),
+        w = THUMBNAIL_WIDTH,
+        h = THUMBNAIL_HEIGHT,
+        hue = hue,
+        title = title_lines,
+        author = author_lines,
+    );
+
+    format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg))
+}
+
+/// Stable across builds and platforms, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ u32::from(*b)).wrapping_mul(0x01000193)
+    })
+}
+
+fn wrap_text(text: &str, max_chars: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= max_chars => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            last.push('…');
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,591 @@
+use crate::models::{Annotation, BookMetadata};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// =======================
+/// Clocks
+/// =======================
+///
+/// Satu event = satu `Dot` (device, counter). `VersionVector` mencatat dot
+/// terakhir yang sudah dilihat dari setiap device.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Dot {
+    pub device: String,
+    pub counter: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionVector(BTreeMap<String, u64>);
+
+impl VersionVector {
+    pub fn get(&self, device: &str) -> u64 {
+        self.0.get(device).copied().unwrap_or(0)
+    }
+
+    /// Next event of `device`
+    pub fn tick(&mut self, device: &str) -> Dot {
+        let counter = self.get(device) + 1;
+        self.0.insert(device.to_string(), counter);
+        Dot {
+            device: device.to_string(),
+            counter,
+        }
+    }
+
+    pub fn contains(&self, dot: &Dot) -> bool {
+        self.get(&dot.device) >= dot.counter
+    }
+
+    pub fn merge(&mut self, other: &VersionVector) {
+        for (device, counter) in &other.0 {
+            let entry = self.0.entry(device.clone()).or_insert(0);
+            *entry = (*entry).max(*counter);
+        }
+    }
+}
+
+/// Wall-clock time plus device id. The device id breaks ties, so two
+/// replicas always pick the same winner.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Stamp {
+    pub millis: i64,
+    pub device: String,
+}
+
+impl Stamp {
+    /// A stamp for a local edit that is guaranteed to be newer than `seen`,
+    /// even if this device's clock is behind
+    pub fn next(device: &str, seen: Option<&Stamp>) -> Self {
+        let now = chrono::Utc::now().timestamp_millis();
+        Self {
+            millis: seen.map_or(now, |s| now.max(s.millis + 1)),
+            device: device.to_string(),
+        }
+    }
+}
+
+/// =======================
+/// Registers & Sets
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LwwRegister<T> {
+    pub value: T,
+    pub stamp: Stamp,
+}
+
+impl<T: Clone> LwwRegister<T> {
+    pub fn new(value: T, stamp: Stamp) -> Self {
+        Self { value, stamp }
+    }
+
+    pub fn merge(&mut self, other: &LwwRegister<T>) {
+        if other.stamp > self.stamp {
+            *self = other.clone();
+        }
+    }
+}
+
+/// Observed-remove set (add-wins): a remove only cancels the adds it has
+/// seen, so an add concurrent with a remove survives the merge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrSet<T: Ord> {
+    entries: BTreeMap<T, BTreeSet<Dot>>,
+    context: VersionVector,
+}
+
+impl<T: Ord> Default for OrSet<T> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            context: VersionVector::default(),
+        }
+    }
+}
+
+impl<T: Ord + Clone> OrSet<T> {
+    pub fn insert(&mut self, value: T, device: &str) {
+        let dot = self.context.tick(device);
+        // Dot lama sudah tercakup context, cukup simpan yang baru
+        self.entries.insert(value, BTreeSet::from([dot]));
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.entries.remove(value);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.entries.contains_key(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.keys()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn merge(&mut self, other: &OrSet<T>) {
+        let values: BTreeSet<T> = self
+            .entries
+            .keys()
+            .chain(other.entries.keys())
+            .cloned()
+            .collect();
+        let none = BTreeSet::new();
+
+        for value in values {
+            let ours = self.entries.get(&value).unwrap_or(&none);
+            let theirs = other.entries.get(&value).unwrap_or(&none);
+
+            // Dot yang ada di kedua sisi tetap; dot yang hanya ada di satu sisi
+            // tetap kecuali sisi lain sudah melihatnya (berarti sudah dihapus)
+            let kept: BTreeSet<Dot> = ours
+                .intersection(theirs)
+                .chain(
+                    ours.difference(theirs)
+                        .filter(|d| !other.context.contains(d)),
+                )
+                .chain(
+                    theirs
+                        .difference(ours)
+                        .filter(|d| !self.context.contains(d)),
+                )
+                .cloned()
+                .collect();
+
+            if kept.is_empty() {
+                self.entries.remove(&value);
+            } else {
+                self.entries.insert(value, kept);
+            }
+        }
+
+        self.context.merge(&other.context);
+    }
+}
+
+/// =======================
+/// Reading Position
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionPolicy {
+    /// Where the reader was last, on any device
+    #[default]
+    MostRecent,
+    /// The furthest point reached on any device
+    Furthest,
+}
+
+impl PositionPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionPolicy::MostRecent => "most_recent",
+            PositionPolicy::Furthest => "furthest",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value {
+            "most_recent" => Some(PositionPolicy::MostRecent),
+            "furthest" => Some(PositionPolicy::Furthest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PositionEntry {
+    pub position: usize,
+    pub total_pages: usize,
+    pub stamp: Stamp,
+}
+
+/// Keeps both the most recent and the furthest position, so merging doesn't
+/// depend on the policy; the policy only picks which one to open at.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadingPosition {
+    pub latest: Option<PositionEntry>,
+    pub furthest: Option<PositionEntry>,
+}
+
+impl ReadingPosition {
+    pub fn update(&mut self, position: usize, total_pages: usize, stamp: Stamp) {
+        let entry = PositionEntry {
+            position,
+            total_pages,
+            stamp,
+        };
+        self.merge(&ReadingPosition {
+            latest: Some(entry.clone()),
+            furthest: Some(entry),
+        });
+    }
+
+    pub fn merge(&mut self, other: &ReadingPosition) {
+        self.latest = max_by_key(self.latest.take(), other.latest.clone(), |e| {
+            e.stamp.clone()
+        });
+        self.furthest = max_by_key(self.furthest.take(), other.furthest.clone(), |e| {
+            (e.position, e.stamp.clone())
+        });
+    }
+
+    pub fn resolve(&self, policy: PositionPolicy) -> Option<&PositionEntry> {
+        match policy {
+            PositionPolicy::MostRecent => self.latest.as_ref(),
+            PositionPolicy::Furthest => self.furthest.as_ref(),
+        }
+    }
+
+    pub fn last_stamp(&self) -> Option<&Stamp> {
+        self.latest.as_ref().map(|e| &e.stamp)
+    }
+}
+
+fn max_by_key<K: Ord>(
+    a: Option<PositionEntry>,
+    b: Option<PositionEntry>,
+    key: impl Fn(&PositionEntry) -> K,
+) -> Option<PositionEntry> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if key(&b) > key(&a) { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// =======================
+/// Annotations
+/// =======================
+///
+/// Annotations of one book: membership is an [`OrSet`] of ids and each
+/// annotation's content is a last-writer-wins register. Editing an
+/// annotation re-adds it, so an edit concurrent with a delete keeps it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AnnotationSet {
+    ids: OrSet<String>,
+    values: BTreeMap<String, LwwRegister<Annotation>>,
+}
+
+impl AnnotationSet {
+    /// Add or edit
+    pub fn upsert(&mut self, annotation: Annotation, device: &str) {
+        let stamp = Stamp::next(device, self.values.get(&annotation.id).map(|r| &r.stamp));
+        self.upsert_with_stamp(annotation, stamp);
+    }
+
+    pub fn upsert_with_stamp(&mut self, annotation: Annotation, stamp: Stamp) {
+        self.ids.insert(annotation.id.clone(), &stamp.device);
+        self.values
+            .insert(annotation.id.clone(), LwwRegister::new(annotation, stamp));
+    }
+
+    pub fn remove(&mut self, annotation_id: &str) {
+        self.ids.remove(&annotation_id.to_string());
+        self.values.remove(annotation_id);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Annotation> {
+        self.values.values().map(|r| &r.value)
+    }
+
+    pub fn merge(&mut self, other: &AnnotationSet) {
+        self.ids.merge(&other.ids);
+
+        for (id, register) in &other.values {
+            match self.values.get_mut(id) {
+                Some(ours) => ours.merge(register),
+                None => {
+                    self.values.insert(id.clone(), register.clone());
+                }
+            }
+        }
+        let ids = &self.ids;
+        self.values.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// =======================
+/// Library State
+/// =======================
+///
+/// Per-book state that is edited on several devices
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BookState {
+    pub position: ReadingPosition,
+    pub tags: OrSet<String>,
+    pub annotations: AnnotationSet,
+}
+
+impl BookState {
+    pub fn merge(&mut self, other: &BookState) {
+        self.position.merge(&other.position);
+        self.tags.merge(&other.tags);
+        self.annotations.merge(&other.annotations);
+    }
+}
+
+/// The syncable part of the library, merged conflict-free between devices.
+/// `BookMetadata::tags`, `last_read_position` and `Library::annotations`
+/// are projections of this state.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ReadingState {
+    pub books: BTreeMap<String, BookState>,
+    /// Collection name -> book ids
+    pub collections: BTreeMap<String, OrSet<String>>,
+}
+
+impl ReadingState {
+    /// Build the state for an existing library, as edits made by `device`
+    pub fn seed(device: &str, books: &[BookMetadata], annotations: &[Annotation]) -> Self {
+        let mut state = Self::default();
+        for book in books {
+            state.seed_book(device, book);
+        }
+        for annotation in annotations {
+            state
+                .book_mut(&annotation.book_id)
+                .annotations
+                .upsert(annotation.clone(), device);
+        }
+        state
+    }
+
+    /// Tags and position of a book that is new to this library
+    pub fn seed_book(&mut self, device: &str, book: &BookMetadata) {
+        let state = self.book_mut(&book.id);
+        for tag in &book.tags {
+            state.tags.insert(tag.clone(), device);
+        }
+        if book.last_read_position > 0 {
+            let stamp = Stamp::next(device, state.position.last_stamp());
+            state
+                .position
+                .update(book.last_read_position, book.total_pages, stamp);
+        }
+    }
+
+    pub fn book_mut(&mut self, book_id: &str) -> &mut BookState {
+        self.books.entry(book_id.to_string()).or_default()
+    }
+
+    pub fn collection_mut(&mut self, name: &str) -> &mut OrSet<String> {
+        self.collections.entry(name.to_string()).or_default()
+    }
+
+    pub fn merge(&mut self, other: &ReadingState) {
+        for (book_id, state) in &other.books {
+            self.book_mut(book_id).merge(state);
+        }
+        for (name, members) in &other.collections {
+            self.collection_mut(name).merge(members);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(millis: i64, device: &str) -> Stamp {
+        Stamp {
+            millis,
+            device: device.to_string(),
+        }
+    }
+
+    fn annotation(id: &str, note: &str) -> Annotation {
+        Annotation {
+            id: id.to_string(),
+            book_id: "book".to_string(),
+            position: 10,
+            text: "text".to_string(),
+            note: Some(note.to_string()),
+            created_date: String::new(),
+        }
+    }
+
+    fn tags(set: &OrSet<String>) -> Vec<&str> {
+        set.iter().map(String::as_str).collect()
+    }
+
+    /// Merge both ways and check the replicas converge
+    fn converge<T: Clone + PartialEq + std::fmt::Debug>(
+        a: &T,
+        b: &T,
+        merge: impl Fn(&mut T, &T),
+    ) -> T {
+        let mut ab = a.clone();
+        merge(&mut ab, b);
+        let mut ba = b.clone();
+        merge(&mut ba, a);
+        assert_eq!(ab, ba, "merge is not commutative");
+
+        let mut again = ab.clone();
+        merge(&mut again, b);
+        assert_eq!(again, ab, "merge is not idempotent");
+        ab
+    }
+
+    #[test]
+    fn version_vectors_remember_every_device() {
+        let mut a = VersionVector::default();
+        let first = a.tick("phone");
+        let mut b = a.clone();
+        let laptop = b.tick("laptop");
+        let second = a.tick("phone");
+        assert!(!a.contains(&laptop) && !b.contains(&second));
+
+        a.merge(&b);
+        assert!(a.contains(&first) && a.contains(&second) && a.contains(&laptop));
+        assert_eq!(a.get("phone"), 2);
+        assert_eq!(a.get("tablet"), 0);
+    }
+
+    #[test]
+    fn concurrent_positions_follow_policy() {
+        let mut phone = ReadingPosition::default();
+        let mut laptop = ReadingPosition::default();
+        // Laptop membaca sampai halaman 120, lalu phone membuka halaman 40 belakangan
+        laptop.update(120, 300, stamp(1_000, "laptop"));
+        phone.update(40, 300, stamp(2_000, "phone"));
+
+        let merged = converge(&phone, &laptop, ReadingPosition::merge);
+        assert_eq!(
+            merged.resolve(PositionPolicy::MostRecent).unwrap().position,
+            40
+        );
+        assert_eq!(
+            merged.resolve(PositionPolicy::Furthest).unwrap().position,
+            120
+        );
+    }
+
+    #[test]
+    fn position_ties_break_on_device_id() {
+        let mut a = ReadingPosition::default();
+        let mut b = ReadingPosition::default();
+        a.update(10, 100, stamp(5_000, "a"));
+        b.update(20, 100, stamp(5_000, "b"));
+
+        let merged = converge(&a, &b, ReadingPosition::merge);
+        assert_eq!(
+            merged.resolve(PositionPolicy::MostRecent).unwrap().position,
+            20
+        );
+    }
+
+    #[test]
+    fn local_stamp_is_newer_than_a_skewed_remote_clock() {
+        let future = stamp(i64::MAX / 2, "fast-clock");
+        let next = Stamp::next("slow-clock", Some(&future));
+        assert!(next > future);
+    }
+
+    #[test]
+    fn concurrent_tag_adds_are_unioned() {
+        let mut base = OrSet::default();
+        base.insert("fiqh".to_string(), "a");
+        let mut a = base.clone();
+        let mut b = base.clone();
+        a.insert("hadith".to_string(), "a");
+        b.insert("tafsir".to_string(), "b");
+
+        let merged = converge(&a, &b, OrSet::merge);
+        assert_eq!(tags(&merged), ["fiqh", "hadith", "tafsir"]);
+    }
+
+    #[test]
+    fn observed_tag_removal_wins() {
+        let mut a = OrSet::default();
+        a.insert("draft".to_string(), "a");
+        let mut b = a.clone();
+        b.remove(&"draft".to_string());
+
+        let merged = converge(&a, &b, OrSet::merge);
+        assert!(merged.is_empty());
+    }
+
+    #[test]
+    fn concurrent_add_beats_remove() {
+        let mut a = OrSet::default();
+        a.insert("draft".to_string(), "a");
+        let mut b = a.clone();
+        // a menambah ulang tag yang sama, b menghapusnya, tanpa saling tahu
+        a.insert("draft".to_string(), "a");
+        b.remove(&"draft".to_string());
+
+        let merged = converge(&a, &b, OrSet::merge);
+        assert_eq!(tags(&merged), ["draft"]);
+    }
+
+    #[test]
+    fn concurrent_annotation_edits_pick_the_same_winner() {
+        let mut base = AnnotationSet::default();
+        base.upsert_with_stamp(annotation("n1", "original"), stamp(1_000, "a"));
+        let mut a = base.clone();
+        let mut b = base.clone();
+        a.upsert_with_stamp(annotation("n1", "edited on a"), stamp(2_000, "a"));
+        b.upsert_with_stamp(annotation("n1", "edited on b"), stamp(3_000, "b"));
+
+        let merged = converge(&a, &b, AnnotationSet::merge);
+        let notes: Vec<_> = merged.iter().map(|a| a.note.as_deref()).collect();
+        assert_eq!(notes, [Some("edited on b")]);
+    }
+
+    #[test]
+    fn annotation_edit_concurrent_with_delete_is_kept() {
+        let mut base = AnnotationSet::default();
+        base.upsert_with_stamp(annotation("n1", "original"), stamp(1_000, "a"));
+        let mut a = base.clone();
+        let mut b = base.clone();
+        a.upsert_with_stamp(annotation("n1", "edited"), stamp(2_000, "a"));
+        b.remove("n1");
+
+        let merged = converge(&a, &b, AnnotationSet::merge);
+        let notes: Vec<_> = merged.iter().map(|a| a.note.as_deref()).collect();
+        assert_eq!(notes, [Some("edited")]);
+    }
+
+    #[test]
+    fn annotation_delete_after_sync_is_kept_deleted() {
+        let mut a = AnnotationSet::default();
+        a.upsert_with_stamp(annotation("n1", "note"), stamp(1_000, "a"));
+        a.upsert_with_stamp(annotation("n2", "other"), stamp(1_000, "a"));
+        let mut b = a.clone();
+        b.remove("n1");
+
+        let merged = converge(&a, &b, AnnotationSet::merge);
+        let ids: Vec<_> = merged.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["n2"]);
+    }
+
+    #[test]
+    fn collections_and_books_merge_independently() {
+        let mut a = ReadingState::default();
+        a.collection_mut("Ramadan").insert("b1".to_string(), "a");
+        let mut b = a.clone();
+
+        a.collection_mut("Ramadan").insert("b2".to_string(), "a");
+        a.book_mut("b1").tags.insert("fiqh".to_string(), "a");
+        b.collection_mut("Ramadan").remove(&"b1".to_string());
+        b.collection_mut("Study").insert("b1".to_string(), "b");
+        b.book_mut("b1").position.update(7, 50, stamp(1_000, "b"));
+
+        let merged = converge(&a, &b, ReadingState::merge);
+        assert_eq!(tags(&merged.collections["Ramadan"]), ["b2"]);
+        assert_eq!(tags(&merged.collections["Study"]), ["b1"]);
+        assert_eq!(tags(&merged.books["b1"].tags), ["fiqh"]);
+        assert_eq!(
+            merged.books["b1"]
+                .position
+                .resolve(PositionPolicy::MostRecent)
+                .map(|e| e.position),
+            Some(7)
+        );
+    }
+}
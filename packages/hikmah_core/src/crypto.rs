@@ -0,0 +1,517 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// =======================
+/// Envelope Format
+/// =======================
+///
+/// Semua yang dienkripsi (backup, index & file WebDAV, record sync) memakai
+/// format yang sama:
+///
+/// `MAGIC (4) | VERSION (1) | salt (16) | m_cost, t_cost, p_cost (3 x u32 LE) | nonce (24) | ciphertext + tag`
+///
+/// Header ikut diautentikasi (AAD). Salt & parameter Argon2 ada di header,
+/// jadi passphrase saja sudah cukup untuk membuka data dari perangkat lain.
+const MAGIC: &[u8; 4] = b"BAHE";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+/// Everything before the ciphertext
+pub const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + 12 + NONCE_LEN;
+
+/// =======================
+/// Data Model
+/// =======================
+///
+/// Argon2id cost, the OWASP minimum by default. Kept in every envelope so
+/// it can be raised later without breaking old data.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// KiB
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+/// Upper bounds for parameters read from an envelope. Someone who can write
+/// to the sync target could otherwise make every device that opens the data
+/// spend gigabytes of memory or minutes of CPU before failing.
+const MAX_KDF_PARAMS: KdfParams = KdfParams {
+    // 1 GiB
+    m_cost: 1024 * 1024,
+    t_cost: 16,
+    p_cost: 8,
+};
+
+impl KdfParams {
+    fn check(&self) -> Result<(), CryptoError> {
+        let max = MAX_KDF_PARAMS;
+        if self.m_cost > max.m_cost || self.t_cost > max.t_cost || self.p_cost > max.p_cost {
+            return Err(CryptoError::Kdf(format!(
+                "Argon2 parameters m={}, t={}, p={} are above the limit",
+                self.m_cost, self.t_cost, self.p_cost
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+/// Identifies which passphrase derivation a piece of data needs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyHeader {
+    /// base64
+    pub salt: String,
+    pub params: KdfParams,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// The data isn't encrypted with our format
+    NotEncrypted,
+    /// Encrypted with another passphrase (or before it was changed)
+    OtherKey(KeyHeader),
+    WrongPassphrase,
+    /// Authentication failed: tampered with or truncated
+    Corrupt,
+    Kdf(String),
+}
+
+impl std::fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CryptoError::NotEncrypted => write!(f, "Data is not encrypted"),
+            CryptoError::OtherKey(_) => write!(
+                f,
+                "Data was encrypted with a different passphrase. Enter it under Settings → Encryption."
+            ),
+            CryptoError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            CryptoError::Corrupt => write!(f, "Encrypted data is damaged or was modified"),
+            CryptoError::Kdf(e) => write!(f, "Key derivation failed: {e}"),
+        }
+    }
+}
+
+/// A key derived from the user's passphrase. Only the derived key is kept
+/// on the device; the passphrase itself is never stored.
+///
+/// The key is saved as is in `library.json`, so syncs and backups run
+/// without asking for the passphrase each time. That is the trade-off:
+/// encryption protects what leaves the device (sync server, WebDAV,
+/// backup files), not the device itself. Anyone who can read the library
+/// folder can read the key, just like they can read the books and notes
+/// stored next to it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LibraryKey {
+    pub header: KeyHeader,
+    /// base64
+    key: String,
+}
+
+impl std::fmt::Debug for LibraryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LibraryKey")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+/// =======================
+/// Keys
+/// =======================
+///
+/// Argon2id turns the passphrase into a key; XChaCha20-Poly1305 (random
+/// 24-byte nonce per envelope) encrypts and authenticates.
+impl LibraryKey {
+    /// New key with a fresh random salt
+    pub fn create(passphrase: &str) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; SALT_LEN];
+        random_bytes(&mut salt)?;
+        Self::derive(
+            passphrase,
+            &KeyHeader {
+                salt: STANDARD.encode(salt),
+                params: KdfParams::default(),
+            },
+        )
+    }
+
+    /// Derive the key described by `header`. This doesn't prove the
+    /// passphrase is right; use [`LibraryKey::unlock`] or [`LibraryKey::verify`].
+    pub fn derive(passphrase: &str, header: &KeyHeader) -> Result<Self, CryptoError> {
+        if passphrase.is_empty() {
+            return Err(CryptoError::Kdf("passphrase is empty".to_string()));
+        }
+        header.params.check()?;
+
+        let salt = STANDARD
+            .decode(&header.salt)
+            .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+        let params = Params::new(
+            header.params.m_cost,
+            header.params.t_cost,
+            header.params.p_cost,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+
+        let mut key = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+
+        Ok(Self {
+            header: header.clone(),
+            key: STANDARD.encode(key),
+        })
+    }
+
+    /// Derive the key that `sample` was encrypted with, and check the
+    /// passphrase by decrypting it
+    pub fn unlock(passphrase: &str, sample: &[u8]) -> Result<Self, CryptoError> {
+        let (header, _) = parse_header(sample)?;
+        let key = Self::derive(passphrase, &header)?;
+        match key.decrypt(sample) {
+            Ok(_) => Ok(key),
+            Err(CryptoError::Corrupt) => Err(CryptoError::WrongPassphrase),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Is `passphrase` the one this key was made from?
+    pub fn verify(&self, passphrase: &str) -> Result<(), CryptoError> {
+        let derived = Self::derive(passphrase, &self.header)?;
+        if bool::from(derived.key.as_bytes().ct_eq(self.key.as_bytes())) {
+            Ok(())
+        } else {
+            Err(CryptoError::WrongPassphrase)
+        }
+    }
+
+    /* =========================
+       ENCRYPT / DECRYPT
+    ========================= */
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        random_bytes(&mut nonce)?;
+
+        let mut envelope = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        envelope.extend_from_slice(MAGIC);
+        envelope.push(VERSION);
+        envelope.extend_from_slice(&self.salt_bytes()?);
+        for value in [
+            self.header.params.m_cost,
+            self.header.params.t_cost,
+            self.header.params.p_cost,
+        ] {
+            envelope.extend_from_slice(&value.to_le_bytes());
+        }
+        envelope.extend_from_slice(&nonce);
+
+        let ciphertext = self
+            .cipher()?
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &envelope,
+                },
+            )
+            .map_err(|_| CryptoError::Corrupt)?;
+        envelope.extend_from_slice(&ciphertext);
+        Ok(envelope)
+    }
+
+    pub fn decrypt(&self, envelope: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (header, nonce) = parse_header(envelope)?;
+        if header != self.header {
+            return Err(CryptoError::OtherKey(header));
+        }
+
+        self.cipher()?
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: &envelope[HEADER_LEN..],
+                    aad: &envelope[..HEADER_LEN],
+                },
+            )
+            .map_err(|_| CryptoError::Corrupt)
+    }
+
+    /// Stable id that hides a name from the server (e.g. a collection name)
+    pub fn blind_id(&self, id: &str) -> String {
+        let digest = Sha256::new()
+            .chain_update(self.key.as_bytes())
+            .chain_update(b"\0id\0")
+            .chain_update(id.as_bytes())
+            .finalize();
+        format!("{:x}", digest)[..32].to_string()
+    }
+
+    fn cipher(&self) -> Result<XChaCha20Poly1305, CryptoError> {
+        let key = STANDARD
+            .decode(&self.key)
+            .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+        if key.len() != KEY_LEN {
+            return Err(CryptoError::Kdf(
+                "stored key has the wrong length".to_string(),
+            ));
+        }
+        Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn salt_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        let salt = STANDARD
+            .decode(&self.header.salt)
+            .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+        if salt.len() != SALT_LEN {
+            return Err(CryptoError::Kdf("salt has the wrong length".to_string()));
+        }
+        Ok(salt)
+    }
+}
+
+/// =======================
+/// Library Encryption State
+/// =======================
+///
+/// Stored in the sync state. Backups, WebDAV files and sync records are
+/// encrypted with `key`; reading also tries `previous`, so data written
+/// before a passphrase change stays readable until it's rewritten.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EncryptionState {
+    /// None = sync targets and backups get plaintext
+    pub key: Option<LibraryKey>,
+    /// The key before the last passphrase change
+    pub previous: Option<LibraryKey>,
+    /// base64 envelope from a sync target that no key here could open.
+    /// The next passphrase entered is checked against it (and its salt is
+    /// adopted), so every device ends up with the same key.
+    pub locked_sample: Option<String>,
+}
+
+impl EncryptionState {
+    /// Plaintext is passed through when encryption is off
+    pub fn encrypt(&self, plaintext: Vec<u8>) -> Result<Vec<u8>, CryptoError> {
+        match &self.key {
+            Some(key) => key.encrypt(&plaintext),
+            None => Ok(plaintext),
+        }
+    }
+
+    /// Data that isn't encrypted is passed through as is
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if !is_encrypted(bytes) {
+            return Ok(bytes.to_vec());
+        }
+
+        let header = envelope_header(bytes)?;
+        self.key_for(&header)
+            .ok_or(CryptoError::OtherKey(header))?
+            .decrypt(bytes)
+    }
+
+    /// Plaintext, or encrypted with a key we have
+    pub fn can_open(&self, bytes: &[u8]) -> bool {
+        match envelope_header(bytes) {
+            Ok(header) => self.key_for(&header).is_some(),
+            Err(_) => !is_encrypted(bytes),
+        }
+    }
+
+    fn key_for(&self, header: &KeyHeader) -> Option<&LibraryKey> {
+        [&self.key, &self.previous]
+            .into_iter()
+            .flatten()
+            .find(|k| &k.header == header)
+    }
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Header of an envelope, to know which passphrase setup it needs
+pub fn envelope_header(bytes: &[u8]) -> Result<KeyHeader, CryptoError> {
+    parse_header(bytes).map(|(header, _)| header)
+}
+
+/// Key that logs in to the sync server: Argon2id over the password, salted
+/// with the username so every device derives the same key without storing
+/// a salt. The server only ever sees this, never the password.
+pub fn login_key(username: &str, password: &str) -> Result<String, CryptoError> {
+    let salt = Sha256::new()
+        .chain_update(b"hikmah-sync\0")
+        .chain_update(username.as_bytes())
+        .finalize();
+    let header = KeyHeader {
+        salt: STANDARD.encode(&salt[..SALT_LEN]),
+        params: KdfParams::default(),
+    };
+    let key = LibraryKey::derive(password, &header)?;
+    let bytes = STANDARD
+        .decode(&key.key)
+        .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(KeyHeader, &[u8]), CryptoError> {
+    if !is_encrypted(bytes) {
+        return Err(CryptoError::NotEncrypted);
+    }
+    if bytes.len() < HEADER_LEN || bytes[MAGIC.len()] != VERSION {
+        return Err(CryptoError::Corrupt);
+    }
+
+    let salt_start = MAGIC.len() + 1;
+    let params_start = salt_start + SALT_LEN;
+    let u32_at = |i: usize| {
+        let start = params_start + i * 4;
+        u32::from_le_bytes([
+            bytes[start],
+            bytes[start + 1],
+            bytes[start + 2],
+            bytes[start + 3],
+        ])
+    };
+
+    let header = KeyHeader {
+        salt: STANDARD.encode(&bytes[salt_start..params_start]),
+        params: KdfParams {
+            m_cost: u32_at(0),
+            t_cost: u32_at(1),
+            p_cost: u32_at(2),
+        },
+    };
+    header.params.check()?;
+    Ok((header, &bytes[params_start + 12..HEADER_LEN]))
+}
+
+fn random_bytes(buf: &mut [u8]) -> Result<(), CryptoError> {
+    getrandom::getrandom(buf).map_err(|e| CryptoError::Kdf(format!("no randomness: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap_key(passphrase: &str, salt: u8) -> LibraryKey {
+        // Parameter Argon2 sekecil mungkin supaya test cepat
+        let header = KeyHeader {
+            salt: STANDARD.encode([salt; SALT_LEN]),
+            params: KdfParams {
+                m_cost: 64,
+                t_cost: 1,
+                p_cost: 1,
+            },
+        };
+        LibraryKey::derive(passphrase, &header).unwrap()
+    }
+
+    #[test]
+    fn round_trips_and_rejects_the_wrong_passphrase() {
+        let key = cheap_key("correct horse", 1);
+        let envelope = key.encrypt(b"annotations").unwrap();
+        assert!(is_encrypted(&envelope));
+        assert_eq!(key.decrypt(&envelope).unwrap(), b"annotations");
+
+        assert_eq!(LibraryKey::unlock("correct horse", &envelope).unwrap(), key);
+        assert_eq!(
+            LibraryKey::unlock("battery staple", &envelope),
+            Err(CryptoError::WrongPassphrase)
+        );
+        assert!(key.verify("correct horse").is_ok());
+        assert_eq!(
+            key.verify("battery staple"),
+            Err(CryptoError::WrongPassphrase)
+        );
+    }
+
+    #[test]
+    fn tampering_is_detected() {
+        let key = cheap_key("correct horse", 1);
+        let mut envelope = key.encrypt(b"annotations").unwrap();
+        let last = envelope.len() - 1;
+        envelope[last] ^= 1;
+        assert_eq!(key.decrypt(&envelope), Err(CryptoError::Corrupt));
+
+        // Header juga diautentikasi
+        let mut envelope = key.encrypt(b"annotations").unwrap();
+        envelope[HEADER_LEN - 1] ^= 1;
+        assert_eq!(key.decrypt(&envelope), Err(CryptoError::Corrupt));
+    }
+
+    #[test]
+    fn data_from_before_a_passphrase_change_stays_readable() {
+        let old = cheap_key("correct horse", 1);
+        let new = cheap_key("battery staple", 2);
+        let written_before = old.encrypt(b"old").unwrap();
+
+        let state = EncryptionState {
+            key: Some(new.clone()),
+            previous: Some(old),
+            locked_sample: None,
+        };
+        assert_eq!(state.decrypt(&written_before).unwrap(), b"old");
+        assert!(state.can_open(b"plain zip"));
+
+        let other = cheap_key("correct horse", 3).encrypt(b"x").unwrap();
+        assert!(!state.can_open(&other));
+        assert!(matches!(
+            state.decrypt(&other),
+            Err(CryptoError::OtherKey(_))
+        ));
+        assert_ne!(new.blind_id("Tafsir"), cheap_key("x", 3).blind_id("Tafsir"));
+    }
+
+    #[test]
+    fn expensive_kdf_parameters_are_refused_before_deriving() {
+        let mut envelope = cheap_key("correct horse", 1).encrypt(b"x").unwrap();
+        // m_cost = u32::MAX: tanpa batas ini Argon2 akan mencoba ~4 TiB
+        let m_cost = MAGIC.len() + 1 + SALT_LEN;
+        envelope[m_cost..m_cost + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(
+            LibraryKey::unlock("correct horse", &envelope),
+            Err(CryptoError::Kdf(_))
+        ));
+        assert!(matches!(
+            envelope_header(&envelope),
+            Err(CryptoError::Kdf(_))
+        ));
+
+        let header = KeyHeader {
+            salt: STANDARD.encode([1; SALT_LEN]),
+            params: KdfParams {
+                t_cost: MAX_KDF_PARAMS.t_cost + 1,
+                ..KdfParams::default()
+            },
+        };
+        assert!(matches!(
+            LibraryKey::derive("correct horse", &header),
+            Err(CryptoError::Kdf(_))
+        ));
+        assert!(KdfParams::default().check().is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use dioxus::document::{eval, EvalError};
 use serde::{Deserialize, Serialize};
 
@@ -27,6 +29,12 @@ struct FilePickerOptionsInternal<'a> {
     encoding: Option<DataEncoding>,
 }
 
+#[derive(Debug, Serialize)]
+struct SaveFileInternal<'a> {
+    name: &'a str,
+    data: &'a str,
+}
+
 /// =======================
 /// JavaScript (Unified)
 /// =======================
@@ -76,6 +84,19 @@ input.onchange = async () => {
 input.click();
 "#;
 
+const SAVE_FILE_SCRIPT: &str = r#"
+const file = await dioxus.recv();
+
+const link = document.createElement("a");
+link.href = file.data;
+link.download = file.name;
+document.body.appendChild(link);
+link.click();
+link.remove();
+
+dioxus.send(true);
+"#;
+
 /// =======================
 /// Public API (SINGLE ENTRY)
 /// =======================
@@ -88,10 +109,25 @@ pub async fn select_ebook_files() -> Result<Vec<FileSelection>, EvalError> {
     let accept =
         Some(".epub,.pdf,.txt,application/epub+zip,application/pdf,text/plain".to_string());
 
+    select_files(&accept, true).await // unified: always true
+}
+
+/// Select a single library backup archive (.zip)
+pub async fn select_backup_file() -> Result<Option<FileSelection>, EvalError> {
+    let accept = Some(".zip,.enc,application/zip".to_string());
+
+    let files = select_files(&accept, false).await?;
+    Ok(files.into_iter().next())
+}
+
+async fn select_files(
+    accept: &Option<String>,
+    multiple: bool,
+) -> Result<Vec<FileSelection>, EvalError> {
     let mut eval = eval(SELECT_FILE_SCRIPT);
     eval.send(&FilePickerOptionsInternal {
-        accept: &accept,
-        multiple: true, // unified: always true
+        accept,
+        multiple,
         capture: &None,
         encoding: Some(DataEncoding::DataUrl),
     })?;
@@ -99,6 +135,21 @@ pub async fn select_ebook_files() -> Result<Vec<FileSelection>, EvalError> {
     eval.recv().await
 }
 
+/// =======================
+/// Save / Download
+/// =======================
+///
+/// Hand bytes to the user as a downloaded file (browser download / webview save)
+pub async fn save_file(name: &str, mime_type: &str, bytes: &[u8]) -> Result<(), EvalError> {
+    let mut eval = eval(SAVE_FILE_SCRIPT);
+    eval.send(&SaveFileInternal {
+        name,
+        data: &format!("data:{};base64,{}", mime_type, STANDARD.encode(bytes)),
+    })?;
+
+    eval.recv::<bool>().await.map(|_| ())
+}
+
 /// =======================
 /// Utilities
 /// =======================
@@ -116,3 +167,47 @@ pub fn detect_book_format(mime_type: &str, filename: &str) -> Option<String> {
             .filter(|e| matches!(e.as_str(), "epub" | "pdf" | "txt")),
     }
 }
+
+/// Files to import from dropped or picked paths: folders are searched
+/// recursively (hidden entries skipped), plain files are kept as they are
+/// so unsupported ones still get reported
+#[cfg(not(target_arch = "wasm32"))]
+pub fn book_files(paths: &[std::path::PathBuf]) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_book_files(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn collect_book_files(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        tracing::warn!("Cannot read folder {}", dir.display());
+        return;
+    };
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            !p.file_name()
+                .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        // Symlink ke folder dilewati, supaya tidak berputar-putar di tree besar
+        if path.is_dir() && !path.is_symlink() {
+            collect_book_files(&path, files);
+        } else if (path.extension()).is_some_and(|ext| {
+            crate::models::BookFormat::from_extension(&ext.to_string_lossy()).is_some()
+        }) {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use super::opf::{parse_package, parse_xml};
+use super::{Chapter, ExtractedMetadata};
+use roxmltree::Node;
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use tracing::warn;
+use zip::ZipArchive;
+
+type Archive<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+pub(super) fn extract_metadata(bytes: &[u8]) -> Result<ExtractedMetadata, String> {
+    let mut archive = open(bytes)?;
+    let (_, opf) = read_package(&mut archive)?;
+    let package = parse_package(&opf)?;
+
+    Ok(ExtractedMetadata {
+        title: package.title,
+        authors: package.authors,
+        series: package.series,
+        tags: package.tags,
+    })
+}
+
+/// Number of spine items (reading-order documents)
+pub(super) fn spine_len(bytes: &[u8]) -> Result<usize, String> {
+    let mut archive = open(bytes)?;
+    let (_, opf) = read_package(&mut archive)?;
+    Ok(parse_package(&opf)?.spine_len)
+}
+
+/// Raw bytes of the cover image declared in the OPF manifest
+pub(super) fn extract_cover(bytes: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let mut archive = open(bytes)?;
+    let (opf_path, opf) = read_package(&mut archive)?;
+
+    let Some(href) = parse_package(&opf)?.cover_href else {
+        return Ok(None);
+    };
+
+    read_entry(&mut archive, &resolve_href(&opf_path, &href)).map(Some)
+}
+
+/// Metadata and cover of an EPUB on disk. Only the container, the OPF and
+/// the cover image are read, however large the file is.
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn inspect_file(path: &Path) -> Result<(ExtractedMetadata, Option<Vec<u8>>), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut archive = ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Invalid EPUB archive: {e}"))?;
+    let (opf_path, opf) = read_package(&mut archive)?;
+    let package = parse_package(&opf)?;
+
+    let cover = package.cover_href.and_then(|href| {
+        read_entry(&mut archive, &resolve_href(&opf_path, &href))
+            .inspect_err(|e| warn!("{}", e))
+            .ok()
+    });
+    let metadata = ExtractedMetadata {
+        title: package.title,
+        authors: package.authors,
+        series: package.series,
+        tags: package.tags,
+    };
+    Ok((metadata, cover))
+}
+
+/* =========================
+   CHAPTER TEXT
+========================= */
+
+/// Elements read as one paragraph when they hold no other block inside
+const BLOCKS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "blockquote",
+    "pre",
+    "div",
+    "dt",
+    "dd",
+    "td",
+    "th",
+    "figcaption",
+];
+
+/// Text of every spine document, in reading order
+pub(super) fn chapters(bytes: &[u8]) -> Result<Vec<Chapter>, String> {
+    let mut archive = open(bytes)?;
+    let (opf_path, opf) = read_package(&mut archive)?;
+
+    let mut chapters = Vec::new();
+    for href in spine_hrefs(&opf)? {
+        // Bab yang hilang dilewati, sisanya tetap bisa dibaca
+        match read_entry(&mut archive, &resolve_href(&opf_path, &href)) {
+            Ok(xhtml) => chapters.push(chapter_text(&String::from_utf8_lossy(&xhtml))),
+            Err(e) => warn!("{}", e),
+        }
+    }
+    Ok(chapters)
+}
+
+/// Manifest hrefs of the spine items, in order
+fn spine_hrefs(opf: &str) -> Result<Vec<String>, String> {
+    let doc = parse_xml(opf).map_err(|e| format!("Invalid OPF document: {e}"))?;
+    let manifest: HashMap<&str, &str> = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "item")
+        .filter_map(|n| Some((n.attribute("id")?, n.attribute("href")?)))
+        .collect();
+
+    Ok(doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "itemref")
+        .filter_map(|n| manifest.get(n.attribute("idref")?))
+        .map(|href| href.split('#').next().unwrap_or(href).replace("%20", " "))
+        .collect())
+}
+
+fn chapter_text(xhtml: &str) -> Chapter {
+    // Entity HTML tidak dikenal parser XML tanpa DTD XHTML
+    let xhtml = xhtml.replace("&nbsp;", "&#160;");
+    let Ok(doc) = parse_xml(&xhtml) else {
+        return Chapter {
+            title: None,
+            paragraphs: vec![strip_tags(&xhtml)],
+        };
+    };
+    let Some(body) = doc.descendants().find(|n| n.tag_name().name() == "body") else {
+        return Chapter::default();
+    };
+
+    let mut chapter = Chapter::default();
+    let innermost = body
+        .descendants()
+        .filter(|n| is_block(n) && !n.descendants().skip(1).any(|d| is_block(&d)));
+    for node in innermost {
+        let text = collapse_whitespace(
+            &node
+                .descendants()
+                .filter(|n| n.is_text())
+                .filter(|n| {
+                    !n.ancestors()
+                        .any(|a| matches!(a.tag_name().name(), "script" | "style"))
+                })
+                .filter_map(|n| n.text())
+                .collect::<String>(),
+        );
+        if text.is_empty() {
+            continue;
+        }
+        let heading = matches!(node.tag_name().name(), "h1" | "h2" | "h3");
+        if heading && chapter.title.is_none() && chapter.paragraphs.is_empty() {
+            chapter.title = Some(text);
+        } else {
+            chapter.paragraphs.push(text);
+        }
+    }
+    chapter
+}
+
+fn is_block(node: &Node) -> bool {
+    node.is_element() && BLOCKS.contains(&node.tag_name().name())
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Last resort for documents that aren't well-formed XML
+fn strip_tags(xhtml: &str) -> String {
+    let body = xhtml.split_once("<body").map_or(xhtml, |(_, rest)| rest);
+    let mut text = String::new();
+    let mut in_tag = true;
+    for c in body.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    collapse_whitespace(&text)
+}
+
+/* =========================
+   ARCHIVE HELPERS
+========================= */
+
+fn open(bytes: &[u8]) -> Result<Archive<'_>, String> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid EPUB archive: {e}"))
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|e| format!("Missing {name} in EPUB: {e}"))?;
+    let mut buf = Vec::new();
+    entry
+        .read_to_end(&mut buf)
+        .map_err(|e| format!("Failed to read {name}: {e}"))?;
+    Ok(buf)
+}
+
+/// Returns (path, xml) of the OPF package document referenced by META-INF/container.xml
+fn read_package<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<(String, String), String> {
+    let container = read_entry(archive, "META-INF/container.xml")?;
+    let container = String::from_utf8_lossy(&container);
+    let doc = parse_xml(&container).map_err(|e| format!("Invalid container.xml: {e}"))?;
+
+    let path = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "rootfile")
+        .and_then(|n| n.attribute("full-path"))
+        .ok_or("EPUB has no rootfile")?
+        .to_string();
+
+    let opf = read_entry(archive, &path)?;
+    Ok((path, String::from_utf8_lossy(&opf).into_owned()))
+}
+
+/// Manifest hrefs are relative to the OPF file
+fn resolve_href(opf_path: &str, href: &str) -> String {
+    let mut parts: Vec<&str> = match opf_path.rsplit_once('/') {
+        Some((dir, _)) => dir.split('/').collect(),
+        None => vec![],
+    };
+
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            segment => parts.push(segment),
+        }
+    }
+
+    parts.join("/")
+}
@@ -0,0 +1,135 @@
+use crate::models::{Author, BookFormat, Series};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+mod epub;
+pub(crate) mod opf;
+mod pdf;
+
+/// =======================
+/// Extracted Metadata
+/// =======================
+///
+/// Metadata yang berhasil dibaca dari isi file buku.
+/// Field yang tidak ditemukan dibiarkan kosong, caller yang menentukan fallback.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractedMetadata {
+    pub title: Option<String>,
+    pub authors: Vec<Author>,
+    pub series: Option<Series>,
+    pub tags: Vec<String>,
+}
+
+/// Read title and authors embedded in the book file (OPF for EPUB, Info dict for PDF)
+pub fn extract_metadata(format: &BookFormat, bytes: &[u8]) -> Result<ExtractedMetadata, String> {
+    match format {
+        BookFormat::EPUB => epub::extract_metadata(bytes),
+        BookFormat::PDF => pdf::extract_metadata(bytes),
+        BookFormat::TXT => Ok(ExtractedMetadata::default()),
+    }
+}
+
+/// Raw bytes (JPEG/PNG) of the book's cover, if the file has one
+pub fn extract_cover(format: &BookFormat, bytes: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    match format {
+        BookFormat::EPUB => epub::extract_cover(bytes),
+        BookFormat::PDF => pdf::extract_cover(bytes),
+        BookFormat::TXT => Ok(None),
+    }
+}
+
+/// Metadata and raw cover of a book file on disk, without loading an EPUB
+/// into memory (PDFs are parsed once for both)
+#[cfg(not(target_arch = "wasm32"))]
+pub fn inspect_file(
+    format: &BookFormat,
+    path: &Path,
+) -> Result<(ExtractedMetadata, Option<Vec<u8>>), String> {
+    match format {
+        BookFormat::EPUB => epub::inspect_file(path),
+        BookFormat::PDF => pdf::inspect_file(path),
+        BookFormat::TXT => Ok((ExtractedMetadata::default(), None)),
+    }
+}
+
+/// Number of reading-order documents in an EPUB spine
+pub fn epub_spine_len(bytes: &[u8]) -> Result<usize, String> {
+    epub::spine_len(bytes)
+}
+
+/// =======================
+/// Book Text
+/// =======================
+///
+/// Isi buku per bab sebagai teks biasa, untuk tampilan baca. Markup EPUB
+/// tidak ditampilkan apa adanya (bisa berisi script); yang diambil hanya
+/// judul bab dan paragrafnya.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chapter {
+    pub title: Option<String>,
+    pub paragraphs: Vec<String>,
+}
+
+/// Chapters in reading order: one per spine item for EPUB, a single one for
+/// TXT. PDF pages aren't text, so readers show the file itself instead.
+pub fn book_chapters(format: &BookFormat, bytes: &[u8]) -> Result<Vec<Chapter>, String> {
+    match format {
+        BookFormat::EPUB => epub::chapters(bytes),
+        BookFormat::TXT => Ok(vec![text_chapter(&String::from_utf8_lossy(bytes))]),
+        BookFormat::PDF => Err("PDF books have no text view".to_string()),
+    }
+}
+
+/// Paragraphs are separated by blank lines; line breaks inside one are kept
+/// (puisi, matan bait)
+fn text_chapter(text: &str) -> Chapter {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    Chapter {
+        title: None,
+        paragraphs,
+    }
+}
+
+/// Split author strings like "Al-Nawawi; Ibn Daqiq al-Eid & Ibn Rajab"
+/// into separate names. Commas are kept because they usually mean "Surname, Given".
+fn split_author_list(raw: &str) -> Vec<String> {
+    raw.split([';', '&'])
+        .flat_map(|part| part.split(" and "))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn author_lists_are_split_on_separators_but_not_commas() {
+        assert_eq!(
+            split_author_list("Al-Nawawi; Ibn Daqiq al-Eid & Ibn Rajab and Ibn Hajar"),
+            ["Al-Nawawi", "Ibn Daqiq al-Eid", "Ibn Rajab", "Ibn Hajar"]
+        );
+        assert_eq!(split_author_list("Nawawi, Yahya"), ["Nawawi, Yahya"]);
+        assert_eq!(split_author_list(" ; & "), Vec::<String>::new());
+        // "and" di dalam kata bukan pemisah
+        assert_eq!(
+            split_author_list("Alexander Anderson"),
+            ["Alexander Anderson"]
+        );
+    }
+}
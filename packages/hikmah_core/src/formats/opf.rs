@@ -0,0 +1,566 @@
+use crate::models::{Author, AuthorRole, BookMetadata, Series};
+use crate::utils::escape_xml;
+use roxmltree::{Document, Node, ParsingOptions};
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+const OPF_NS: &str = "http://www.idpf.org/2007/opf";
+
+/// =======================
+/// OPF Package
+/// =======================
+///
+/// Metadata dari OPF package document. Dipakai untuk isi EPUB dan juga
+/// file `metadata.opf` milik Calibre (yang menyimpan series, tags & rating
+/// sebagai `<meta name="calibre:...">`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct OpfPackage {
+    pub title: Option<String>,
+    pub authors: Vec<Author>,
+    pub series: Option<Series>,
+    pub tags: Vec<String>,
+    /// Calibre scale, 0-10
+    pub rating: Option<u8>,
+    /// scheme (lowercase) -> value, e.g. "isbn" -> "9789..."
+    pub identifiers: BTreeMap<String, String>,
+    /// Relative to the OPF file
+    pub cover_href: Option<String>,
+    /// Number of `<itemref>` in the spine (= KOReader's DocFragment count)
+    pub spine_len: usize,
+}
+
+pub(crate) fn parse_package(xml: &str) -> Result<OpfPackage, String> {
+    let doc = parse_xml(xml).map_err(|e| format!("Invalid OPF document: {e}"))?;
+    let cover_href = cover_href(&doc).map(str::to_string);
+    let spine_len = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "itemref")
+        .count();
+
+    let Some(metadata) = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "metadata")
+    else {
+        return Ok(OpfPackage {
+            cover_href,
+            spine_len,
+            ..OpfPackage::default()
+        });
+    };
+
+    let title = metadata
+        .children()
+        .find(|n| n.tag_name().name() == "title")
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+
+    let refinements = collect_refinements(metadata);
+
+    let authors = metadata
+        .children()
+        .filter(|n| matches!(n.tag_name().name(), "creator" | "contributor"))
+        .filter_map(|n| parse_creator(n, &refinements))
+        .collect();
+
+    let tags = metadata
+        .children()
+        .filter(|n| n.tag_name().name() == "subject")
+        .filter_map(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let series = named_meta(metadata, "calibre:series").map(|name| Series {
+        name: name.to_string(),
+        index: named_meta(metadata, "calibre:series_index")
+            .and_then(|i| i.parse().ok())
+            .unwrap_or(1.0),
+    });
+
+    let identifiers = metadata
+        .children()
+        .filter(|n| n.tag_name().name() == "identifier")
+        .filter_map(|n| {
+            let scheme = n.attribute((OPF_NS, "scheme")).or(n.attribute("scheme"))?;
+            let value = n.text()?.trim();
+            (!value.is_empty()).then(|| (scheme.to_lowercase(), value.to_string()))
+        })
+        .collect();
+
+    let rating = named_meta(metadata, "calibre:rating")
+        .and_then(|r| r.parse::<f32>().ok())
+        .map(|r| r.clamp(0.0, 10.0).round() as u8);
+
+    Ok(OpfPackage {
+        title,
+        authors,
+        series,
+        tags,
+        rating,
+        identifiers,
+        cover_href,
+        spine_len,
+    })
+}
+
+/// =======================
+/// OPF Writer
+/// =======================
+///
+/// Calibre-style `metadata.opf` sidecar for a book. Calibre reads series,
+/// rating etc. from `calibre:*` meta tags, so they round-trip through
+/// "Add books" in Calibre and through our own Calibre importer.
+pub(crate) fn write_package(book: &BookMetadata, cover_file: Option<&str>) -> String {
+    let mut metadata = vec![format!(
+        r#"<dc:identifier opf:scheme="uuid" id="uuid_id">{}</dc:identifier>"#,
+        escape_xml(&book.id)
+    )];
+
+    for (scheme, value) in book.identifiers.iter().filter(|(s, _)| *s != "uuid") {
+        metadata.push(format!(
+            r#"<dc:identifier opf:scheme="{}">{}</dc:identifier>"#,
+            escape_xml(&scheme.to_uppercase()),
+            escape_xml(value)
+        ));
+    }
+
+    metadata.push(format!("<dc:title>{}</dc:title>", escape_xml(&book.title)));
+
+    for author in &book.authors {
+        let element = if author.role == AuthorRole::Author {
+            "creator"
+        } else {
+            "contributor"
+        };
+        metadata.push(format!(
+            r#"<dc:{element} opf:file-as="{}" opf:role="{}">{}</dc:{element}>"#,
+            escape_xml(&author.sort_name),
+            author.role.to_marc_relator(),
+            escape_xml(&author.name)
+        ));
+    }
+
+    metadata.push(format!(
+        "<dc:date>{}</dc:date>",
+        escape_xml(&book.added_date)
+    ));
+
+    for tag in &book.tags {
+        metadata.push(format!("<dc:subject>{}</dc:subject>", escape_xml(tag)));
+    }
+
+    if let Some(series) = &book.series {
+        metadata.push(format!(
+            r#"<meta name="calibre:series" content="{}"/>"#,
+            escape_xml(&series.name)
+        ));
+        metadata.push(format!(
+            r#"<meta name="calibre:series_index" content="{}"/>"#,
+            series.index
+        ));
+    }
+
+    if let Some(rating) = book.rating {
+        metadata.push(format!(
+            r#"<meta name="calibre:rating" content="{rating}"/>"#
+        ));
+    }
+
+    let guide = cover_file
+        .map(|href| {
+            format!(
+                r#"  <guide>
+    <reference type="cover" title="Cover" href="{}"/>
+  </guide>
+"#,
+                escape_xml(href)
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xml version='1.0' encoding='utf-8'?>
+<package xmlns="{OPF_NS}" unique-identifier="uuid_id" version="2.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="{OPF_NS}">
+    {}
+  </metadata>
+{guide}</package>
+"#,
+        metadata.join("\n    ")
+    )
+}
+
+/// Some publishers still ship a DOCTYPE in the OPF/NCX files
+pub(crate) fn parse_xml(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    Document::parse_with_options(
+        text,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+}
+
+/// `<meta name="..." content="..."/>` (EPUB2 / Calibre style)
+fn named_meta<'a>(metadata: Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    metadata
+        .children()
+        .find(|n| n.tag_name().name() == "meta" && n.attribute("name") == Some(name))
+        .and_then(|n| n.attribute("content"))
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+}
+
+/* =========================
+   COVER
+========================= */
+
+fn cover_href<'a>(doc: &'a Document) -> Option<&'a str> {
+    let items: Vec<Node> = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "item")
+        .collect();
+
+    // EPUB3: <item properties="cover-image" .../>
+    if let Some(item) = items.iter().find(|n| {
+        n.attribute("properties")
+            .is_some_and(|p| p.split_whitespace().any(|p| p == "cover-image"))
+    }) {
+        return item.attribute("href");
+    }
+
+    // EPUB2: <meta name="cover" content="item-id"/>
+    let cover_id = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "meta" && n.attribute("name") == Some("cover"))
+        .and_then(|n| n.attribute("content"));
+    if let Some(item) = cover_id.and_then(|id| items.iter().find(|n| n.attribute("id") == Some(id)))
+    {
+        return item.attribute("href");
+    }
+
+    // Calibre (dan writer di atas): <guide><reference type="cover" href="..."/>.
+    // Di EPUB referensi ini sering menunjuk halaman XHTML, bukan gambar.
+    let guide_image = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "reference" && n.attribute("type") == Some("cover"))
+        .filter_map(|n| n.attribute("href"))
+        .find(|href| {
+            let href = href.to_lowercase();
+            [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+                .iter()
+                .any(|ext| href.ends_with(ext))
+        });
+    if guide_image.is_some() {
+        return guide_image;
+    }
+
+    // Fallback: gambar yang id/href-nya mengandung "cover"
+    items
+        .iter()
+        .filter(|n| {
+            n.attribute("media-type")
+                .is_some_and(|m| m.starts_with("image/"))
+        })
+        .find(|n| {
+            [n.attribute("id"), n.attribute("href")]
+                .into_iter()
+                .flatten()
+                .any(|v| v.to_lowercase().contains("cover"))
+        })
+        .and_then(|n| n.attribute("href"))
+}
+
+/* =========================
+   CREATORS
+========================= */
+
+#[derive(Default)]
+struct Refinement {
+    role: Option<String>,
+    file_as: Option<String>,
+}
+
+/// EPUB3 puts role and file-as in `<meta refines="#id" property="...">`
+fn collect_refinements<'a>(metadata: Node<'a, 'a>) -> HashMap<&'a str, Refinement> {
+    let mut refinements: HashMap<&str, Refinement> = HashMap::new();
+
+    for meta in metadata
+        .children()
+        .filter(|n| n.tag_name().name() == "meta")
+    {
+        let (Some(target), Some(property), Some(value)) = (
+            meta.attribute("refines"),
+            meta.attribute("property"),
+            meta.text(),
+        ) else {
+            continue;
+        };
+
+        let entry = refinements
+            .entry(target.trim_start_matches('#'))
+            .or_default();
+        match property {
+            "role" => entry.role = Some(value.trim().to_string()),
+            "file-as" => entry.file_as = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    refinements
+}
+
+fn parse_creator(node: Node, refinements: &HashMap<&str, Refinement>) -> Option<Author> {
+    let name = node.text()?.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let refinement = node.attribute("id").and_then(|id| refinements.get(id));
+
+    let role_code = node
+        .attribute((OPF_NS, "role"))
+        .or_else(|| refinement.and_then(|r| r.role.as_deref()));
+
+    let role = match role_code {
+        Some(code) => AuthorRole::from_marc_relator(code)?,
+        // dc:creator tanpa role dianggap penulis, contributor tanpa role diabaikan
+        None if node.tag_name().name() == "creator" => AuthorRole::Author,
+        None => return None,
+    };
+
+    let mut author = Author::new(name, role);
+    if let Some(file_as) = node
+        .attribute((OPF_NS, "file-as"))
+        .or_else(|| refinement.and_then(|r| r.file_as.as_deref()))
+        .filter(|f| !f.trim().is_empty())
+    {
+        author.sort_name = file_as.trim().to_string();
+    }
+
+    Some(author)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_epub3_refinements() {
+        let package = parse_package(
+            r##"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+              <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+                <dc:title> Al-Muwatta </dc:title>
+                <dc:creator id="a1">Malik ibn Anas</dc:creator>
+                <meta refines="#a1" property="role">aut</meta>
+                <meta refines="#a1" property="file-as">Malik, Imam</meta>
+                <dc:contributor id="t1">Aisha Bewley</dc:contributor>
+                <meta refines="#t1" property="role">trl</meta>
+                <dc:contributor>Penerbit tanpa role</dc:contributor>
+              </metadata>
+              <manifest>
+                <item id="img" href="images/front.jpg" media-type="image/jpeg"
+                      properties="cover-image"/>
+              </manifest>
+              <spine><itemref idref="c1"/><itemref idref="c2"/></spine>
+            </package>"##,
+        )
+        .unwrap();
+
+        assert_eq!(package.title.as_deref(), Some("Al-Muwatta"));
+        assert_eq!(package.authors.len(), 2);
+        assert_eq!(package.authors[0].sort_name, "Malik, Imam");
+        assert_eq!(package.authors[1].role, AuthorRole::Translator);
+        assert_eq!(package.cover_href.as_deref(), Some("images/front.jpg"));
+        assert_eq!(package.spine_len, 2);
+    }
+
+    #[test]
+    fn reads_epub2_roles_and_keeps_unknown_ones() {
+        let package = parse_package(
+            r#"<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+              <metadata xmlns:dc="http://purl.org/dc/elements/1.1/"
+                        xmlns:opf="http://www.idpf.org/2007/opf">
+                <dc:creator opf:role="aut" opf:file-as="Nawawi, Yahya">Yahya ibn Sharaf al-Nawawi</dc:creator>
+                <dc:creator>Ibn Daqiq al-Eid</dc:creator>
+                <dc:contributor opf:role="EDT">Nasir al-Din al-Albani</dc:contributor>
+                <dc:contributor opf:role="ill">Siti Aminah</dc:contributor>
+                <dc:contributor opf:role="bkp">calibre (7.0.0)</dc:contributor>
+              </metadata>
+            </package>"#,
+        )
+        .unwrap();
+
+        let authors: Vec<(&str, &str, AuthorRole)> = package
+            .authors
+            .iter()
+            .map(|a| (a.name.as_str(), a.sort_name.as_str(), a.role))
+            .collect();
+        assert_eq!(
+            authors,
+            [
+                (
+                    "Yahya ibn Sharaf al-Nawawi",
+                    "Nawawi, Yahya",
+                    AuthorRole::Author
+                ),
+                ("Ibn Daqiq al-Eid", "al-Eid, Ibn Daqiq", AuthorRole::Author),
+                (
+                    "Nasir al-Din al-Albani",
+                    "al-Albani, Nasir al-Din",
+                    AuthorRole::Editor
+                ),
+                ("Siti Aminah", "Aminah, Siti", AuthorRole::Other),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_epub3_roles_are_kept() {
+        let package = parse_package(
+            r##"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+              <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+                <dc:creator id="c1">Ibn Hajar al-Asqalani</dc:creator>
+                <meta refines="#c1" property="role" scheme="marc:relators">cmm</meta>
+                <dc:contributor id="c2">Siti Aminah</dc:contributor>
+                <meta refines="#c2" property="role" scheme="marc:relators">ill</meta>
+                <dc:contributor id="c3">Sigil</dc:contributor>
+                <meta refines="#c3" property="role" scheme="marc:relators">bkp</meta>
+              </metadata>
+            </package>"##,
+        )
+        .unwrap();
+
+        let roles: Vec<AuthorRole> = package.authors.iter().map(|a| a.role).collect();
+        assert_eq!(roles, [AuthorRole::Commentator, AuthorRole::Other]);
+    }
+
+    #[test]
+    fn reads_calibre_meta_tags() {
+        let package = parse_package(
+            r#"<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+              <metadata xmlns:dc="http://purl.org/dc/elements/1.1/"
+                        xmlns:opf="http://www.idpf.org/2007/opf">
+                <dc:title>Bulugh al-Maram</dc:title>
+                <dc:identifier opf:scheme="ISBN">9786020000000</dc:identifier>
+                <dc:subject>fiqh</dc:subject>
+                <dc:subject> </dc:subject>
+                <meta name="calibre:series" content="Hadis Ahkam"/>
+                <meta name="calibre:series_index" content="1.5"/>
+                <meta name="calibre:rating" content="7.6"/>
+                <meta name="cover" content="cover-img"/>
+              </metadata>
+              <manifest><item id="cover-img" href="cover.jpeg" media-type="image/jpeg"/></manifest>
+            </package>"#,
+        )
+        .unwrap();
+
+        assert_eq!(package.identifiers["isbn"], "9786020000000");
+        assert_eq!(package.tags, vec!["fiqh"]);
+        assert_eq!(
+            package.series,
+            Some(Series {
+                name: "Hadis Ahkam".to_string(),
+                index: 1.5,
+            })
+        );
+        assert_eq!(package.rating, Some(8));
+        assert_eq!(package.cover_href.as_deref(), Some("cover.jpeg"));
+    }
+
+    #[test]
+    fn guide_cover_is_used_only_when_it_is_an_image() {
+        let package = |guide: &str| {
+            parse_package(&format!(
+                r#"<package xmlns="http://www.idpf.org/2007/opf" version="2.0">
+                  <manifest>
+                    <item id="front" href="images/front-cover.png" media-type="image/png"/>
+                  </manifest>
+                  <guide><reference type="cover" href="{guide}"/></guide>
+                </package>"#
+            ))
+            .unwrap()
+            .cover_href
+        };
+        assert_eq!(package("cover.jpg").as_deref(), Some("cover.jpg"));
+        assert_eq!(
+            package("text/cover.xhtml").as_deref(),
+            Some("images/front-cover.png")
+        );
+    }
+
+    #[test]
+    fn a_package_without_metadata_is_empty() {
+        let package = parse_package(r#"<package version="2.0"><spine/></package>"#).unwrap();
+        assert_eq!(package, OpfPackage::default());
+        assert!(parse_package("<package>").is_err());
+    }
+
+    fn exported_book() -> BookMetadata {
+        let mut translator = Author::new("Abdullah Yusuf Ali", AuthorRole::Translator);
+        translator.sort_name = "Ali, Abdullah Yusuf".to_string();
+        BookMetadata {
+            id: "book-1".to_string(),
+            title: "Tafsir <Ibn Kathir> & \"Notes\"".to_string(),
+            authors: vec![Author::new("Ibn Kathir", AuthorRole::Author), translator],
+            series: Some(Series {
+                name: "Tafsir & Ulum".to_string(),
+                index: 3.5,
+            }),
+            tags: vec!["tafsir".to_string(), "qur'an".to_string()],
+            rating: Some(10),
+            identifiers: BTreeMap::from([
+                ("isbn".to_string(), "9780000000001".to_string()),
+                ("google".to_string(), "abc<123>".to_string()),
+            ]),
+            file_name: "tafsir.epub".to_string(),
+            format: crate::models::BookFormat::EPUB,
+            size: 0,
+            file_data: String::new(),
+            blob: None,
+            cover_image: None,
+            last_read_position: 0,
+            total_pages: 0,
+            added_date: "2026-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn written_package_reads_back_the_same() {
+        let book = exported_book();
+        let xml = write_package(&book, Some("cover.jpg"));
+        let package = parse_package(&xml).unwrap();
+
+        assert_eq!(package.title.as_deref(), Some(book.title.as_str()));
+        assert_eq!(package.authors, book.authors);
+        assert_eq!(package.series, book.series);
+        assert_eq!(package.tags, book.tags);
+        assert_eq!(package.rating, book.rating);
+        assert_eq!(package.cover_href.as_deref(), Some("cover.jpg"));
+
+        // Identifier uuid adalah id buku; sisanya dikembalikan apa adanya
+        let mut identifiers = book.identifiers.clone();
+        identifiers.insert("uuid".to_string(), book.id.clone());
+        assert_eq!(package.identifiers, identifiers);
+    }
+
+    #[test]
+    fn written_package_without_extras() {
+        let book = BookMetadata {
+            authors: vec![],
+            series: None,
+            tags: vec![],
+            rating: None,
+            identifiers: BTreeMap::new(),
+            ..exported_book()
+        };
+        let xml = write_package(&book, None);
+        assert!(!xml.contains("calibre:") && !xml.contains("<guide>"));
+
+        let package = parse_package(&xml).unwrap();
+        assert!(package.authors.is_empty() && package.series.is_none());
+        assert_eq!(package.rating, None);
+        assert_eq!(package.cover_href, None);
+    }
+}
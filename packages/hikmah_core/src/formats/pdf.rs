@@ -0,0 +1,167 @@
+use super::{split_author_list, ExtractedMetadata};
+use crate::models::{Author, AuthorRole};
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use lopdf::xobject::PdfImage;
+use lopdf::{decode_text_string, Dictionary, Document};
+use std::io::Cursor;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+pub(super) fn extract_metadata(bytes: &[u8]) -> Result<ExtractedMetadata, String> {
+    let doc = Document::load_mem(bytes).map_err(|e| format!("Invalid PDF: {e}"))?;
+    Ok(metadata(&doc))
+}
+
+/// Metadata and cover of a PDF on disk, parsed once for both
+#[cfg(not(target_arch = "wasm32"))]
+pub(super) fn inspect_file(path: &Path) -> Result<(ExtractedMetadata, Option<Vec<u8>>), String> {
+    let doc = Document::load(path).map_err(|e| format!("Invalid PDF: {e}"))?;
+    Ok((metadata(&doc), cover(&doc)))
+}
+
+fn metadata(doc: &Document) -> ExtractedMetadata {
+    let Some(info) = info_dictionary(doc) else {
+        return ExtractedMetadata::default();
+    };
+
+    let title = info_text(doc, info, b"Title");
+    let authors = info_text(doc, info, b"Author")
+        .map(|raw| {
+            split_author_list(&raw)
+                .iter()
+                .map(|name| Author::new(name, AuthorRole::Author))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    ExtractedMetadata {
+        title,
+        authors,
+        ..ExtractedMetadata::default()
+    }
+}
+
+/// Cover = the largest image drawn on the first page.
+/// Works for scanned books; vector-only first pages return None.
+pub(super) fn extract_cover(bytes: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let doc = Document::load_mem(bytes).map_err(|e| format!("Invalid PDF: {e}"))?;
+    Ok(cover(&doc))
+}
+
+fn cover(doc: &Document) -> Option<Vec<u8>> {
+    let first_page = doc.get_pages().values().next().copied()?;
+    let images = doc.get_page_images(first_page).unwrap_or_default();
+    let image = images.iter().max_by_key(|i| i.width * i.height)?;
+    encode_image(doc, image)
+}
+
+fn encode_image(doc: &Document, image: &PdfImage) -> Option<Vec<u8>> {
+    let filters = image.filters.as_deref().unwrap_or_default();
+
+    // DCTDecode stream sudah berupa file JPEG utuh
+    if filters.iter().any(|f| f == "DCTDecode") {
+        return Some(image.content.to_vec());
+    }
+
+    // Raw 8-bit RGB / grayscale pixels (biasanya FlateDecode)
+    if image.bits_per_component != Some(8) {
+        return None;
+    }
+    let pixels = doc
+        .get_object(image.id)
+        .and_then(|o| o.as_stream())
+        .and_then(|s| s.decompressed_content())
+        .ok()?;
+    let (width, height) = (
+        u32::try_from(image.width).ok()?,
+        u32::try_from(image.height).ok()?,
+    );
+
+    let decoded = match image.color_space.as_deref() {
+        Some("DeviceRGB") => DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, pixels)?),
+        Some("DeviceGray") => DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, pixels)?),
+        _ => return None,
+    };
+
+    let mut png = Cursor::new(Vec::new());
+    decoded.write_to(&mut png, ImageFormat::Png).ok()?;
+    Some(png.into_inner())
+}
+
+fn info_dictionary(doc: &Document) -> Option<&Dictionary> {
+    let info = doc.trailer.get(b"Info").ok()?;
+    let (_, info) = doc.dereference(info).ok()?;
+    info.as_dict().ok()
+}
+
+fn info_text(doc: &Document, info: &Dictionary, key: &[u8]) -> Option<String> {
+    let value = info.get(key).ok()?;
+    let (_, value) = doc.dereference(value).ok()?;
+    decode_text_string(value)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object, StringFormat};
+
+    fn with_info(info: Dictionary) -> Document {
+        let mut doc = Document::with_version("1.5");
+        let info = doc.add_object(info);
+        doc.trailer.set("Info", info);
+        doc
+    }
+
+    #[test]
+    fn authors_are_split_from_the_info_dictionary() {
+        let doc = with_info(dictionary! {
+            "Title" => Object::string_literal(" Riyad as-Salihin "),
+            "Author" => Object::string_literal("Al-Nawawi; Ibn Daqiq al-Eid & Ismail ibn Kathir"),
+        });
+
+        let metadata = metadata(&doc);
+        assert_eq!(metadata.title.as_deref(), Some("Riyad as-Salihin"));
+        let authors: Vec<(&str, &str)> = metadata
+            .authors
+            .iter()
+            .map(|a| (a.name.as_str(), a.sort_name.as_str()))
+            .collect();
+        assert_eq!(
+            authors,
+            [
+                ("Al-Nawawi", "Al-Nawawi"),
+                ("Ibn Daqiq al-Eid", "al-Eid, Ibn Daqiq"),
+                ("Ismail ibn Kathir", "ibn Kathir, Ismail"),
+            ]
+        );
+        assert!(metadata
+            .authors
+            .iter()
+            .all(|a| a.role == AuthorRole::Author));
+    }
+
+    #[test]
+    fn utf16_authors_are_decoded() {
+        // Text string PDF: UTF-16BE dengan BOM
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend("النووي".encode_utf16().flat_map(u16::to_be_bytes));
+        let doc = with_info(dictionary! {
+            "Author" => Object::String(bytes, StringFormat::Hexadecimal),
+            "Title" => Object::string_literal("  "),
+        });
+
+        let metadata = metadata(&doc);
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.authors[0].name, "النووي");
+    }
+
+    #[test]
+    fn a_pdf_without_info_has_no_metadata() {
+        let metadata = metadata(&Document::with_version("1.5"));
+        assert_eq!(metadata.title, None);
+        assert!(metadata.authors.is_empty());
+    }
+}
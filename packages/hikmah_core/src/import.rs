@@ -0,0 +1,686 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::blobs::{blob_name, blob_path, import_file};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::calibre::{is_calibre_library, read_calibre_library, CalibreBook};
+use crate::covers::cover_thumbnail;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::covers::make_thumbnail;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::file_picker::book_files;
+use crate::file_picker::{detect_book_format, FileSelection};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::formats::inspect_file;
+use crate::formats::{extract_metadata, ExtractedMetadata};
+use crate::models::{BookFormat, BookMetadata};
+use crate::utils::decode_data_url;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::watch::{WatchedFile, WatchedItem};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tracing::{info, warn};
+
+const DUPLICATE_BOOK: &str = "This book already exists in your library";
+
+/// =======================
+/// Import Queue
+/// =======================
+///
+/// Semua import (file picker, drag & drop, folder) lewat antrean ini:
+/// decode, hash, baca metadata, dan buat cover dikerjakan di luar jalur UI.
+/// Di desktop pekerjaan itu jalan di thread terpisah; di web (tanpa thread)
+/// satu file diproses per batch, dengan jeda supaya browser sempat
+/// menggambar ulang. Buku baru masuk ke `Library` lewat
+/// [`Library::apply_import_events`](crate::library::Library::apply_import_events).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportItem {
+    /// A file read by the browser, as a data URL
+    File(FileSelection),
+    /// A file, or a folder searched recursively (desktop). A Calibre
+    /// library folder is read through its `metadata.db` instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    Path(PathBuf),
+    /// One book from a Calibre library, imported with Calibre's metadata
+    #[cfg(not(target_arch = "wasm32"))]
+    Calibre(Box<CalibreBook>),
+    /// A new or changed file in a watched folder
+    #[cfg(not(target_arch = "wasm32"))]
+    Watched(WatchedItem),
+}
+
+impl ImportItem {
+    pub fn name(&self) -> String {
+        match self {
+            ImportItem::File(file) => file.name.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ImportItem::Path(path) | ImportItem::Watched(WatchedItem { path, .. }) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            #[cfg(not(target_arch = "wasm32"))]
+            ImportItem::Calibre(book) => book.title.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportEvent {
+    /// The folders were searched; this many files will be imported
+    Found(usize),
+    /// Started on this file
+    Current(String),
+    /// A book ready to add
+    Added {
+        file_name: String,
+        book: Box<BookMetadata>,
+    },
+    /// A file that was skipped. `retry` is set when trying again might
+    /// help: a read or copy error, not a duplicate or an unsupported format.
+    Failed {
+        file_name: String,
+        error: String,
+        retry: Option<ImportItem>,
+    },
+    /// A watched file that changed was stored as the new file of its book
+    #[cfg(not(target_arch = "wasm32"))]
+    Replaced {
+        file_name: String,
+        book_id: String,
+        blob: String,
+        size: u64,
+    },
+    /// Sent after the result of a watched file, to remember it for the next
+    /// rescan; `book_id` is empty when the import failed
+    #[cfg(not(target_arch = "wasm32"))]
+    Watched { path: String, file: WatchedFile },
+    /// Every file was handled, or the import was cancelled
+    Finished,
+}
+
+pub struct ImportQueue {
+    cancel: Arc<AtomicBool>,
+    #[cfg(not(target_arch = "wasm32"))]
+    events: UnboundedReceiver<ImportEvent>,
+    #[cfg(target_arch = "wasm32")]
+    worker: Option<Worker>,
+}
+
+impl ImportQueue {
+    /// Start importing `items`. `known` holds the (file name, size) of the
+    /// books already in the library; `max_size` is the limit for files
+    /// carried as data URLs.
+    pub fn start(items: Vec<ImportItem>, known: HashSet<(String, u64)>, max_size: u64) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            items: items.into(),
+            found: false,
+            known,
+            max_size,
+            hashes: HashMap::new(),
+            cancel: cancel.clone(),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let (sender, events) = unbounded_channel();
+            #[cfg(test)]
+            let test_data_dir = crate::storage::share_test_data_dir();
+            std::thread::spawn(move || {
+                #[cfg(test)]
+                test_data_dir();
+                let mut worker = worker;
+                while worker.step(&mut |event| sender.send(event).is_ok()) {}
+            });
+            Self { cancel, events }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        Self {
+            cancel,
+            worker: Some(worker),
+        }
+    }
+
+    /// Shared with [`ImportProgress`](crate::models::ImportProgress), which cancels through it
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    /// Wait for the next events, taking everything that's already there, so
+    /// the library is saved once per batch instead of once per book.
+    /// `None` once the import is over.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn next_batch(&mut self) -> Option<Vec<ImportEvent>> {
+        let mut batch = vec![self.events.recv().await?];
+        while let Ok(event) = self.events.try_recv() {
+            batch.push(event);
+        }
+        Some(batch)
+    }
+
+    /// Import the next file and return what happened
+    #[cfg(target_arch = "wasm32")]
+    pub async fn next_batch(&mut self) -> Option<Vec<ImportEvent>> {
+        // Beri browser kesempatan menggambar progress bar dulu
+        let mut pause = dioxus::document::eval("setTimeout(() => dioxus.send(true), 0)");
+        let _ = pause.recv::<bool>().await;
+
+        let worker = self.worker.as_mut()?;
+        let mut batch = Vec::new();
+        if !worker.step(&mut |event| {
+            batch.push(event);
+            true
+        }) {
+            self.worker = None;
+        }
+        Some(batch)
+    }
+}
+
+struct Worker {
+    items: VecDeque<ImportItem>,
+    /// `Found` was sent
+    found: bool,
+    known: HashSet<(String, u64)>,
+    max_size: u64,
+    /// SHA-256 of every file read so far, with its name
+    hashes: HashMap<[u8; 32], String>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Worker {
+    /// Take the next step. `emit` returns false once nobody is listening;
+    /// returns false when the import is over.
+    fn step(&mut self, emit: &mut impl FnMut(ImportEvent) -> bool) -> bool {
+        if !self.found {
+            #[cfg(not(target_arch = "wasm32"))]
+            self.expand_folders();
+            self.found = true;
+            info!("Importing {} files", self.items.len());
+            return emit(ImportEvent::Found(self.items.len()));
+        }
+
+        if self.cancel.load(Ordering::Relaxed) && !self.items.is_empty() {
+            info!("Import cancelled");
+            self.items.clear();
+        }
+        let Some(item) = self.items.pop_front() else {
+            emit(ImportEvent::Finished);
+            return false;
+        };
+
+        let file_name = item.name();
+        if !emit(ImportEvent::Current(file_name.clone())) {
+            return false;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let ImportItem::Watched(watched) = &item {
+            let watched = watched.clone();
+            let mut file = watched.file.clone();
+            let event = if file.book_id.is_empty() {
+                self.import_item(item, file_name)
+            } else {
+                Self::replace(&watched, file_name)
+            };
+            file.book_id = match &event {
+                ImportEvent::Added { book, .. } => book.id.clone(),
+                ImportEvent::Replaced { book_id, .. } => book_id.clone(),
+                _ => String::new(),
+            };
+            let path = watched.path.to_string_lossy().into_owned();
+            return emit(event) && emit(ImportEvent::Watched { path, file });
+        }
+
+        // Penerima sudah hilang (aplikasi ditutup): tidak ada gunanya lanjut
+        let event = self.import_item(item, file_name);
+        emit(event)
+    }
+
+    fn import_item(&mut self, item: ImportItem, file_name: String) -> ImportEvent {
+        match self.import(&item) {
+            Ok(book) => ImportEvent::Added {
+                file_name,
+                book: Box::new(book),
+            },
+            Err((error, retry)) => ImportEvent::Failed {
+                file_name,
+                error,
+                retry: retry.then_some(item),
+            },
+        }
+    }
+
+    /// Store a changed watched file under its book's id, leaving the
+    /// book's metadata, tags and reading position alone
+    #[cfg(not(target_arch = "wasm32"))]
+    fn replace(watched: &WatchedItem, file_name: String) -> ImportEvent {
+        let book_id = watched.file.book_id.clone();
+        let stored = check_path(&watched.path, |_, _| false).and_then(|(format, _)| {
+            let blob = blob_name(&book_id, format.to_extension());
+            import_file(&watched.path, &blob).map(|(size, _)| (blob, size))
+        });
+        match stored {
+            Ok((blob, size)) => ImportEvent::Replaced {
+                file_name,
+                book_id,
+                blob,
+                size,
+            },
+            Err(error) => ImportEvent::Failed {
+                file_name,
+                error,
+                retry: Some(ImportItem::Watched(watched.clone())),
+            },
+        }
+    }
+
+    /// Replace folders with the book files in them
+    #[cfg(not(target_arch = "wasm32"))]
+    fn expand_folders(&mut self) {
+        let mut items = VecDeque::new();
+        for item in self.items.drain(..) {
+            match item {
+                ImportItem::Path(path) if is_calibre_library(&path) => {
+                    match read_calibre_library(&path) {
+                        Ok(books) => items.extend(
+                            (books.into_iter()).map(|book| ImportItem::Calibre(Box::new(book))),
+                        ),
+                        Err(e) => {
+                            warn!("Could not read Calibre library {}: {}", path.display(), e);
+                            items.extend(book_files(&[path]).into_iter().map(ImportItem::Path))
+                        }
+                    }
+                }
+                ImportItem::Path(path) => {
+                    items.extend(book_files(&[path]).into_iter().map(ImportItem::Path))
+                }
+                file => items.push_back(file),
+            }
+        }
+        self.items = items;
+    }
+
+    /// Errors say whether trying again might help
+    fn import(&mut self, item: &ImportItem) -> Result<BookMetadata, (String, bool)> {
+        let known = &self.known;
+        let is_duplicate = |name: &str, size: u64| known.contains(&(name.to_string(), size));
+        let prepared = match item {
+            ImportItem::File(file) => {
+                let format =
+                    check_file(file, self.max_size, is_duplicate).map_err(|e| (e, false))?;
+                prepare_file(file.clone(), format).map_err(|e| (e, false))?
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ImportItem::Path(path) | ImportItem::Watched(WatchedItem { path, .. }) => {
+                let (format, size) = check_path(path, is_duplicate).map_err(|e| (e, false))?;
+                prepare_path(path, format, size).map_err(|e| (e, true))?
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            ImportItem::Calibre(calibre) => {
+                let (path, _) = (calibre.preferred_format())
+                    .ok_or(("No EPUB, PDF or TXT file".to_string(), false))?;
+                let (format, size) = check_path(path, is_duplicate).map_err(|e| (e, false))?;
+                let mut prepared = prepare_path(path, format, size).map_err(|e| (e, true))?;
+                calibre.apply_to(&mut prepared.book);
+                prepared
+            }
+        };
+
+        // Isi sama dengan nama berbeda (salinan di dua folder): cukup satu
+        if let Some(hash) = prepared.hash {
+            if let Some(other) = self.hashes.get(&hash) {
+                let error = format!("Same file as {other}");
+                prepared.discard();
+                return Err((error, false));
+            }
+            self.hashes.insert(hash, prepared.book.file_name.clone());
+        }
+
+        let book = prepared.book;
+        self.known.insert((book.file_name.clone(), book.size));
+        Ok(book)
+    }
+}
+
+/* =========================
+   PIPELINE
+========================= */
+
+/// A book ready to be added, with the SHA-256 of its content when it was read
+pub(crate) struct Prepared {
+    pub book: BookMetadata,
+    pub hash: Option<[u8; 32]>,
+}
+
+impl Prepared {
+    /// Drop a book that won't be added, with its copy in the blob store
+    pub fn discard(self) {
+        if let Some(blob) = &self.book.blob {
+            if let Err(e) = crate::blobs::remove_blob(blob) {
+                warn!("{}", e);
+            }
+        }
+    }
+}
+
+/// Format of a picked file about to be added; unknown formats, files over
+/// `max_size` (the import limit from [`crate::preferences::Preferences`])
+/// and duplicates are refused
+pub(crate) fn check_file(
+    file: &FileSelection,
+    max_size: u64,
+    is_duplicate: impl Fn(&str, u64) -> bool,
+) -> Result<BookFormat, String> {
+    check_size(file.size, max_size)?;
+
+    let format_str =
+        detect_book_format(&file.r#type, &file.name).ok_or("Unsupported file format")?;
+    let format = BookFormat::from_extension(&format_str).ok_or("Invalid book format")?;
+    if is_duplicate(&file.name, file.size) {
+        return Err(DUPLICATE_BOOK.to_string());
+    }
+    Ok(format)
+}
+
+pub(crate) fn prepare_file(file: FileSelection, format: BookFormat) -> Result<Prepared, String> {
+    info!("Adding book: {} ({} bytes)", file.name, file.size);
+
+    let bytes = decode_data_url(&file.data)
+        .map_err(|e| warn!("Could not decode {}: {}", file.name, e))
+        .ok();
+    let mut book = describe_book(&file.name, format, file.size, bytes.as_deref());
+    book.file_data = file.data; // Store base64 data directly
+    Ok(Prepared {
+        book,
+        hash: bytes.map(|b| Sha256::digest(b).into()),
+    })
+}
+
+/// Refuse files over the import limit before they are read
+pub fn check_size(size: u64, max_size: u64) -> Result<(), String> {
+    const MB: u64 = 1024 * 1024;
+    if size > max_size {
+        return Err(format!(
+            "File too large: {} MB (max {} MB)",
+            size / MB,
+            max_size / MB
+        ));
+    }
+    Ok(())
+}
+
+/// Format and size of a file on disk about to be added
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn check_path(
+    path: &Path,
+    is_duplicate: impl Fn(&str, u64) -> bool,
+) -> Result<(BookFormat, u64), String> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or("Not a file")?;
+    let format = (path.extension())
+        .and_then(|ext| BookFormat::from_extension(&ext.to_string_lossy()))
+        .ok_or("Unsupported file format")?;
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if is_duplicate(&name, size) {
+        return Err(DUPLICATE_BOOK.to_string());
+    }
+    Ok((format, size))
+}
+
+/// The file is streamed into the blob store, hashed on the way, and its
+/// metadata and cover are read back from the stored copy
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn prepare_path(path: &Path, format: BookFormat, size: u64) -> Result<Prepared, String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    info!("Adding book: {} ({} bytes)", name, size);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let blob = blob_name(&id, format.to_extension());
+    let (size, hash) = import_file(path, &blob)?;
+
+    let (extracted, cover) = blob_path(&blob)
+        .and_then(|stored| inspect_file(&format, &stored))
+        .unwrap_or_else(|e| {
+            warn!("Could not read metadata from {}: {}", name, e);
+            (ExtractedMetadata::default(), None)
+        });
+    let cover_image = cover.and_then(|cover| {
+        make_thumbnail(&cover)
+            .inspect_err(|e| warn!("Could not create cover thumbnail: {}", e))
+            .ok()
+    });
+
+    let mut book = new_book(id, &name, format, size, extracted, cover_image);
+    book.blob = Some(blob);
+    Ok(Prepared {
+        book,
+        hash: Some(hash),
+    })
+}
+
+/// New `BookMetadata` for a file; title, authors and cover come from `bytes`
+/// when given, with the file name as fallback title
+fn describe_book(name: &str, format: BookFormat, size: u64, bytes: Option<&[u8]>) -> BookMetadata {
+    // Baca judul, penulis & cover dari isi file, fallback ke nama file
+    let (extracted, cover_image) = match bytes {
+        Some(bytes) => (
+            extract_metadata(&format, bytes).unwrap_or_else(|e| {
+                warn!("Could not read metadata from {}: {}", name, e);
+                ExtractedMetadata::default()
+            }),
+            cover_thumbnail(&format, bytes),
+        ),
+        None => (ExtractedMetadata::default(), None),
+    };
+    let id = uuid::Uuid::new_v4().to_string();
+    new_book(id, name, format, size, extracted, cover_image)
+}
+
+fn new_book(
+    id: String,
+    name: &str,
+    format: BookFormat,
+    size: u64,
+    extracted: ExtractedMetadata,
+    cover_image: Option<String>,
+) -> BookMetadata {
+    // "Kitab.EPUB" -> "Kitab"
+    let title = extracted.title.unwrap_or_else(|| {
+        name.rsplit_once('.')
+            .filter(|(_, extension)| extension.eq_ignore_ascii_case(format.to_extension()))
+            .map_or(name, |(stem, _)| stem)
+            .to_string()
+    });
+
+    BookMetadata {
+        id,
+        title,
+        authors: extracted.authors,
+        series: extracted.series,
+        tags: extracted.tags,
+        rating: None,
+        identifiers: BTreeMap::new(),
+        file_name: name.to_string(),
+        format,
+        size,
+        file_data: String::new(),
+        blob: None,
+        cover_image,
+        last_read_position: 0,
+        total_pages: 0,
+        added_date: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::Library;
+    use crate::utils::encode_data_url;
+
+    fn text_file(name: &str, text: &str) -> ImportItem {
+        ImportItem::File(FileSelection {
+            name: name.to_string(),
+            r#type: "text/plain".to_string(),
+            size: text.len() as u64,
+            data: encode_data_url("text/plain", text.as_bytes()),
+        })
+    }
+
+    async fn import(library: &mut Library, items: Vec<ImportItem>) {
+        let mut queue = library.start_import(items).unwrap();
+        while let Some(events) = queue.next_batch().await {
+            library.apply_import_events(events).unwrap();
+        }
+    }
+
+    fn results(library: &Library) -> Vec<(&str, Result<(), &str>)> {
+        (library.import_results.iter())
+            .map(|o| {
+                (
+                    o.file_name.as_str(),
+                    o.result.as_ref().map(|_| ()).map_err(String::as_str),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn duplicates_are_skipped_within_a_batch_and_against_the_library() {
+        crate::storage::use_test_data_dir();
+        let mut library = Library::new();
+        import(&mut library, vec![text_file("arbain.txt", "hadis")]).await;
+        assert_eq!(library.books.len(), 1);
+
+        import(
+            &mut library,
+            vec![
+                text_file("arbain.txt", "hadis"),
+                text_file("bulugh.txt", "kitab"),
+                // Isi sama dengan nama lain, lalu nama dan ukuran yang sama
+                text_file("bulugh (copy).txt", "kitab"),
+                text_file("bulugh.txt", "fiqh!"),
+            ],
+        )
+        .await;
+
+        assert_eq!(
+            results(&library),
+            [
+                ("arbain.txt", Err(DUPLICATE_BOOK)),
+                ("bulugh.txt", Ok(())),
+                ("bulugh (copy).txt", Err("Same file as bulugh.txt")),
+                ("bulugh.txt", Err(DUPLICATE_BOOK)),
+            ]
+        );
+        assert_eq!(library.books.len(), 2);
+        // Duplikat tidak perlu dicoba lagi
+        assert!(library.import_retry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn data_urls_over_the_limit_are_refused() {
+        crate::storage::use_test_data_dir();
+        let mut library = Library::new();
+        library
+            .set_app_preferences(crate::preferences::Preferences {
+                max_import_mb: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        let ImportItem::File(mut large) = text_file("besar.txt", "hadis") else {
+            unreachable!();
+        };
+        large.size = 3 * 1024 * 1024;
+
+        import(
+            &mut library,
+            vec![ImportItem::File(large), text_file("kecil.txt", "hadis")],
+        )
+        .await;
+
+        assert_eq!(
+            results(&library),
+            [
+                ("besar.txt", Err("File too large: 3 MB (max 1 MB)")),
+                ("kecil.txt", Ok(())),
+            ]
+        );
+        assert_eq!(library.books.len(), 1);
+    }
+
+    #[test]
+    fn cancelling_stops_before_the_next_file() {
+        crate::storage::use_test_data_dir();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut worker = Worker {
+            items: ["satu.txt", "dua.txt", "tiga.txt"]
+                .map(|name| text_file(name, name))
+                .into(),
+            found: false,
+            known: HashSet::new(),
+            max_size: 1024,
+            hashes: HashMap::new(),
+            cancel: cancel.clone(),
+        };
+        let mut events = Vec::new();
+        let mut step = |worker: &mut Worker| {
+            worker.step(&mut |event| {
+                events.push(event);
+                true
+            })
+        };
+
+        assert!(step(&mut worker));
+        assert!(step(&mut worker));
+        cancel.store(true, Ordering::Relaxed);
+        assert!(!step(&mut worker));
+
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ImportEvent::Found(3),
+                ImportEvent::Current(_),
+                ImportEvent::Added { file_name, .. },
+                ImportEvent::Finished,
+            ] if file_name == "satu.txt"
+        ));
+    }
+
+    #[test]
+    fn file_name_titles_drop_the_extension_in_any_case() {
+        let title = |name: &str, format: BookFormat| {
+            new_book(
+                String::new(),
+                name,
+                format,
+                0,
+                ExtractedMetadata::default(),
+                None,
+            )
+            .title
+        };
+        assert_eq!(title("Kitab.EPUB", BookFormat::EPUB), "Kitab");
+        assert_eq!(
+            title("Riyad as-Salihin.txt", BookFormat::TXT),
+            "Riyad as-Salihin"
+        );
+        assert_eq!(title("Fath al-Bari.Pdf", BookFormat::PDF), "Fath al-Bari");
+        // Ekstensi lain tetap bagian dari judul
+        assert_eq!(title("Catatan.md", BookFormat::TXT), "Catatan.md");
+        assert_eq!(title("Kitab", BookFormat::EPUB), "Kitab");
+    }
+}
@@ -0,0 +1,465 @@
+use crate::formats::epub_spine_len;
+use crate::models::{BookFormat, BookMetadata};
+use md5::{Digest, Md5};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Server publik KOReader; bisa diganti dengan server self-hosted
+pub const DEFAULT_SERVER: &str = "https://sync.koreader.rocks";
+
+const ACCEPT: &str = "application/vnd.koreader.v1+json";
+
+/// =======================
+/// Data Model
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KosyncConfig {
+    pub server_url: String,
+    pub username: String,
+    /// MD5 of the password, as KOReader stores it. The password itself is never kept.
+    pub userkey: String,
+    pub device: String,
+    pub device_id: String,
+}
+
+impl KosyncConfig {
+    pub fn new(server_url: &str, username: &str, password: &str, device: &str) -> Self {
+        Self {
+            server_url: server_url.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            userkey: md5_hex(password.as_bytes()),
+            device: device.to_string(),
+            device_id: uuid::Uuid::new_v4().simple().to_string().to_uppercase(),
+        }
+    }
+}
+
+/// Progress record as exchanged with `/syncs/progress`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KoProgress {
+    pub document: String,
+    /// Page number for paged documents (PDF), XPointer for reflowable ones (EPUB/TXT)
+    pub progress: String,
+    /// 0.0 - 1.0
+    pub percentage: f64,
+    pub device: String,
+    pub device_id: String,
+    /// Set by the server (unix seconds)
+    #[serde(default, skip_serializing)]
+    pub timestamp: Option<i64>,
+}
+
+impl KoProgress {
+    /// Build the progress record for a book from its reading position
+    pub fn for_book(book: &BookMetadata, bytes: &[u8], config: &KosyncConfig) -> Self {
+        let percentage = if book.total_pages > 0 {
+            (book.last_read_position as f64 / book.total_pages as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let progress = match book.format {
+            BookFormat::PDF => book.last_read_position.max(1).to_string(),
+            // KOReader membuka EPUB/TXT lewat crengine: satu DocFragment per item spine.
+            // Kita hanya tahu persentase, jadi tunjuk awal bab yang sesuai.
+            BookFormat::EPUB | BookFormat::TXT => {
+                let fragments = match book.format {
+                    BookFormat::EPUB => epub_spine_len(bytes).unwrap_or(1).max(1),
+                    _ => 1,
+                };
+                let fragment =
+                    ((percentage * fragments as f64).floor() as usize + 1).min(fragments);
+                format!("/body/DocFragment[{fragment}]")
+            }
+        };
+
+        Self {
+            document: document_hash(bytes),
+            progress,
+            percentage,
+            device: config.device.clone(),
+            device_id: config.device_id.clone(),
+            timestamp: None,
+        }
+    }
+
+    /// Map remote progress onto our `last_read_position`.
+    /// None when it can't be mapped (reflowable book with unknown page count).
+    pub fn reading_position(&self, book: &BookMetadata) -> Option<usize> {
+        match book.format {
+            BookFormat::PDF => self.progress.trim().parse().ok(),
+            _ if book.total_pages > 0 => {
+                Some((self.percentage.clamp(0.0, 1.0) * book.total_pages as f64).round() as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// =======================
+/// Document Hash
+/// =======================
+///
+/// KOReader's "partial MD5": MD5 over 1 KiB samples taken at offsets
+/// 0, 1K, 4K, 16K, ... (1024 << 2i for i = -1..=10), stopping at end of file.
+pub fn document_hash(bytes: &[u8]) -> String {
+    const SAMPLE: usize = 1024;
+    let mut md5 = Md5::new();
+
+    for i in -1i32..=10 {
+        // KOReader memakai `bit.lshift(1024, -2)`; LuaJIT memotong jumlah shift
+        // ke 5 bit (-2 jadi 30), hasilnya 0, bukan 256
+        let offset = if i < 0 { 0 } else { SAMPLE << (2 * i) };
+        if offset >= bytes.len() {
+            break;
+        }
+        md5.update(&bytes[offset..(offset + SAMPLE).min(bytes.len())]);
+    }
+
+    format!("{:x}", md5.finalize())
+}
+
+fn md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Md5::digest(bytes))
+}
+
+/// =======================
+/// Client
+/// =======================
+
+#[derive(Debug, Clone)]
+pub struct KosyncClient {
+    config: KosyncConfig,
+    http: Client,
+}
+
+#[derive(Deserialize)]
+struct UserResponse {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct PushResponse {
+    timestamp: i64,
+}
+
+impl KosyncClient {
+    pub fn new(config: KosyncConfig) -> Self {
+        Self {
+            config,
+            http: Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &KosyncConfig {
+        &self.config
+    }
+
+    /// POST /users/create
+    pub async fn register(&self) -> Result<(), String> {
+        let response = self
+            .request(self.http.post(self.url("/users/create")))
+            .json(&serde_json::json!({
+                "username": self.config.username,
+                "password": self.config.userkey,
+            }))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::CREATED | StatusCode::OK => {
+                let user: UserResponse = response.json().await.map_err(network_error)?;
+                info!("Registered kosync user {}", user.username);
+                Ok(())
+            }
+            StatusCode::PAYMENT_REQUIRED => Err("Username is already registered".to_string()),
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// GET /users/auth
+    pub async fn authorize(&self) -> Result<(), String> {
+        let response = self
+            .authed(self.http.get(self.url("/users/auth")))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::OK => Ok(()),
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// PUT /syncs/progress, returns the server timestamp
+    pub async fn push_progress(&self, progress: &KoProgress) -> Result<i64, String> {
+        let response = self
+            .authed(self.http.put(self.url("/syncs/progress")))
+            .json(progress)
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let pushed: PushResponse = response.json().await.map_err(network_error)?;
+                Ok(pushed.timestamp)
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// GET /syncs/progress/:document. The server answers `{}` for unknown documents.
+    pub async fn pull_progress(&self, document: &str) -> Result<Option<KoProgress>, String> {
+        let response = self
+            .authed(
+                self.http
+                    .get(self.url(&format!("/syncs/progress/{document}"))),
+            )
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let body: serde_json::Value = response.json().await.map_err(network_error)?;
+                if body.get("progress").is_none() {
+                    return Ok(None);
+                }
+                serde_json::from_value(body)
+                    .map(Some)
+                    .map_err(|e| format!("Invalid progress from server: {e}"))
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    // ===== BOOK HELPERS =====
+
+    pub async fn push_book(&self, book: &BookMetadata) -> Result<(), String> {
+        let bytes = book.read_file()?;
+        let progress = KoProgress::for_book(book, &bytes, &self.config);
+        self.push_progress(&progress).await?;
+        info!(
+            "Pushed progress of '{}' ({})",
+            book.title, progress.progress
+        );
+        Ok(())
+    }
+
+    /// Fetch the remote reading position of a book, already mapped onto
+    /// `last_read_position`. Progress pushed by this same device is ignored.
+    pub async fn pull_book(&self, book: &BookMetadata) -> Result<Option<usize>, String> {
+        let bytes = book.read_file()?;
+        let Some(progress) = self.pull_progress(&document_hash(&bytes)).await? else {
+            return Ok(None);
+        };
+
+        if progress.device_id == self.config.device_id {
+            return Ok(None);
+        }
+        Ok(progress.reading_position(book))
+    }
+
+    /* =========================
+       HTTP HELPERS
+    ========================= */
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.server_url, path)
+    }
+
+    fn request(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder.header("Accept", ACCEPT)
+    }
+
+    fn authed(&self, builder: RequestBuilder) -> RequestBuilder {
+        self.request(builder)
+            .header("x-auth-user", &self.config.username)
+            .header("x-auth-key", &self.config.userkey)
+    }
+}
+
+fn network_error(e: reqwest::Error) -> String {
+    format!("Sync server error: {e}")
+}
+
+fn status_error(status: StatusCode) -> String {
+    match status {
+        StatusCode::UNAUTHORIZED => "Unauthorized: wrong username or password".to_string(),
+        status => format!("Sync server returned {status}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::{Matcher, Server};
+    use serde_json::json;
+
+    fn config(server: &Server) -> KosyncConfig {
+        KosyncConfig::new(&server.url(), "ahmad", "rahasia", "Hikmah")
+    }
+
+    fn progress(config: &KosyncConfig) -> KoProgress {
+        KoProgress {
+            document: "c43e7af7c64be64ff8765e78ee771294".to_string(),
+            progress: "/body/DocFragment[3]".to_string(),
+            percentage: 0.25,
+            device: config.device.clone(),
+            device_id: config.device_id.clone(),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn small_files_hash_like_a_plain_md5() {
+        // Di bawah 1 KiB KOReader hanya mengambil satu sampel dari offset 0
+        assert_eq!(
+            document_hash(b"The quick brown fox jumps over the lazy dog"),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+    }
+
+    #[test]
+    fn document_hash_matches_koreader() {
+        // Dihitung dengan partialMD5 KOReader (frontend/util.lua)
+        let bytes: Vec<u8> = (0..300_000).map(|i| (i % 251) as u8).collect();
+        assert_eq!(document_hash(&bytes), "c43e7af7c64be64ff8765e78ee771294");
+    }
+
+    #[test]
+    fn userkey_is_the_md5_of_the_password() {
+        let config = KosyncConfig::new("https://sync.example/", "ahmad", "password", "Hikmah");
+        assert_eq!(config.server_url, "https://sync.example");
+        assert_eq!(config.userkey, "5f4dcc3b5aa765d61d8327deb882cf99");
+    }
+
+    #[tokio::test]
+    async fn register_sends_the_userkey_and_reports_taken_names() {
+        let mut server = Server::new_async().await;
+        let config = config(&server);
+        let created = server
+            .mock("POST", "/users/create")
+            .match_header("accept", ACCEPT)
+            .match_body(Matcher::Json(json!({
+                "username": "ahmad",
+                "password": config.userkey,
+            })))
+            .with_status(201)
+            .with_body(r#"{"username":"ahmad"}"#)
+            .create_async()
+            .await;
+
+        let client = KosyncClient::new(config);
+        assert_eq!(client.register().await, Ok(()));
+        created.assert_async().await;
+
+        created.remove_async().await;
+        server
+            .mock("POST", "/users/create")
+            .with_status(402)
+            .with_body(r#"{"code":2002,"message":"Username is already registered."}"#)
+            .create_async()
+            .await;
+        assert_eq!(
+            client.register().await,
+            Err("Username is already registered".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn authorize_sends_the_auth_headers() {
+        let mut server = Server::new_async().await;
+        let config = config(&server);
+        server
+            .mock("GET", "/users/auth")
+            .match_header("x-auth-user", "ahmad")
+            .match_header("x-auth-key", config.userkey.as_str())
+            .with_status(200)
+            .with_body(r#"{"authorized":"OK"}"#)
+            .create_async()
+            .await;
+        assert_eq!(KosyncClient::new(config).authorize().await, Ok(()));
+
+        let wrong = KosyncConfig::new(&server.url(), "ahmad", "salah", "Hikmah");
+        server
+            .mock("GET", "/users/auth")
+            .match_header("x-auth-key", wrong.userkey.as_str())
+            .with_status(401)
+            .create_async()
+            .await;
+        assert_eq!(
+            KosyncClient::new(wrong).authorize().await,
+            Err("Unauthorized: wrong username or password".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn push_returns_the_server_timestamp() {
+        let mut server = Server::new_async().await;
+        let config = config(&server);
+        let progress = progress(&config);
+        let pushed = server
+            .mock("PUT", "/syncs/progress")
+            .match_header("x-auth-user", "ahmad")
+            .match_body(Matcher::Json(json!({
+                "document": progress.document,
+                "progress": "/body/DocFragment[3]",
+                "percentage": 0.25,
+                "device": "Hikmah",
+                "device_id": config.device_id,
+            })))
+            .with_status(200)
+            .with_body(r#"{"document":"c43e7af7c64be64ff8765e78ee771294","timestamp":1700000000}"#)
+            .create_async()
+            .await;
+
+        let client = KosyncClient::new(config);
+        assert_eq!(client.push_progress(&progress).await, Ok(1_700_000_000));
+        pushed.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn pull_reads_progress_and_treats_an_empty_answer_as_none() {
+        let mut server = Server::new_async().await;
+        let config = config(&server);
+        server
+            .mock("GET", "/syncs/progress/c43e7af7c64be64ff8765e78ee771294")
+            .match_header("x-auth-user", "ahmad")
+            .with_status(200)
+            .with_body(
+                json!({
+                    "document": "c43e7af7c64be64ff8765e78ee771294",
+                    "progress": "12",
+                    "percentage": 0.5,
+                    "device": "Kobo",
+                    "device_id": "KOBO1",
+                    "timestamp": 1700000000,
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/syncs/progress/unknown")
+            .with_status(200)
+            .with_body("{}")
+            .create_async()
+            .await;
+
+        let client = KosyncClient::new(config);
+        let pulled = client
+            .pull_progress("c43e7af7c64be64ff8765e78ee771294")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pulled.progress, "12");
+        assert_eq!(pulled.device_id, "KOBO1");
+        assert_eq!(pulled.timestamp, Some(1_700_000_000));
+        assert_eq!(client.pull_progress("unknown").await, Ok(None));
+    }
+}
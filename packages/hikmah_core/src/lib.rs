@@ -1,4 +1,23 @@
+pub mod backup;
+pub mod blobs;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod calibre;
+pub mod covers;
+pub mod crdt;
+pub mod crypto;
 pub mod file_picker;
+pub mod formats;
+pub mod import;
+pub mod kosync;
 pub mod library;
 pub mod models;
+pub mod opds;
+pub mod preferences;
+pub mod reader_settings;
+pub mod storage;
+pub mod sync;
 pub mod utils;
+pub mod watch;
+#[cfg(target_arch = "wasm32")]
+pub mod web_storage;
+pub mod webdav;
@@ -1,118 +1,424 @@
-use crate::file_picker::{detect_book_format, FileSelection};
-use crate::models::{BookFormat, BookMetadata};
-use tracing::{error, info};
+use crate::backup::{
+    commit_staged, discard_staged, export_backup, read_backup, RestoreConflict, RestoreMode,
+    RestoreReport,
+};
+use crate::blobs::remove_blob;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::blobs::{blob_name, blob_path, hash_file, write_blob};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::calibre::export_book;
+use crate::covers::cover_thumbnail;
+use crate::crdt::{PositionPolicy, ReadingState, Stamp};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::crypto::{is_encrypted, HEADER_LEN};
+use crate::crypto::{CryptoError, LibraryKey};
+use crate::file_picker::FileSelection;
+use crate::import::{check_file, prepare_file, ImportEvent, ImportItem, ImportQueue};
+use crate::models::{
+    Annotation, AuthorEntry, AuthoredBook, BookMetadata, Bookmark, ImportOutcome, ImportProgress,
+};
+use crate::opds::{OpdsCatalog, OpdsServerSettings};
+use crate::preferences::{Preferences, SortOrder};
+use crate::reader_settings::{ReaderOverrides, ReaderSettings};
+use crate::storage::{self, StorageError, StoredLibrary, StoredLibraryRef};
+use crate::sync::{
+    now_millis, PullResponse, RecordKind, RemoteChange, SyncConfig, SyncRecord, SyncState,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::decode_data_url;
+#[cfg(target_arch = "wasm32")]
+use crate::utils::encode_data_url;
+use crate::watch::WatchState;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::watch::{FolderScan, WatchReport, WatchedFile, WatchedItem};
+#[cfg(target_arch = "wasm32")]
+use crate::web_storage;
+use crate::webdav::{WebDavConfig, WebDavState};
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(not(target_arch = "wasm32"))]
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+const POSITION_POLICY_KEY: &str = "position_policy";
+const WEBDAV_URL_KEY: &str = "webdav.url";
+const WEBDAV_USERNAME_KEY: &str = "webdav.username";
+const WEBDAV_PASSWORD_KEY: &str = "webdav.password";
+const SYNC_SERVER_URL_KEY: &str = "sync.server_url";
+const SYNC_USERNAME_KEY: &str = "sync.username";
+const SYNC_KEY_KEY: &str = "sync.key";
+const OPDS_CATALOGS_KEY: &str = "opds.catalogs";
+const OPDS_SERVER_ENABLED_KEY: &str = "opds_server.enabled";
+const OPDS_SERVER_PORT_KEY: &str = "opds_server.port";
+const READER_SETTINGS_KEY: &str = "reader.settings";
+const READER_OVERRIDES_KEY: &str = "reader.books";
+const APP_PREFERENCES_KEY: &str = "app.preferences";
+
+const ENCRYPTION_LOCKED: &str = "Data on the sync target is encrypted with a passphrase this device doesn't know yet. Enter it under Settings → Encryption.";
 
 #[derive(Debug, Clone)]
 pub struct Library {
     pub books: Vec<BookMetadata>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<Bookmark>,
+    pub preferences: BTreeMap<String, String>,
+    pub sync: SyncState,
+    /// Source of truth for tags, positions, annotations and collections;
+    /// the matching fields above are kept in step with it
+    pub reading_state: ReadingState,
+    /// Folders imported from automatically (desktop) and what was found in them
+    pub watch: WatchState,
+    /// None = tidak disimpan (tidak ada data dir, atau file gagal dibaca)
+    storage_path: Option<PathBuf>,
     pub err: Option<String>,
+    /// Per-file results of the last drop or folder import, shown until dismissed
+    pub import_results: Vec<ImportOutcome>,
+    /// Set while an import runs in the background
+    pub import_progress: Option<ImportProgress>,
+    /// Files from the last import worth trying again
+    pub import_retry: Vec<ImportItem>,
 }
 
 impl Library {
+    /// The library of the only process using it (mobile, tests). On desktop
+    /// several processes share the file; use [`Library::open`] there.
     pub fn new() -> Self {
+        Self::load(true)
+    }
+
+    /// Open the library for a process that shares it (desktop app, CLI).
+    /// With the lock it's read and written as usual. Without it nothing is
+    /// ever written, not even an upgrade of an older library file; the
+    /// upgrade only happens in memory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open(lock: Option<&storage::LibraryLock>) -> Self {
+        let mut lib = Self::load(lock.is_some());
+        if lock.is_none() {
+            lib.storage_path = None;
+        }
+        lib
+    }
+
+    fn load(persist_upgrade: bool) -> Self {
         let mut lib = Self {
             books: vec![],
+            annotations: vec![],
+            bookmarks: vec![],
+            preferences: BTreeMap::new(),
+            sync: SyncState::default(),
+            reading_state: ReadingState::default(),
+            watch: WatchState::default(),
+            storage_path: storage::library_path(),
             err: None,
+            import_results: vec![],
+            import_progress: None,
+            import_retry: vec![],
         };
 
-        // Load library dari localStorage/browser storage
-        if let Err(e) = lib.load_from_browser() {
+        // Load library dari disk (dengan migrasi skema jika perlu)
+        if let Err(e) = lib.load_from_storage(persist_upgrade) {
             error!("Failed to load library: {}", e);
+            // Jangan sampai file lama tertimpa library kosong
+            lib.storage_path = None;
+            lib.err = Some(format!(
+                "{e}. Your library file was left untouched; changes made now won't be saved."
+            ));
+        }
+
+        if lib.sync.device_id.is_empty() {
+            lib.sync.device_id = uuid::Uuid::new_v4().to_string();
         }
 
         lib
     }
 
-    // ===== BROWSER STORAGE (IndexedDB/localStorage) =====
+    // ===== STORAGE =====
 
-    fn load_from_browser(&mut self) -> Result<(), String> {
-        // Try to load from browser storage
-        // For now, just start empty - browser storage akan di-implement via JavaScript
-        info!("Library initialized (browser storage not yet implemented)");
+    fn load_from_storage(&mut self, persist_upgrade: bool) -> Result<(), StorageError> {
+        let Some(path) = &self.storage_path else {
+            info!("Library initialized without persistent storage");
+            return Ok(());
+        };
+
+        if let Some(stored) = storage::load_library(path, persist_upgrade)? {
+            info!(
+                "Loaded {} books from {}",
+                stored.books.len(),
+                path.display()
+            );
+            self.apply_stored(stored);
+        }
         Ok(())
     }
 
-    fn save_to_browser(&self) -> Result<(), String> {
-        // Save to browser storage
-        // Will be implemented via JavaScript eval
-        info!("Saving library to browser storage");
-        Ok(())
+    fn apply_stored(&mut self, stored: StoredLibrary) {
+        self.books = stored.books;
+        self.annotations = stored.annotations;
+        self.bookmarks = stored.bookmarks;
+        self.preferences = stored.preferences;
+        self.sync = stored.sync;
+        self.reading_state = stored.reading_state;
+        self.watch = stored.watch;
+        if self.sync.device_id.is_empty() {
+            self.sync.device_id = uuid::Uuid::new_v4().to_string();
+        }
     }
 
-    // ===== BOOK MANAGEMENT =====
+    /// Web: the library saved in this browser (IndexedDB). Browsers only
+    /// offer async storage, so the web app starts from [`Library::new`] and
+    /// swaps this in once loaded; nothing is written before that, so an
+    /// empty library can't replace the saved one.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn load_from_browser() -> Self {
+        let mut lib = Self::new();
 
-    pub fn add_book_from_file(&mut self, file: FileSelection) -> Result<(), String> {
-        info!("Adding book: {} ({} bytes)", file.name, file.size);
-
-        // Validate file size (max 50MB untuk avoid memory issues)
-        const MAX_SIZE: u64 = 50 * 1024 * 1024;
-        if file.size > MAX_SIZE {
-            return Err(format!(
-                "File too large: {} MB (max 50 MB)",
-                file.size / (1024 * 1024)
-            ));
+        match web_storage::load_library().await {
+            Ok(stored) => {
+                if let Some(stored) = stored {
+                    lib.apply_stored(stored);
+                }
+                // Di web path ini hanya penanda bahwa library boleh disimpan
+                lib.storage_path = Some(PathBuf::from(web_storage::LIBRARY_KEY));
+            }
+            Err(e) => {
+                error!("Failed to load library: {}", e);
+                lib.err = Some(format!(
+                    "{e}. Your saved library was left untouched; changes made now won't be saved."
+                ));
+            }
         }
 
-        // Detect format
-        let format_str =
-            detect_book_format(&file.r#type, &file.name).ok_or("Unsupported file format")?;
+        lib
+    }
 
-        let format = BookFormat::from_extension(&format_str).ok_or("Invalid book format")?;
+    /// Keep the library in memory but stop writing it, e.g. when another
+    /// process holds the library lock
+    pub fn set_read_only(&mut self, reason: &str) {
+        self.storage_path = None;
+        self.err = Some(format!("{reason}. Changes made now won't be saved."));
+    }
 
-        // Extract title from filename
-        let title = file
-            .name
-            .strip_suffix(&format!(".{}", format_str))
-            .unwrap_or(&file.name)
-            .to_string();
+    fn save_to_storage(&self) -> Result<(), String> {
+        let Some(path) = &self.storage_path else {
+            return Ok(());
+        };
 
-        // Check if book already exists
-        if self
-            .books
-            .iter()
-            .any(|b| b.file_name == file.name && b.size == file.size)
+        let library = StoredLibraryRef {
+            books: &self.books,
+            annotations: &self.annotations,
+            bookmarks: &self.bookmarks,
+            preferences: &self.preferences,
+            sync: &self.sync,
+            reading_state: &self.reading_state,
+            watch: &self.watch,
+        };
+        #[cfg(target_arch = "wasm32")]
         {
-            return Err("This book already exists in your library".to_string());
+            let _ = path;
+            web_storage::save_library(&library).map_err(|e| e.to_string())
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        storage::save_library(path, &library).map_err(|e| e.to_string())
+    }
 
-        // Create metadata
-        let metadata = BookMetadata {
-            id: uuid::Uuid::new_v4().to_string(),
-            title,
-            author: "Unknown Author".to_string(),
-            file_name: file.name,
-            format,
-            size: file.size,
-            file_data: file.data, // Store base64 data directly
-            cover_image: None,
-            last_read_position: 0,
-            total_pages: 0,
-            added_date: chrono::Utc::now().to_rfc3339(),
-        };
+    // ===== BOOK MANAGEMENT =====
+
+    pub fn add_book_from_file(&mut self, file: FileSelection) -> Result<(), String> {
+        let metadata = self.build_book(file)?;
 
+        self.adopt_book(&metadata);
         self.books.push(metadata);
-        self.save_to_browser()?;
+        self.save_to_storage()?;
 
         info!("Book added successfully!");
         Ok(())
     }
 
-    pub fn add_multiple_books(&mut self, files: Vec<FileSelection>) -> Vec<String> {
-        let mut errors = Vec::new();
+    /// Validate a picked file and turn it into `BookMetadata` without adding it yet
+    fn build_book(&self, file: FileSelection) -> Result<BookMetadata, String> {
+        let max_size = self.app_preferences().max_import_bytes();
+        let format = check_file(&file, max_size, |name, size| {
+            self.find_duplicate(name, size).is_some()
+        })?;
+        prepare_file(file, format).map(|prepared| prepared.book)
+    }
 
-        for file in files {
-            let file_name = file.name.clone();
-            if let Err(e) = self.add_book_from_file(file) {
-                errors.push(format!("{}: {}", file_name, e));
+    /// Move books still kept as data URLs (imported before the blob store,
+    /// or pulled from the sync server) into the blob store, so they can be
+    /// read from disk like the rest. Returns how many were moved.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn move_files_to_blob_store(&mut self) -> Result<usize, String> {
+        let mut moved = 0;
+        for book in self.books.iter_mut() {
+            if book.blob.is_some() || book.file_data.is_empty() {
+                continue;
             }
+            let blob = blob_name(&book.id, book.format.to_extension());
+            write_blob(&blob, &decode_data_url(&book.file_data)?)?;
+            book.blob = Some(blob);
+            book.file_data = String::new();
+            moved += 1;
         }
 
-        errors
+        if moved > 0 {
+            self.save_to_storage()?;
+            info!("Moved {} books into the blob store", moved);
+        }
+        Ok(moved)
+    }
+
+    /// Books a file opened from outside the app (command line, the OS
+    /// "Open with" menu) may already be: the ones of the same size that
+    /// have a file. Compare them with [`find_same_file`] off the UI thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn books_with_size(&self, size: u64) -> Vec<BookMetadata> {
+        (self.books.iter())
+            .filter(|b| b.size == size && b.has_file())
+            .cloned()
+            .collect()
+    }
+
+    /// Import picked or dropped files, and folders from disk (searched
+    /// recursively for EPUB, PDF and TXT files), off the UI path. Feed the
+    /// queue's events to [`Library::apply_import_events`] while
+    /// [`Library::import_progress`] shows how far it got.
+    pub fn start_import(&mut self, items: Vec<ImportItem>) -> Result<ImportQueue, String> {
+        if self.import_progress.is_some() {
+            return Err("Another import is still running".to_string());
+        }
+
+        let known = (self.books.iter())
+            .map(|b| (b.file_name.clone(), b.size))
+            .collect();
+        let max_size = self.app_preferences().max_import_bytes();
+        let queue = ImportQueue::start(items, known, max_size);
+        self.import_progress = Some(ImportProgress::new(queue.cancel_flag()));
+        self.import_results.clear();
+        self.import_retry.clear();
+        Ok(queue)
+    }
+
+    /// Import the files that failed last time for a reason that may have passed
+    pub fn retry_failed_imports(&mut self) -> Result<ImportQueue, String> {
+        let items = std::mem::take(&mut self.import_retry);
+        self.start_import(items)
+    }
+
+    /// Add the books an [`ImportQueue`] has prepared so far. Once the import
+    /// has finished, the per-file results move to `import_results`.
+    pub fn apply_import_events(&mut self, events: Vec<ImportEvent>) -> Result<(), String> {
+        let mut changed = 0;
+        for event in events {
+            let progress = self.import_progress.as_mut();
+            match event {
+                ImportEvent::Found(total) => {
+                    if let Some(progress) = progress {
+                        progress.total = total;
+                    }
+                }
+                ImportEvent::Current(file_name) => {
+                    if let Some(progress) = progress {
+                        progress.current = Some(file_name);
+                    }
+                }
+                ImportEvent::Added { file_name, book } => {
+                    let result = self.add_imported_book(*book);
+                    if result.is_ok() {
+                        changed += 1;
+                    }
+                    self.record_import(ImportOutcome { file_name, result });
+                }
+                ImportEvent::Failed {
+                    file_name,
+                    error,
+                    retry,
+                } => {
+                    self.import_retry.extend(retry);
+                    self.record_import(ImportOutcome {
+                        file_name,
+                        result: Err(error),
+                    });
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                ImportEvent::Replaced {
+                    file_name,
+                    book_id,
+                    blob,
+                    size,
+                } => {
+                    let result = self.replace_book_file(&book_id, blob, size);
+                    if result.is_ok() {
+                        changed += 1;
+                    }
+                    self.record_import(ImportOutcome { file_name, result });
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                ImportEvent::Watched { path, mut file } => {
+                    // Impor gagal (misalnya duplikat), atau bukunya tidak jadi ditambahkan
+                    if self.get_book(&file.book_id).is_none() {
+                        let name = Path::new(&path).file_name().map(|n| n.to_string_lossy());
+                        file.book_id = (name.and_then(|n| self.find_duplicate(&n, file.size)))
+                            .map(|b| b.id.clone())
+                            .unwrap_or_default();
+                    }
+                    self.watch.files.insert(path, file);
+                    changed += 1;
+                }
+                ImportEvent::Finished => {
+                    if let Some(progress) = self.import_progress.take() {
+                        info!(
+                            "Import finished: {} of {} files",
+                            progress.done, progress.total
+                        );
+                        self.import_results = progress.outcomes;
+                    }
+                }
+            }
+        }
+
+        if changed > 0 {
+            self.save_to_storage()?;
+        }
+        Ok(())
+    }
+
+    fn add_imported_book(&mut self, book: BookMetadata) -> Result<String, String> {
+        // Bisa saja ditambahkan lewat jalan lain selama import berjalan
+        if self.find_duplicate(&book.file_name, book.size).is_some() {
+            if let Some(blob) = &book.blob {
+                let _ = remove_blob(blob);
+            }
+            return Err("This book already exists in your library".to_string());
+        }
+        let title = book.title.clone();
+        self.adopt_book(&book);
+        self.books.push(book);
+        Ok(title)
+    }
+
+    fn record_import(&mut self, outcome: ImportOutcome) {
+        if let Some(progress) = self.import_progress.as_mut() {
+            progress.done += 1;
+            progress.outcomes.push(outcome);
+        }
+    }
+
+    /// Buku dianggap sama jika nama file dan ukurannya sama
+    pub fn find_duplicate(&self, file_name: &str, size: u64) -> Option<&BookMetadata> {
+        self.books
+            .iter()
+            .find(|b| b.file_name == file_name && b.size == size)
     }
 
     pub fn remove_book(&mut self, book_id: &str) -> Result<(), String> {
-        self.books.retain(|b| b.id != book_id);
-        self.save_to_browser()?;
+        self.forget_book(book_id);
+        self.save_to_storage()?;
 
         info!("Book removed successfully");
         Ok(())
@@ -123,24 +429,95 @@ impl Library {
         book_id: &str,
         position: usize,
     ) -> Result<(), String> {
+        let device = self.sync.device_id.clone();
         if let Some(book) = self.books.iter_mut().find(|b| b.id == book_id) {
             book.last_read_position = position;
-            self.save_to_browser()?;
+
+            let state = &mut self.reading_state.book_mut(book_id).position;
+            let stamp = Stamp::next(&device, state.last_stamp());
+            state.update(position, book.total_pages, stamp);
+            self.sync.track(RecordKind::Position, book_id);
+            self.save_to_storage()?;
         }
         Ok(())
     }
 
+    /// Page count found when a reader opens the book (chapters for EPUB & TXT)
+    pub fn set_total_pages(&mut self, book_id: &str, total_pages: usize) -> Result<(), String> {
+        match self.books.iter_mut().find(|b| b.id == book_id) {
+            Some(book) if book.total_pages != total_pages => {
+                book.total_pages = total_pages;
+                self.save_to_storage()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The started book whose position changed most recently, to continue reading
+    pub fn last_read_book(&self) -> Option<&BookMetadata> {
+        (self.books.iter())
+            .filter(|b| b.last_read_position > 0)
+            .max_by_key(|b| {
+                (self.reading_state.books.get(&b.id))
+                    .and_then(|state| state.position.last_stamp())
+                    .map_or(0, |stamp| stamp.millis)
+            })
+    }
+
+    /// Put `books` (all or some of this library's) in the given order
+    pub fn sort_books(&self, books: &mut [&BookMetadata], order: SortOrder) {
+        let title = |b: &BookMetadata| b.title.to_lowercase();
+        match order {
+            SortOrder::Title => books.sort_by_cached_key(|b| title(b)),
+            SortOrder::Author => books.sort_by_cached_key(|b| {
+                let author = (b.authors.first()).map(|a| a.sort_name.to_lowercase());
+                // Tanpa penulis di akhir
+                (author.is_none(), author, title(b))
+            }),
+            SortOrder::Added => books.sort_by(|a, b| b.added_date.cmp(&a.added_date)),
+            SortOrder::LastRead => books.sort_by_cached_key(|b| {
+                let read = (self.reading_state.books.get(&b.id))
+                    .filter(|_| b.last_read_position > 0)
+                    .and_then(|state| state.position.last_stamp())
+                    .map_or(0, |stamp| stamp.millis);
+                std::cmp::Reverse(read)
+            }),
+        }
+    }
+
     pub fn search_books(&self, query: &str) -> Vec<&BookMetadata> {
-        let query_lower = query.to_lowercase();
         self.books
             .iter()
-            .filter(|book| {
-                book.title.to_lowercase().contains(&query_lower)
-                    || book.author.to_lowercase().contains(&query_lower)
-            })
+            .filter(|book| book.matches(query))
             .collect()
     }
 
+    /// Every author (and editor, translator, sharih) with the books they worked on,
+    /// sorted by sort name
+    pub fn authors_index(&self) -> Vec<AuthorEntry> {
+        let mut index: BTreeMap<String, AuthorEntry> = BTreeMap::new();
+
+        for book in &self.books {
+            for author in &book.authors {
+                let entry = index
+                    .entry(author.sort_name.to_lowercase())
+                    .or_insert_with(|| AuthorEntry {
+                        name: author.name.clone(),
+                        sort_name: author.sort_name.clone(),
+                        books: vec![],
+                    });
+
+                entry.books.push(AuthoredBook {
+                    book_id: book.id.clone(),
+                    title: book.title.clone(),
+                    role: author.role,
+                });
+            }
+        }
+
+        index.into_values().collect()
+    }
+
     pub fn get_book(&self, book_id: &str) -> Option<&BookMetadata> {
         self.books.iter().find(|b| b.id == book_id)
     }
@@ -149,12 +526,1446 @@ impl Library {
         self.err = None;
     }
 
+    pub fn clear_import_results(&mut self) {
+        self.import_results.clear();
+        self.import_retry.clear();
+    }
+
     pub fn clear_library(&mut self) -> Result<(), String> {
-        self.books.clear();
-        self.save_to_browser()?;
+        let ids: Vec<String> = self.books.iter().map(|b| b.id.clone()).collect();
+        for id in ids {
+            self.forget_book(&id);
+        }
+        self.save_to_storage()?;
         info!("Library cleared");
         Ok(())
     }
+
+    /// Start tracking a book that is new to this library (added, imported or restored)
+    fn adopt_book(&mut self, book: &BookMetadata) {
+        self.reading_state.seed_book(&self.sync.device_id, book);
+        self.sync.track(RecordKind::Book, &book.id);
+        self.sync.track(RecordKind::Position, &book.id);
+        self.sync.track(RecordKind::Tags, &book.id);
+    }
+
+    /// Remove a book with its annotations, bookmarks and collection entries,
+    /// recording every deletion for the next sync
+    fn forget_book(&mut self, book_id: &str) {
+        if let Some(blob) = self.get_book(book_id).and_then(|b| b.blob.clone()) {
+            if let Err(e) = remove_blob(&blob) {
+                warn!("{}", e);
+            }
+        }
+        self.books.retain(|b| b.id != book_id);
+        self.annotations.retain(|a| a.book_id != book_id);
+        self.reading_state.books.remove(book_id);
+        for kind in [
+            RecordKind::Book,
+            RecordKind::Position,
+            RecordKind::Tags,
+            RecordKind::Annotations,
+        ] {
+            self.sync.track(kind, book_id);
+        }
+
+        for bookmark in self.bookmarks.iter().filter(|b| b.book_id == book_id) {
+            self.sync.track(RecordKind::Bookmark, &bookmark.id);
+        }
+        self.bookmarks.retain(|b| b.book_id != book_id);
+
+        let book_id = book_id.to_string();
+        for (name, members) in self.reading_state.collections.iter_mut() {
+            if members.contains(&book_id) {
+                members.remove(&book_id);
+                self.sync.track(RecordKind::Collection, name);
+            }
+        }
+
+        let mut overrides = self.reader_overrides();
+        if overrides.remove(&book_id).is_some() {
+            if let Err(e) = self.set_reader_overrides(&overrides) {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    // ===== TAGS & COLLECTIONS =====
+
+    pub fn set_tags(&mut self, book_id: &str, tags: Vec<String>) -> Result<(), String> {
+        if self.get_book(book_id).is_none() {
+            return Err("Book not found".to_string());
+        }
+
+        let device = self.sync.device_id.clone();
+        let state = &mut self.reading_state.book_mut(book_id).tags;
+        let removed: Vec<String> = state
+            .iter()
+            .filter(|t| !tags.contains(t))
+            .cloned()
+            .collect();
+        for tag in &removed {
+            state.remove(tag);
+        }
+        for tag in tags {
+            if !state.contains(&tag) {
+                state.insert(tag, &device);
+            }
+        }
+
+        self.project_book(book_id);
+        self.sync.track(RecordKind::Tags, book_id);
+        self.save_to_storage()
+    }
+
+    /// Collection name -> book ids, without empty collections
+    pub fn collections(&self) -> BTreeMap<String, Vec<String>> {
+        self.reading_state
+            .collections
+            .iter()
+            .filter(|(_, members)| !members.is_empty())
+            .map(|(name, members)| (name.clone(), members.iter().cloned().collect()))
+            .collect()
+    }
+
+    pub fn add_to_collection(&mut self, name: &str, book_id: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Collection name cannot be empty".to_string());
+        }
+        if self.get_book(book_id).is_none() {
+            return Err("Book not found".to_string());
+        }
+
+        let device = self.sync.device_id.clone();
+        self.reading_state
+            .collection_mut(name)
+            .insert(book_id.to_string(), &device);
+        self.sync.track(RecordKind::Collection, name);
+        self.save_to_storage()
+    }
+
+    pub fn remove_from_collection(&mut self, name: &str, book_id: &str) -> Result<(), String> {
+        if let Some(members) = self.reading_state.collections.get_mut(name) {
+            members.remove(&book_id.to_string());
+            self.sync.track(RecordKind::Collection, name);
+            self.save_to_storage()?;
+        }
+        Ok(())
+    }
+
+    // ===== READING POSITION =====
+
+    pub fn position_policy(&self) -> PositionPolicy {
+        self.preferences
+            .get(POSITION_POLICY_KEY)
+            .and_then(|v| PositionPolicy::from_name(v))
+            .unwrap_or_default()
+    }
+
+    /// Which synced position to open books at; applies to every book right away
+    pub fn set_position_policy(&mut self, policy: PositionPolicy) -> Result<(), String> {
+        self.preferences
+            .insert(POSITION_POLICY_KEY.to_string(), policy.as_str().to_string());
+
+        let ids: Vec<String> = self.books.iter().map(|b| b.id.clone()).collect();
+        for id in ids {
+            self.project_book(&id);
+        }
+        self.save_to_storage()
+    }
+
+    /// Copy the merged state of a book into `BookMetadata` and `annotations`
+    fn project_book(&mut self, book_id: &str) {
+        let Some(state) = self.reading_state.books.get(book_id) else {
+            return;
+        };
+        let policy = self.position_policy();
+
+        if let Some(book) = self.books.iter_mut().find(|b| b.id == book_id) {
+            book.tags = state.tags.iter().cloned().collect();
+            if let Some(entry) = state.position.resolve(policy) {
+                book.last_read_position = entry.position;
+                if entry.total_pages > 0 {
+                    book.total_pages = entry.total_pages;
+                }
+            }
+        }
+
+        self.annotations.retain(|a| a.book_id != book_id);
+        self.annotations.extend(state.annotations.iter().cloned());
+    }
+
+    // ===== BOOKMARKS & ANNOTATIONS =====
+
+    pub fn add_bookmark(
+        &mut self,
+        book_id: &str,
+        position: usize,
+        label: Option<String>,
+    ) -> Result<(), String> {
+        if self.get_book(book_id).is_none() {
+            return Err("Book not found".to_string());
+        }
+
+        let bookmark = Bookmark {
+            id: uuid::Uuid::new_v4().to_string(),
+            book_id: book_id.to_string(),
+            position,
+            label,
+            created_date: chrono::Utc::now().to_rfc3339(),
+        };
+        self.sync.track(RecordKind::Bookmark, &bookmark.id);
+        self.bookmarks.push(bookmark);
+        self.save_to_storage()
+    }
+
+    pub fn remove_bookmark(&mut self, bookmark_id: &str) -> Result<(), String> {
+        self.bookmarks.retain(|b| b.id != bookmark_id);
+        self.sync.track(RecordKind::Bookmark, bookmark_id);
+        self.save_to_storage()
+    }
+
+    pub fn add_annotation(
+        &mut self,
+        book_id: &str,
+        position: usize,
+        text: String,
+        note: Option<String>,
+    ) -> Result<(), String> {
+        if self.get_book(book_id).is_none() {
+            return Err("Book not found".to_string());
+        }
+
+        let annotation = Annotation {
+            id: uuid::Uuid::new_v4().to_string(),
+            book_id: book_id.to_string(),
+            position,
+            text,
+            note,
+            created_date: chrono::Utc::now().to_rfc3339(),
+        };
+        self.save_annotation(annotation)
+    }
+
+    pub fn edit_annotation_note(
+        &mut self,
+        annotation_id: &str,
+        note: Option<String>,
+    ) -> Result<(), String> {
+        let mut annotation = self
+            .annotations
+            .iter()
+            .find(|a| a.id == annotation_id)
+            .cloned()
+            .ok_or("Annotation not found")?;
+        annotation.note = note;
+        self.save_annotation(annotation)
+    }
+
+    pub fn remove_annotation(&mut self, annotation_id: &str) -> Result<(), String> {
+        let Some(book_id) = self
+            .annotations
+            .iter()
+            .find(|a| a.id == annotation_id)
+            .map(|a| a.book_id.clone())
+        else {
+            return Ok(());
+        };
+
+        self.reading_state
+            .book_mut(&book_id)
+            .annotations
+            .remove(annotation_id);
+        self.project_book(&book_id);
+        self.sync.track(RecordKind::Annotations, &book_id);
+        self.save_to_storage()
+    }
+
+    fn save_annotation(&mut self, annotation: Annotation) -> Result<(), String> {
+        self.save_annotation_state(annotation);
+        self.save_to_storage()
+    }
+
+    /// Add or edit an annotation in the reading state, without writing to disk
+    fn save_annotation_state(&mut self, annotation: Annotation) {
+        let book_id = annotation.book_id.clone();
+        let device = self.sync.device_id.clone();
+        self.reading_state
+            .book_mut(&book_id)
+            .annotations
+            .upsert(annotation, &device);
+        self.project_book(&book_id);
+        self.sync.track(RecordKind::Annotations, &book_id);
+    }
+
+    // ===== SYNC =====
+
+    /// Records for every entity changed since the last push. Book records
+    /// carry metadata only; the file itself stays on this device.
+    pub fn pending_records(&self) -> Vec<SyncRecord> {
+        let changed = self.sync.pending.values().map(|change| SyncRecord {
+            kind: change.kind,
+            id: change.id.clone(),
+            data: self.record_data(change.kind, &change.id),
+            modified: change.modified,
+            version: 0,
+            encrypted: false,
+        });
+
+        // Sudah dalam bentuk di server, jadi tidak di-seal lagi
+        let retired = self.sync.retired_collections.iter().map(|id| SyncRecord {
+            kind: RecordKind::Collection,
+            id: id.clone(),
+            data: None,
+            modified: now_millis(),
+            version: 0,
+            encrypted: true,
+        });
+
+        changed.chain(retired).collect()
+    }
+
+    /// Current state of one entity as record data; None = it's gone
+    fn record_data(&self, kind: RecordKind, id: &str) -> Option<serde_json::Value> {
+        let state = || {
+            self.get_book(id)
+                .and_then(|_| self.reading_state.books.get(id))
+        };
+        match kind {
+            RecordKind::Book => self.get_book(id).and_then(|book| {
+                let mut book = book.clone();
+                book.file_data = String::new();
+                book.blob = None;
+                serde_json::to_value(book).ok()
+            }),
+            RecordKind::Position => state().and_then(|s| serde_json::to_value(&s.position).ok()),
+            RecordKind::Tags => state().and_then(|s| serde_json::to_value(&s.tags).ok()),
+            RecordKind::Annotations => {
+                state().and_then(|s| serde_json::to_value(&s.annotations).ok())
+            }
+            RecordKind::Collection => self
+                .reading_state
+                .collections
+                .get(id)
+                .and_then(|c| serde_json::to_value(c).ok()),
+            RecordKind::Bookmark => self
+                .bookmarks
+                .iter()
+                .find(|b| b.id == id)
+                .and_then(|b| serde_json::to_value(b).ok()),
+        }
+    }
+
+    /// Apply the result of [`SyncClient::sync`](crate::sync::SyncClient::sync):
+    /// forget what was pushed and merge what was pulled. Tags, positions,
+    /// annotations and collections are merged with local edits; for other
+    /// records a local edit newer than the remote one wins and stays pending.
+    ///
+    /// `pushed` are the records before sealing. Records encrypted with a key
+    /// this device doesn't have stop the sync until the passphrase is entered.
+    pub fn apply_sync(
+        &mut self,
+        pushed: &[SyncRecord],
+        pulled: PullResponse,
+    ) -> Result<(), String> {
+        self.sync.mark_pushed(pushed);
+        let mut locked = None;
+        let mut merged = Vec::new();
+
+        for record in &pulled.records {
+            let record = match record.open(&self.sync.encryption) {
+                Ok(record) => record,
+                Err(CryptoError::OtherKey(_)) => {
+                    if let Some(serde_json::Value::String(sample)) = &record.data {
+                        locked.get_or_insert(sample.clone());
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Skipping {:?} record {}: {}", record.kind, record.id, e);
+                    continue;
+                }
+            };
+
+            let local_is_newer = self
+                .sync
+                .pending_change(record.kind, &record.id)
+                .is_some_and(|p| p.modified > record.modified);
+            let mergeable = record.kind.is_mergeable() && record.data.is_some();
+            if local_is_newer && !mergeable {
+                continue;
+            }
+
+            match record.decode() {
+                Ok(change) => self.apply_remote_change(change),
+                Err(e) => warn!("{}", e),
+            }
+            if mergeable {
+                merged.push(record);
+            }
+        }
+
+        // Server tidak bisa menggabungkan record terenkripsi; kalau hasil
+        // gabungan di sini berbeda dari yang di server, kirim balik
+        for record in merged {
+            let local = self.record_data(record.kind, &record.id);
+            if local.is_some() && local != record.data {
+                self.sync.track(record.kind, &record.id);
+            }
+        }
+
+        if let Some(sample) = locked {
+            return Err(self.lock_encryption(sample));
+        }
+
+        self.sync.last_version = pulled.version;
+        self.save_to_storage()?;
+        info!(
+            "Sync done at version {} ({} records pulled, {} still pending)",
+            pulled.version,
+            pulled.records.len(),
+            self.sync.pending.len()
+        );
+        Ok(())
+    }
+
+    /// Perubahan dari server tidak di-track lagi, supaya tidak dikirim balik
+    fn apply_remote_change(&mut self, change: RemoteChange) {
+        match change {
+            RemoteChange::Book(remote) => {
+                let book_id = remote.id.clone();
+                match self.books.iter_mut().find(|b| b.id == remote.id) {
+                    Some(local) => {
+                        // File punya jalurnya sendiri; tag & posisi dari reading state
+                        let file_data = std::mem::take(&mut local.file_data);
+                        let blob = local.blob.take();
+                        let position = local.last_read_position;
+                        *local = *remote;
+                        local.file_data = file_data;
+                        local.blob = blob;
+                        local.last_read_position = position;
+                    }
+                    // Buku dari perangkat lain: metadata saja, filenya belum ada
+                    None => self.books.push(*remote),
+                }
+                self.project_book(&book_id);
+            }
+            RemoteChange::Position(book_id, position) => {
+                self.reading_state
+                    .book_mut(&book_id)
+                    .position
+                    .merge(&position);
+                self.project_book(&book_id);
+            }
+            RemoteChange::Tags(book_id, tags) => {
+                self.reading_state.book_mut(&book_id).tags.merge(&tags);
+                self.project_book(&book_id);
+            }
+            RemoteChange::Annotations(book_id, annotations) => {
+                self.reading_state
+                    .book_mut(&book_id)
+                    .annotations
+                    .merge(&annotations);
+                self.project_book(&book_id);
+            }
+            RemoteChange::Collection(name, members) => {
+                self.reading_state.collection_mut(&name).merge(&members);
+            }
+            RemoteChange::Bookmark(bookmark) => {
+                match self.bookmarks.iter_mut().find(|b| b.id == bookmark.id) {
+                    Some(local) => *local = bookmark,
+                    None => self.bookmarks.push(bookmark),
+                }
+            }
+            RemoteChange::Deleted(kind, id) => match kind {
+                RecordKind::Book => {
+                    if let Some(blob) = self.get_book(&id).and_then(|b| b.blob.clone()) {
+                        if let Err(e) = remove_blob(&blob) {
+                            warn!("{}", e);
+                        }
+                    }
+                    self.books.retain(|b| b.id != id);
+                    self.annotations.retain(|a| a.book_id != id);
+                    self.bookmarks.retain(|b| b.book_id != id);
+                    self.reading_state.books.remove(&id);
+                }
+                RecordKind::Bookmark => self.bookmarks.retain(|b| b.id != id),
+                // Ikut terhapus bersama bukunya
+                RecordKind::Position
+                | RecordKind::Tags
+                | RecordKind::Annotations
+                | RecordKind::Collection => {}
+            },
+        }
+    }
+
+    // ===== SYNC SERVER =====
+
+    pub fn sync_server_config(&self) -> Option<SyncConfig> {
+        let get = |key: &str| self.preferences.get(key).cloned().unwrap_or_default();
+        let server_url = self.preferences.get(SYNC_SERVER_URL_KEY)?.clone();
+        Some(SyncConfig {
+            server_url,
+            username: get(SYNC_USERNAME_KEY),
+            key: get(SYNC_KEY_KEY),
+        })
+    }
+
+    /// `None` turns server sync off
+    pub fn set_sync_server_config(&mut self, config: Option<SyncConfig>) -> Result<(), String> {
+        // Server atau akun lain: pull lagi dari awal
+        let account = |c: &SyncConfig| (c.server_url.clone(), c.username.clone());
+        if self.sync_server_config().as_ref().map(account) != config.as_ref().map(account) {
+            self.sync.last_version = 0;
+        }
+
+        match config {
+            Some(config) => {
+                self.preferences
+                    .insert(SYNC_SERVER_URL_KEY.to_string(), config.server_url);
+                self.preferences
+                    .insert(SYNC_USERNAME_KEY.to_string(), config.username);
+                self.preferences
+                    .insert(SYNC_KEY_KEY.to_string(), config.key);
+            }
+            None => {
+                for key in [SYNC_SERVER_URL_KEY, SYNC_USERNAME_KEY, SYNC_KEY_KEY] {
+                    self.preferences.remove(key);
+                }
+            }
+        }
+        self.save_to_storage()
+    }
+
+    // ===== ENCRYPTION =====
+
+    pub fn encryption_enabled(&self) -> bool {
+        self.sync.encryption.key.is_some()
+    }
+
+    /// A sync target holds data that no key on this device can open
+    pub fn encryption_locked(&self) -> bool {
+        self.sync.encryption.locked_sample.is_some()
+    }
+
+    /// Turn encryption on, or unlock data encrypted on another device. When
+    /// a sync target already holds encrypted data, the passphrase is checked
+    /// against it and that key is adopted, so every device shares one key.
+    pub fn set_passphrase(&mut self, passphrase: &str) -> Result<(), String> {
+        if let Some(sample) = self.sync.encryption.locked_sample.clone() {
+            let sample = STANDARD.decode(sample).map_err(|e| e.to_string())?;
+            let key = LibraryKey::unlock(passphrase, &sample).map_err(|e| e.to_string())?;
+
+            // Yang sudah terkirim dengan key lama ditulis ulang dengan key ini
+            if self.sync.encryption.key.is_some() {
+                self.retire_collection_ids();
+                self.track_everything();
+            }
+            let encryption = &mut self.sync.encryption;
+            encryption.previous = encryption.key.take();
+            encryption.key = Some(key);
+            encryption.locked_sample = None;
+            info!("Unlocked data encrypted on another device");
+            return self.save_to_storage();
+        }
+
+        if self.encryption_enabled() {
+            return Err("Encryption is already on".to_string());
+        }
+        let key = LibraryKey::create(passphrase).map_err(|e| e.to_string())?;
+        self.retire_collection_ids();
+        self.sync.encryption.key = Some(key);
+        self.track_everything();
+        info!("Encryption turned on");
+        self.save_to_storage()
+    }
+
+    /// Re-encrypt everything on the sync targets with a key made from `new`
+    pub fn change_passphrase(&mut self, old: &str, new: &str) -> Result<(), String> {
+        let key = self
+            .sync
+            .encryption
+            .key
+            .clone()
+            .ok_or("Encryption is off")?;
+        key.verify(old).map_err(|e| e.to_string())?;
+        let new_key = LibraryKey::create(new).map_err(|e| e.to_string())?;
+
+        self.retire_collection_ids();
+        self.sync.encryption.previous = Some(key);
+        self.sync.encryption.key = Some(new_key);
+        self.track_everything();
+        info!("Passphrase changed; everything will be re-encrypted on the next sync");
+        self.save_to_storage()
+    }
+
+    /// Sync targets and backups get plaintext again. Devices that still have
+    /// the key refuse plaintext sync records until it's turned off there too.
+    pub fn disable_encryption(&mut self, passphrase: &str) -> Result<(), String> {
+        let key = self
+            .sync
+            .encryption
+            .key
+            .clone()
+            .ok_or("Encryption is off")?;
+        key.verify(passphrase).map_err(|e| e.to_string())?;
+
+        self.retire_collection_ids();
+        self.sync.encryption.previous = self.sync.encryption.key.take();
+        self.track_everything();
+        info!("Encryption turned off");
+        self.save_to_storage()
+    }
+
+    /// Decrypt something downloaded from a sync target. Data under an
+    /// unknown key locks encryption until [`Library::set_passphrase`].
+    pub fn decrypt_synced(&mut self, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        match self.sync.encryption.decrypt(bytes) {
+            Ok(plaintext) => Ok(plaintext),
+            Err(CryptoError::OtherKey(_)) => Err(self.lock_encryption(STANDARD.encode(bytes))),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Encrypt something before it goes to a sync target (if encryption is on)
+    pub fn encrypt_synced(&self, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        self.sync
+            .encryption
+            .encrypt(bytes)
+            .map_err(|e| e.to_string())
+    }
+
+    fn lock_encryption(&mut self, sample: String) -> String {
+        self.sync.encryption.locked_sample = Some(sample);
+        if let Err(e) = self.save_to_storage() {
+            error!("Failed to save library: {}", e);
+        }
+        ENCRYPTION_LOCKED.to_string()
+    }
+
+    /// Ids the server knows collections by right now; removed on the next
+    /// push since the key (and so the id) is about to change. Never synced:
+    /// nothing to remove, and a tombstone would only reveal the names.
+    fn retire_collection_ids(&mut self) {
+        if self.sync.last_version == 0 {
+            return;
+        }
+        let names: Vec<String> = self.reading_state.collections.keys().cloned().collect();
+        for name in names {
+            let id = match &self.sync.encryption.key {
+                Some(key) => key.blind_id(&name),
+                None => name,
+            };
+            self.sync.retired_collections.insert(id);
+        }
+    }
+
+    fn track_everything(&mut self) {
+        for book_id in self.books.iter().map(|b| b.id.clone()).collect::<Vec<_>>() {
+            for kind in [
+                RecordKind::Book,
+                RecordKind::Position,
+                RecordKind::Tags,
+                RecordKind::Annotations,
+            ] {
+                self.sync.track(kind, &book_id);
+            }
+        }
+        for bookmark_id in self
+            .bookmarks
+            .iter()
+            .map(|b| b.id.clone())
+            .collect::<Vec<_>>()
+        {
+            self.sync.track(RecordKind::Bookmark, &bookmark_id);
+        }
+        for name in self.reading_state.collections.keys() {
+            self.sync.track(RecordKind::Collection, name);
+        }
+    }
+
+    // ===== WEBDAV =====
+
+    pub fn webdav_config(&self) -> Option<WebDavConfig> {
+        let get = |key: &str| self.preferences.get(key).cloned().unwrap_or_default();
+        let url = self.preferences.get(WEBDAV_URL_KEY)?.clone();
+        Some(WebDavConfig {
+            url,
+            username: get(WEBDAV_USERNAME_KEY),
+            password: get(WEBDAV_PASSWORD_KEY),
+        })
+    }
+
+    /// `None` turns WebDAV sync off
+    pub fn set_webdav_config(&mut self, config: Option<WebDavConfig>) -> Result<(), String> {
+        // Folder lain: lupakan semua yang diketahui tentang folder lama
+        let old_url = self.webdav_config().map(|c| c.url);
+        if old_url != config.as_ref().map(|c| c.url.clone()) {
+            self.sync.webdav = WebDavState::default();
+        }
+
+        match config {
+            Some(config) => {
+                self.preferences
+                    .insert(WEBDAV_URL_KEY.to_string(), config.url);
+                self.preferences
+                    .insert(WEBDAV_USERNAME_KEY.to_string(), config.username);
+                self.preferences
+                    .insert(WEBDAV_PASSWORD_KEY.to_string(), config.password);
+            }
+            None => {
+                for key in [WEBDAV_URL_KEY, WEBDAV_USERNAME_KEY, WEBDAV_PASSWORD_KEY] {
+                    self.preferences.remove(key);
+                }
+            }
+        }
+        self.save_to_storage()
+    }
+
+    /// `library.json` for the WebDAV folder: the same document as the local
+    /// file, without book files, preferences (they hold the WebDAV password)
+    /// or sync state
+    pub fn webdav_index(&self) -> Result<Vec<u8>, String> {
+        let books: Vec<BookMetadata> = self
+            .books
+            .iter()
+            .map(|book| BookMetadata {
+                blob: None,
+                ..book.metadata_only()
+            })
+            .collect();
+
+        let library = StoredLibraryRef {
+            books: &books,
+            annotations: &self.annotations,
+            bookmarks: &self.bookmarks,
+            preferences: &BTreeMap::new(),
+            sync: &SyncState::default(),
+            reading_state: &self.reading_state,
+            // Folder yang dipantau hanya berlaku di perangkat ini
+            watch: &WatchState::default(),
+        };
+        storage::to_json(&library)
+            .map(String::into_bytes)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Merge a `library.json` downloaded from WebDAV. Books and bookmarks are
+    /// compared with what was there at the last sync, so deletes on either
+    /// side stick; everything else is merged through the reading state.
+    pub fn merge_webdav_index(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let doc =
+            serde_json::from_slice(bytes).map_err(|e| format!("Invalid library.json: {e}"))?;
+        let remote = storage::migrate(doc).map_err(|e| format!("Invalid library.json: {e}"))?;
+        let known_books = self.sync.webdav.known_books.clone();
+        let known_bookmarks = self.sync.webdav.known_bookmarks.clone();
+
+        // Dihapus di perangkat lain
+        let remote_books: BTreeSet<&str> = remote.books.iter().map(|b| b.id.as_str()).collect();
+        let removed: Vec<String> = self
+            .books
+            .iter()
+            .filter(|b| known_books.contains(&b.id) && !remote_books.contains(b.id.as_str()))
+            .map(|b| b.id.clone())
+            .collect();
+        for book_id in removed {
+            self.forget_book(&book_id);
+        }
+
+        // Ditambahkan di perangkat lain; filenya diunduh terpisah
+        for book in remote.books {
+            if !known_books.contains(&book.id) && self.get_book(&book.id).is_none() {
+                self.books.push(book);
+            }
+        }
+
+        let local_books: BTreeSet<String> = self.books.iter().map(|b| b.id.clone()).collect();
+        self.reading_state.merge(&remote.reading_state);
+        self.reading_state
+            .books
+            .retain(|book_id, _| local_books.contains(book_id));
+        for book_id in &local_books {
+            self.project_book(book_id);
+        }
+
+        let remote_bookmarks: BTreeSet<&str> =
+            remote.bookmarks.iter().map(|b| b.id.as_str()).collect();
+        self.bookmarks.retain(|b| {
+            !known_bookmarks.contains(&b.id) || remote_bookmarks.contains(b.id.as_str())
+        });
+        for bookmark in remote.bookmarks {
+            let exists = self.bookmarks.iter().any(|b| b.id == bookmark.id);
+            if !exists
+                && !known_bookmarks.contains(&bookmark.id)
+                && local_books.contains(&bookmark.book_id)
+            {
+                self.bookmarks.push(bookmark);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store a book file downloaded from another device
+    pub fn attach_book_file(&mut self, book_id: &str, bytes: &[u8]) -> Result<(), String> {
+        let book = self
+            .books
+            .iter_mut()
+            .find(|b| b.id == book_id)
+            .ok_or("Book not found")?;
+
+        // Desktop menyimpannya di blob store; web tidak punya, jadi data URL
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let blob = blob_name(&book.id, book.format.to_extension());
+            write_blob(&blob, bytes)?;
+            book.blob = Some(blob);
+            book.file_data = String::new();
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            book.file_data = encode_data_url(book.format.mime_type(), bytes);
+            book.blob = None;
+        }
+        book.size = bytes.len() as u64;
+        if book.cover_image.is_none() {
+            book.cover_image = cover_thumbnail(&book.format, bytes);
+        }
+        Ok(())
+    }
+
+    /// Remember what the WebDAV folder holds now that `library.json` is written
+    pub fn finish_webdav_sync(&mut self, index_etag: Option<String>) -> Result<(), String> {
+        let state = &mut self.sync.webdav;
+        state.index_etag = index_etag;
+        state.key_salt = self
+            .sync
+            .encryption
+            .key
+            .as_ref()
+            .map(|k| k.header.salt.clone());
+        state.known_books = self.books.iter().map(|b| b.id.clone()).collect();
+        state.known_bookmarks = self.bookmarks.iter().map(|b| b.id.clone()).collect();
+        let known = &state.known_books;
+        state
+            .blob_etags
+            .retain(|book_id, _| known.contains(book_id));
+        self.save_to_storage()
+    }
+
+    /// A copy to run a WebDAV sync on while the library itself stays
+    /// editable. It is never saved; [`Library::apply_webdav_sync`] brings
+    /// the result back. Book files live in the blob store on desktop, so
+    /// only their names are copied; on the web a data URL is the only copy
+    /// of a file and comes along in case it has to be uploaded.
+    pub fn sync_snapshot(&self) -> Library {
+        Library {
+            books: self.books.clone(),
+            annotations: self.annotations.clone(),
+            bookmarks: self.bookmarks.clone(),
+            preferences: self.preferences.clone(),
+            sync: self.sync.clone(),
+            reading_state: self.reading_state.clone(),
+            watch: WatchState::default(),
+            storage_path: None,
+            err: None,
+            import_results: vec![],
+            import_progress: None,
+            import_retry: vec![],
+        }
+    }
+
+    /// Take over what a sync on [`Library::sync_snapshot`] produced. Edits
+    /// made here in the meantime are kept: the synced index is merged like
+    /// one from the server, downloaded files are moved over, and they go
+    /// out with the next sync.
+    pub fn apply_webdav_sync(&mut self, synced: Library) -> Result<(), String> {
+        let index = synced.webdav_index()?;
+        self.merge_webdav_index(&index)?;
+
+        for remote in synced.books {
+            let Some(book) = self.books.iter_mut().find(|b| b.id == remote.id) else {
+                continue;
+            };
+            let downloaded = synced.sync.webdav.blob_etags.get(&remote.id)
+                != self.sync.webdav.blob_etags.get(&remote.id);
+            if remote.has_file() && (downloaded || !book.has_file()) {
+                book.blob = remote.blob;
+                book.file_data = remote.file_data;
+                book.size = remote.size;
+                if book.cover_image.is_none() {
+                    book.cover_image = remote.cover_image;
+                }
+            }
+        }
+
+        self.sync.webdav = synced.sync.webdav;
+        self.save_to_storage()
+    }
+
+    /// A sync on the snapshot stopped at data encrypted with a key this
+    /// device doesn't have; keep its sample so a passphrase can be checked
+    pub fn keep_encryption_lock(&mut self, synced: &Library) {
+        if let Some(sample) = synced.sync.encryption.locked_sample.clone() {
+            self.lock_encryption(sample);
+        }
+    }
+
+    // ===== OPDS =====
+
+    pub fn opds_catalogs(&self) -> Vec<OpdsCatalog> {
+        self.preferences
+            .get(OPDS_CATALOGS_KEY)
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Add a catalog, or update the one with the same URL
+    pub fn save_opds_catalog(&mut self, catalog: OpdsCatalog) -> Result<(), String> {
+        let mut catalogs = self.opds_catalogs();
+        match catalogs.iter_mut().find(|c| c.url == catalog.url) {
+            Some(existing) => *existing = catalog,
+            None => catalogs.push(catalog),
+        }
+        self.set_opds_catalogs(&catalogs)
+    }
+
+    pub fn remove_opds_catalog(&mut self, url: &str) -> Result<(), String> {
+        let mut catalogs = self.opds_catalogs();
+        catalogs.retain(|c| c.url != url);
+        self.set_opds_catalogs(&catalogs)
+    }
+
+    fn set_opds_catalogs(&mut self, catalogs: &[OpdsCatalog]) -> Result<(), String> {
+        let json = serde_json::to_string(catalogs).map_err(|e| e.to_string())?;
+        self.preferences.insert(OPDS_CATALOGS_KEY.to_string(), json);
+        self.save_to_storage()
+    }
+
+    pub fn opds_server_settings(&self) -> OpdsServerSettings {
+        let defaults = OpdsServerSettings::default();
+        OpdsServerSettings {
+            enabled: self
+                .preferences
+                .get(OPDS_SERVER_ENABLED_KEY)
+                .is_some_and(|v| v == "true"),
+            port: self
+                .preferences
+                .get(OPDS_SERVER_PORT_KEY)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.port),
+        }
+    }
+
+    pub fn set_opds_server_settings(&mut self, settings: OpdsServerSettings) -> Result<(), String> {
+        self.preferences.insert(
+            OPDS_SERVER_ENABLED_KEY.to_string(),
+            settings.enabled.to_string(),
+        );
+        self.preferences
+            .insert(OPDS_SERVER_PORT_KEY.to_string(), settings.port.to_string());
+        self.save_to_storage()
+    }
+
+    // ===== PREFERENCES =====
+
+    pub fn app_preferences(&self) -> Preferences {
+        self.preferences
+            .get(APP_PREFERENCES_KEY)
+            .and_then(|json| serde_json::from_str::<Preferences>(json).ok())
+            .filter(|prefs| prefs.validate().is_ok())
+            .unwrap_or_default()
+    }
+
+    /// Takes effect right away; the import limit applies from the next import
+    pub fn set_app_preferences(&mut self, prefs: Preferences) -> Result<(), String> {
+        prefs.validate()?;
+        let json = serde_json::to_string(&prefs).map_err(|e| e.to_string())?;
+        self.preferences
+            .insert(APP_PREFERENCES_KEY.to_string(), json);
+        self.save_to_storage()
+    }
+
+    // ===== READER SETTINGS =====
+
+    pub fn reader_settings(&self) -> ReaderSettings {
+        self.preferences
+            .get(READER_SETTINGS_KEY)
+            .and_then(|json| serde_json::from_str::<ReaderSettings>(json).ok())
+            .unwrap_or_default()
+            .clamped()
+    }
+
+    pub fn set_reader_settings(&mut self, settings: ReaderSettings) -> Result<(), String> {
+        let json = serde_json::to_string(&settings.clamped()).map_err(|e| e.to_string())?;
+        self.preferences
+            .insert(READER_SETTINGS_KEY.to_string(), json);
+        self.save_to_storage()
+    }
+
+    /// Settings a book opens with: the global ones plus its overrides
+    pub fn reader_settings_for(&self, book_id: &str) -> ReaderSettings {
+        let overrides = self.book_reader_overrides(book_id);
+        self.reader_settings().with(&overrides).clamped()
+    }
+
+    pub fn book_reader_overrides(&self, book_id: &str) -> ReaderOverrides {
+        self.reader_overrides().remove(book_id).unwrap_or_default()
+    }
+
+    /// Keep `settings` for this book only, as far as they differ from the
+    /// global ones. `None` makes the book follow the global settings again.
+    pub fn set_book_reader_settings(
+        &mut self,
+        book_id: &str,
+        settings: Option<ReaderSettings>,
+    ) -> Result<(), String> {
+        let global = self.reader_settings();
+        let overrides = settings
+            .map(|s| global.overrides_for(&s.clamped()))
+            .filter(|o| !o.is_empty());
+
+        let mut all = self.reader_overrides();
+        match overrides {
+            Some(overrides) => all.insert(book_id.to_string(), overrides),
+            None => all.remove(book_id),
+        };
+        self.set_reader_overrides(&all)?;
+        self.save_to_storage()
+    }
+
+    fn reader_overrides(&self) -> BTreeMap<String, ReaderOverrides> {
+        self.preferences
+            .get(READER_OVERRIDES_KEY)
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    fn set_reader_overrides(
+        &mut self,
+        overrides: &BTreeMap<String, ReaderOverrides>,
+    ) -> Result<(), String> {
+        if overrides.is_empty() {
+            self.preferences.remove(READER_OVERRIDES_KEY);
+            return Ok(());
+        }
+        let json = serde_json::to_string(overrides).map_err(|e| e.to_string())?;
+        self.preferences
+            .insert(READER_OVERRIDES_KEY.to_string(), json);
+        Ok(())
+    }
+
+    // ===== BACKUP & RESTORE =====
+
+    /// Encrypted with the library key when encryption is on. Built in
+    /// memory, for the browser download; desktop and the CLI can stream
+    /// to a file with [`Library::export_backup_to`].
+    pub fn export_backup(&self) -> Result<Vec<u8>, String> {
+        let archive = self.write_backup(Cursor::new(Vec::new()))?.into_inner();
+        self.sync
+            .encryption
+            .encrypt(archive)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Write the backup to `path`. Without encryption book files go
+    /// straight from the blob store into the file; an encrypted backup is
+    /// one envelope and has to be built in memory first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_backup_to(&self, path: &Path) -> Result<(), String> {
+        let failed =
+            |e: &dyn std::fmt::Display| format!("Failed to write {}: {}", path.display(), e);
+        if self.encryption_enabled() {
+            return fs::write(path, self.export_backup()?).map_err(|e| failed(&e));
+        }
+
+        let file = fs::File::create(path).map_err(|e| failed(&e))?;
+        let result = (self.write_backup(io::BufWriter::new(file)))
+            .and_then(|mut out| out.flush().map_err(|e| failed(&e)));
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    fn write_backup<W: Write + Seek>(&self, out: W) -> Result<W, String> {
+        // Kredensial WebDAV tidak ikut; backup bisa saja tidak terenkripsi
+        let preferences: BTreeMap<String, String> = (self.preferences.iter())
+            .filter(|(key, _)| !is_device_preference(key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        export_backup(
+            out,
+            &self.books,
+            &self.annotations,
+            &self.bookmarks,
+            &preferences,
+        )
+    }
+
+    /// Does restoring `archive` need a passphrase (encrypted with a key
+    /// this device doesn't have)?
+    pub fn backup_needs_passphrase(&self, archive: &[u8]) -> bool {
+        !self.sync.encryption.can_open(archive)
+    }
+
+    /// Like [`Library::backup_needs_passphrase`] for a backup file; only
+    /// its header is read
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup_file_needs_passphrase(&self, path: &Path) -> Result<bool, String> {
+        let mut header = Vec::new();
+        fs::File::open(path)
+            .and_then(|file| file.take(HEADER_LEN as u64).read_to_end(&mut header))
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Ok(self.backup_needs_passphrase(&header))
+    }
+
+    pub fn restore_backup(
+        &mut self,
+        archive: &[u8],
+        mode: RestoreMode,
+        passphrase: Option<&str>,
+    ) -> Result<RestoreReport, String> {
+        let archive = self.open_backup(archive, passphrase)?;
+        self.restore_archive(Cursor::new(archive), mode)
+    }
+
+    /// Restore a backup file. A plain archive is read straight from disk and
+    /// its books streamed into the blob store; an encrypted one has to be
+    /// decrypted in memory first.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore_backup_from(
+        &mut self,
+        path: &Path,
+        mode: RestoreMode,
+        passphrase: Option<&str>,
+    ) -> Result<RestoreReport, String> {
+        let failed = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+        let mut file = fs::File::open(path).map_err(failed)?;
+        let mut magic = Vec::new();
+        (&mut file)
+            .take(HEADER_LEN as u64)
+            .read_to_end(&mut magic)
+            .map_err(failed)?;
+
+        if is_encrypted(&magic) {
+            let archive = fs::read(path).map_err(failed)?;
+            return self.restore_backup(&archive, mode, passphrase);
+        }
+        file.rewind().map_err(failed)?;
+        self.restore_archive(io::BufReader::new(file), mode)
+    }
+
+    fn open_backup(&self, archive: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, String> {
+        match (self.sync.encryption.decrypt(archive), passphrase) {
+            (Ok(archive), _) => Ok(archive),
+            (Err(CryptoError::OtherKey(_)), Some(passphrase)) => {
+                LibraryKey::unlock(passphrase, archive)
+                    .and_then(|key| key.decrypt(archive))
+                    .map_err(|e| e.to_string())
+            }
+            (Err(CryptoError::OtherKey(_)), None) => {
+                Err("This backup is encrypted; enter its passphrase".to_string())
+            }
+            (Err(e), _) => Err(e.to_string()),
+        }
+    }
+
+    fn restore_archive<R: Read + Seek>(
+        &mut self,
+        source: R,
+        mode: RestoreMode,
+    ) -> Result<RestoreReport, String> {
+        let mut backup = read_backup(source)?;
+        info!(
+            "Restoring backup from {} ({} books, {:?})",
+            backup.manifest.created_date, backup.manifest.book_count, mode
+        );
+
+        let mut report = RestoreReport::default();
+
+        // Pengaturan khusus perangkat ini (kredensial WebDAV) tetap milik
+        // perangkat ini, apa pun isi backupnya
+        let preferences: Vec<(String, String)> = std::mem::take(&mut backup.preferences)
+            .into_iter()
+            .filter(|(key, _)| !is_device_preference(key))
+            .collect();
+        let books = std::mem::take(&mut backup.books);
+
+        match mode {
+            RestoreMode::Replace => {
+                // Semua file dari arsip disimpan dulu dengan nama sementara;
+                // library lama baru dibuang kalau semuanya berhasil
+                let mut restored = Vec::with_capacity(books.len());
+                for mut book in books {
+                    if let Err(e) = backup.restore_staged(&mut book) {
+                        discard_staged(&restored);
+                        return Err(format!(
+                            "Backup is incomplete, nothing was restored: '{}': {}",
+                            book.title, e
+                        ));
+                    }
+                    restored.push(book);
+                }
+
+                let ids: Vec<String> = self.books.iter().map(|b| b.id.clone()).collect();
+                for id in ids {
+                    self.forget_book(&id);
+                }
+                self.annotations.clear();
+                self.bookmarks.clear();
+                self.preferences.retain(|key, _| is_device_preference(key));
+                self.preferences.extend(preferences);
+
+                for mut book in restored {
+                    commit_staged(&mut book);
+                    self.adopt_book(&book);
+                    self.books.push(book);
+                    report.books_added += 1;
+                }
+            }
+            RestoreMode::Merge => {
+                for mut book in books {
+                    let reason = if self.get_book(&book.id).is_some() {
+                        Some("already in library".to_string())
+                    } else {
+                        self.find_duplicate(&book.file_name, book.size)
+                            .map(|existing| format!("duplicate of '{}'", existing.title))
+                    };
+                    let result = match reason {
+                        Some(reason) => Err(reason),
+                        None => backup.restore_file(&mut book),
+                    };
+
+                    match result {
+                        Ok(()) => {
+                            self.adopt_book(&book);
+                            self.books.push(book);
+                            report.books_added += 1;
+                        }
+                        Err(reason) => report.conflicts.push(RestoreConflict {
+                            book_id: book.id,
+                            title: book.title,
+                            reason,
+                        }),
+                    }
+                }
+
+                // Preferences yang sudah ada tetap dipakai
+                for (key, value) in preferences {
+                    self.preferences.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        for annotation in backup.annotations {
+            let known_book = self.get_book(&annotation.book_id).is_some();
+            let exists = self.annotations.iter().any(|a| a.id == annotation.id);
+            if known_book && !exists {
+                self.save_annotation_state(annotation);
+                report.annotations_added += 1;
+            }
+        }
+
+        for bookmark in backup.bookmarks {
+            let known_book = self.get_book(&bookmark.book_id).is_some();
+            let exists = self.bookmarks.iter().any(|b| b.id == bookmark.id);
+            if known_book && !exists {
+                self.sync.track(RecordKind::Bookmark, &bookmark.id);
+                self.bookmarks.push(bookmark);
+                report.bookmarks_added += 1;
+            }
+        }
+
+        self.save_to_storage()?;
+        info!("{}", report);
+        Ok(report)
+    }
+
+    // ===== CALIBRE EXPORT =====
+
+    /// Export books (by id) as Calibre-style folders with a `metadata.opf` sidecar.
+    /// Returns the per-book errors.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn export_books(&self, book_ids: &[String], dir: &Path) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        for book_id in book_ids {
+            let Some(book) = self.get_book(book_id) else {
+                errors.push(format!("{}: not in library", book_id));
+                continue;
+            };
+
+            match export_book(book, dir) {
+                Ok(path) => info!("Exported '{}' to {}", book.title, path.display()),
+                Err(e) => errors.push(format!("{}: {}", book.title, e)),
+            }
+        }
+
+        errors
+    }
+
+    // ===== WATCHED FOLDERS =====
+
+    pub fn watched_folders(&self) -> &[String] {
+        &self.watch.folders
+    }
+
+    /// Start watching a folder; its books are imported on the next rescan
+    pub fn add_watched_folder(&mut self, folder: &str) -> Result<(), String> {
+        let folder = folder.trim();
+        if folder.is_empty() {
+            return Err("Choose a folder to watch".to_string());
+        }
+        if self.watch.folders.iter().any(|f| f == folder) {
+            return Err("This folder is already watched".to_string());
+        }
+
+        self.watch.folders.push(folder.to_string());
+        self.save_to_storage()?;
+        info!("Watching {}", folder);
+        Ok(())
+    }
+
+    /// Stop watching a folder. Books imported from it stay in the library.
+    pub fn remove_watched_folder(&mut self, folder: &str) -> Result<(), String> {
+        self.watch.folders.retain(|f| f != folder);
+        // Catatan file dibuang, kecuali masih di bawah folder lain yang dipantau
+        let folders = &self.watch.folders;
+        self.watch.files.retain(|path, _| {
+            let path = Path::new(path);
+            !path.starts_with(folder) || folders.iter().any(|f| path.starts_with(f))
+        });
+        self.save_to_storage()?;
+        info!("Stopped watching {}", folder);
+        Ok(())
+    }
+
+    /// Compare a [`FolderScan`] with what was found in the watched folders
+    /// last time. Renamed files keep their book and deleted files are
+    /// forgotten while their books stay; that is applied right away. New
+    /// files, and changed files that replace their book's file, are
+    /// returned for [`Library::start_import`]. Also run at startup, to
+    /// catch up on changes made while the app was closed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn apply_folder_scan(
+        &mut self,
+        scan: FolderScan,
+    ) -> Result<(WatchReport, Vec<ImportItem>), String> {
+        // Folder bisa saja dilepas selama scan berjalan
+        let folders: Vec<PathBuf> = (scan.folders.into_iter())
+            .filter(|f| self.watch.folders.iter().any(|w| Path::new(w) == f))
+            .collect();
+        let found = scan.files;
+
+        let mut gone: Vec<(String, WatchedFile)> = (self.watch.files.iter())
+            .filter(|(path, _)| {
+                folders.iter().any(|f| Path::new(path).starts_with(f)) && !found.contains_key(*path)
+            })
+            .map(|(path, file)| (path.clone(), file.clone()))
+            .collect();
+        for (path, _) in &gone {
+            self.watch.files.remove(path);
+        }
+
+        let mut report = WatchReport::default();
+        let mut items = Vec::new();
+        for (path, (size, modified)) in found {
+            if !folders.iter().any(|f| Path::new(&path).starts_with(f)) {
+                continue;
+            }
+            let known = self.watch.files.get(&path).cloned();
+            if known
+                .as_ref()
+                .is_some_and(|f| f.size == size && f.modified == modified)
+            {
+                continue;
+            }
+
+            let file_name = Path::new(&path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            let mut file = WatchedFile {
+                book_id: String::new(),
+                size,
+                modified,
+            };
+
+            if known.is_none() {
+                // Ukuran & waktu ubah sama dengan file yang hilang: dipindah atau diganti nama
+                let moved = gone.iter().position(|(_, f)| {
+                    f.size == size && f.modified == modified && !f.book_id.is_empty()
+                });
+                if let Some(index) = moved {
+                    let (_, file) = gone.remove(index);
+                    if let Some(book) = self.books.iter_mut().find(|b| b.id == file.book_id) {
+                        book.file_name = file_name;
+                        self.sync.track(RecordKind::Book, &file.book_id);
+                    }
+                    self.watch.files.insert(path, file);
+                    report.renamed += 1;
+                    continue;
+                }
+
+                // Sudah ada di library (nama & ukuran sama): cukup dihubungkan
+                if let Some(book) = self.find_duplicate(&file_name, size) {
+                    file.book_id = book.id.clone();
+                    self.watch.files.insert(path, file);
+                    report.linked += 1;
+                    continue;
+                }
+            }
+
+            if let Some(book_id) =
+                (known.map(|f| f.book_id)).filter(|id| self.get_book(id).is_some())
+            {
+                file.book_id = book_id;
+            }
+            items.push(ImportItem::Watched(WatchedItem {
+                path: PathBuf::from(path),
+                file,
+            }));
+        }
+        report.removed = gone.len();
+
+        if !report.is_empty() {
+            self.save_to_storage()?;
+            info!("{}", report);
+        }
+        Ok((report, items))
+    }
+
+    /// A changed watched file was stored for its book by the import queue
+    #[cfg(not(target_arch = "wasm32"))]
+    fn replace_book_file(
+        &mut self,
+        book_id: &str,
+        blob: String,
+        size: u64,
+    ) -> Result<String, String> {
+        let Some(book) = self.books.iter_mut().find(|b| b.id == book_id) else {
+            let _ = remove_blob(&blob);
+            return Err("Book not found".to_string());
+        };
+
+        // Ekstensi file bisa berubah; blob lama jangan sampai tertinggal
+        if let Some(old) = book
+            .blob
+            .replace(blob)
+            .filter(|old| Some(old) != book.blob.as_ref())
+        {
+            let _ = remove_blob(&old);
+        }
+        book.size = size;
+        book.file_data = String::new();
+        let title = book.title.clone();
+
+        self.sync.track(RecordKind::Book, book_id);
+        info!("Updated the file of '{}'", title);
+        Ok(title)
+    }
 }
 
 impl Default for Library {
@@ -162,3 +1973,40 @@ impl Default for Library {
         Self::new()
     }
 }
+
+/// Preferences that belong to this device and never leave it: the WebDAV
+/// folder, the sync server and their credentials
+fn is_device_preference(key: &str) -> bool {
+    [
+        WEBDAV_URL_KEY,
+        WEBDAV_USERNAME_KEY,
+        WEBDAV_PASSWORD_KEY,
+        SYNC_SERVER_URL_KEY,
+        SYNC_USERNAME_KEY,
+        SYNC_KEY_KEY,
+    ]
+    .contains(&key)
+}
+
+/// Id of the book among `candidates` (from [`Library::books_with_size`])
+/// whose file has the same content as the file at `path`. Reads and hashes
+/// whole files, so keep it off the UI thread.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn find_same_file(path: &Path, candidates: &[BookMetadata]) -> Result<Option<String>, String> {
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    let hash = hash_file(path)?;
+    Ok((candidates.iter())
+        .find(|b| book_hash(b).is_ok_and(|h| h == hash))
+        .map(|b| b.id.clone()))
+}
+
+/// SHA-256 of a book's file, wherever it's stored
+#[cfg(not(target_arch = "wasm32"))]
+fn book_hash(book: &BookMetadata) -> Result<[u8; 32], String> {
+    match &book.blob {
+        Some(blob) => hash_file(&blob_path(blob)?),
+        None => Ok(Sha256::digest(decode_data_url(&book.file_data)?).into()),
+    }
+}
@@ -1,20 +1,298 @@
+use crate::blobs::{open_blob, read_blob};
+use crate::covers::placeholder_cover;
+use crate::utils::decode_data_url;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct BookMetadata {
     pub id: String,
     pub title: String,
-    pub author: String,
+    pub authors: Vec<Author>,
+    pub series: Option<Series>,
+    pub tags: Vec<String>,
+    pub rating: Option<u8>, // 0-10 seperti Calibre (2 = satu bintang)
+    pub identifiers: BTreeMap<String, String>, // "isbn" -> "978...", "google" -> ...
     pub file_name: String,
     pub format: BookFormat,
     pub size: u64,
     pub file_data: String, // base64 data URL
-    pub cover_image: Option<String>,
+    /// File name in the blob store when the book was imported from disk;
+    /// `file_data` is empty then. Local to this device, never synced.
+    #[serde(default)]
+    pub blob: Option<String>,
+    pub cover_image: Option<String>, // thumbnail data URL
     pub last_read_position: usize,
     pub total_pages: usize,
     pub added_date: String,
 }
 
+impl BookMetadata {
+    /// Nama penulis utama untuk ditampilkan, digabung dengan " & "
+    pub fn author_display(&self) -> String {
+        let primary: Vec<&str> = self
+            .authors
+            .iter()
+            .filter(|a| a.role == AuthorRole::Author)
+            .map(|a| a.name.as_str())
+            .collect();
+
+        if !primary.is_empty() {
+            primary.join(" & ")
+        } else if let Some(first) = self.authors.first() {
+            first.name.clone()
+        } else {
+            "Unknown Author".to_string()
+        }
+    }
+
+    /// A copy without the data URL, which can be the size of the book
+    /// itself; `blob` still points at the file when it has one
+    pub fn metadata_only(&self) -> BookMetadata {
+        BookMetadata {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            authors: self.authors.clone(),
+            series: self.series.clone(),
+            tags: self.tags.clone(),
+            rating: self.rating,
+            identifiers: self.identifiers.clone(),
+            file_name: self.file_name.clone(),
+            format: self.format.clone(),
+            size: self.size,
+            file_data: String::new(),
+            blob: self.blob.clone(),
+            cover_image: self.cover_image.clone(),
+            last_read_position: self.last_read_position,
+            total_pages: self.total_pages,
+            added_date: self.added_date.clone(),
+        }
+    }
+
+    /// Whether this device has the book's file
+    pub fn has_file(&self) -> bool {
+        self.blob.is_some() || !self.file_data.is_empty()
+    }
+
+    /// The book file, from the blob store or the data URL
+    pub fn read_file(&self) -> Result<Vec<u8>, String> {
+        match &self.blob {
+            Some(blob) => read_blob(blob),
+            None => decode_data_url(&self.file_data),
+        }
+    }
+
+    /// The book file as a stream, so large files aren't loaded at once;
+    /// only data URLs (web, older imports) are decoded into memory
+    pub fn open_file(&self) -> Result<Box<dyn Read + Send>, String> {
+        match &self.blob {
+            Some(blob) => Ok(Box::new(open_blob(blob)?)),
+            None => Ok(Box::new(Cursor::new(decode_data_url(&self.file_data)?))),
+        }
+    }
+
+    /// Case-insensitive match on the title or any author's name
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.title.to_lowercase().contains(&query)
+            || self
+                .authors
+                .iter()
+                .any(|a| a.name.to_lowercase().contains(&query))
+    }
+
+    /// Thumbnail cover, or a generated placeholder when the book has none
+    pub fn cover_src(&self) -> String {
+        self.cover_image
+            .clone()
+            .unwrap_or_else(|| placeholder_cover(&self.title, &self.author_display()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub index: f64,
+}
+
+/// Highlight atau catatan pada posisi tertentu di buku
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub id: String,
+    pub book_id: String,
+    pub position: usize,
+    pub text: String,
+    pub note: Option<String>,
+    pub created_date: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub id: String,
+    pub book_id: String,
+    pub position: usize,
+    pub label: Option<String>,
+    pub created_date: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Author {
+    pub name: String,
+    pub sort_name: String,
+    pub role: AuthorRole,
+}
+
+impl Author {
+    pub fn new(name: &str, role: AuthorRole) -> Self {
+        let name = name.trim().to_string();
+        let sort_name = Self::derive_sort_name(&name);
+        Self {
+            name,
+            sort_name,
+            role,
+        }
+    }
+
+    /// "Ibn Hajar al-Asqalani" -> "al-Asqalani, Ibn Hajar",
+    /// "Ismail ibn Kathir" -> "ibn Kathir, Ismail".
+    /// Nama yang sudah mengandung koma dianggap sudah dalam bentuk sort name.
+    pub fn derive_sort_name(name: &str) -> String {
+        if name.contains(',') {
+            return name.to_string();
+        }
+
+        // Partikel di depan kata terakhir ikut nama belakang; nama yang
+        // tinggal partikel saja ("Ibn Kathir", "Abu Hurairah") tidak dibalik
+        let words: Vec<&str> = name.split_whitespace().collect();
+        let is_particle = |word: &str| SURNAME_PARTICLES.contains(&word.to_lowercase().as_str());
+        let mut split = words.len().saturating_sub(1);
+        while split > 0 && is_particle(words[split - 1]) {
+            split -= 1;
+        }
+
+        match split {
+            0 => name.to_string(),
+            _ => format!("{}, {}", words[split..].join(" "), words[..split].join(" ")),
+        }
+    }
+}
+
+/// Words that belong to the surname they come before
+const SURNAME_PARTICLES: &[&str] = &[
+    "ibn", "bin", "bint", "abu", "abi", "umm", "van", "von", "de", "der", "del", "della", "di",
+    "da", "du", "le", "la",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AuthorRole {
+    #[default]
+    Author,
+    Editor,
+    Translator,
+    /// Sharih (pensyarah) of a classical text
+    Commentator,
+    /// Any other contributor: illustrator, muhaqqiq without a code of its own, ...
+    Other,
+}
+
+impl std::fmt::Display for AuthorRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthorRole::Author => write!(f, "Author"),
+            AuthorRole::Editor => write!(f, "Editor"),
+            AuthorRole::Translator => write!(f, "Translator"),
+            AuthorRole::Commentator => write!(f, "Commentator"),
+            AuthorRole::Other => write!(f, "Contributor"),
+        }
+    }
+}
+
+impl AuthorRole {
+    /// Map MARC relator codes (used by OPF `opf:role`) to a role. Codes
+    /// without a role of their own are [`AuthorRole::Other`]; only `bkp`
+    /// (book producer, the software that made the file) is not a person.
+    pub fn from_marc_relator(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "aut" => Some(AuthorRole::Author),
+            "edt" => Some(AuthorRole::Editor),
+            "trl" => Some(AuthorRole::Translator),
+            "cmm" | "ann" => Some(AuthorRole::Commentator),
+            "bkp" => None,
+            _ => Some(AuthorRole::Other),
+        }
+    }
+
+    pub fn to_marc_relator(&self) -> &str {
+        match self {
+            AuthorRole::Author => "aut",
+            AuthorRole::Editor => "edt",
+            AuthorRole::Translator => "trl",
+            AuthorRole::Commentator => "cmm",
+            AuthorRole::Other => "oth",
+        }
+    }
+}
+
+/// Satu baris di authors index: seorang penulis dan buku-bukunya
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorEntry {
+    pub name: String,
+    pub sort_name: String,
+    pub books: Vec<AuthoredBook>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthoredBook {
+    pub book_id: String,
+    pub title: String,
+    pub role: AuthorRole,
+}
+
+/// Result of importing one file: the new book's title, or why it was skipped
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportOutcome {
+    pub file_name: String,
+    pub result: Result<String, String>,
+}
+
+/// How far a background folder import has got
+#[derive(Clone, Debug, Default)]
+pub struct ImportProgress {
+    /// Book files found; 0 while the folders are still being searched
+    pub total: usize,
+    pub done: usize,
+    /// File being imported right now
+    pub current: Option<String>,
+    /// Result for every file handled so far
+    pub outcomes: Vec<ImportOutcome>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ImportProgress {
+    pub fn new(cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            cancel,
+            ..Self::default()
+        }
+    }
+
+    pub fn errors(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.result.is_err()).count()
+    }
+
+    /// Stop after the file being imported now; books added so far stay
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum BookFormat {
     EPUB,
@@ -42,6 +320,14 @@ impl BookFormat {
         }
     }
 
+    pub fn mime_type(&self) -> &str {
+        match self {
+            BookFormat::EPUB => "application/epub+zip",
+            BookFormat::PDF => "application/pdf",
+            BookFormat::TXT => "text/plain",
+        }
+    }
+
     pub fn to_extension(&self) -> &str {
         match self {
             BookFormat::EPUB => "epub",
@@ -50,3 +336,56 @@ impl BookFormat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_names_put_the_surname_first() {
+        let cases = [
+            ("Ibn Hajar al-Asqalani", "al-Asqalani, Ibn Hajar"),
+            ("Ismail ibn Kathir", "ibn Kathir, Ismail"),
+            ("Ludwig van Beethoven", "van Beethoven, Ludwig"),
+            ("Abd al-Rahman ibn Abi Bakr", "ibn Abi Bakr, Abd al-Rahman"),
+            // Sudah "Nama Belakang, Nama Depan"
+            ("Nawawi, Yahya ibn Sharaf", "Nawawi, Yahya ibn Sharaf"),
+            // Hanya partikel dan satu nama: tidak dibalik
+            ("Ibn Kathir", "Ibn Kathir"),
+            ("Abu Hurairah", "Abu Hurairah"),
+            ("Bukhari", "Bukhari"),
+        ];
+        for (name, sort_name) in cases {
+            assert_eq!(Author::derive_sort_name(name), sort_name, "{name}");
+        }
+        assert_eq!(
+            Author::new("  Malik ibn Anas ", AuthorRole::Author).sort_name,
+            "ibn Anas, Malik"
+        );
+    }
+
+    #[test]
+    fn marc_relators_round_trip() {
+        for role in [
+            AuthorRole::Author,
+            AuthorRole::Editor,
+            AuthorRole::Translator,
+            AuthorRole::Commentator,
+            AuthorRole::Other,
+        ] {
+            assert_eq!(
+                AuthorRole::from_marc_relator(role.to_marc_relator()),
+                Some(role)
+            );
+        }
+        assert_eq!(
+            AuthorRole::from_marc_relator(" ANN "),
+            Some(AuthorRole::Commentator)
+        );
+        assert_eq!(
+            AuthorRole::from_marc_relator("ill"),
+            Some(AuthorRole::Other)
+        );
+        assert_eq!(AuthorRole::from_marc_relator("bkp"), None);
+    }
+}
@@ -0,0 +1,1075 @@
+use crate::file_picker::{detect_book_format, FileSelection};
+use crate::formats::opf::parse_xml;
+use crate::utils::{encode_data_url, escape_xml};
+use reqwest::header::{ACCEPT, CONTENT_DISPOSITION, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, StatusCode, Url};
+use roxmltree::Node;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write;
+use tracing::info;
+
+/// =======================
+/// OPDS
+/// =======================
+///
+/// Katalog OPDS 1.2 (Atom XML) dan 2.0 (JSON), misalnya content server
+/// Calibre (`http://localhost:8080/opds`). Keduanya dibaca ke model yang
+/// sama: daftar navigasi (sub-katalog) dan daftar buku yang bisa diunduh,
+/// plus link halaman & pencarian OpenSearch.
+const ACCEPT_FEEDS: &str = "application/opds+json, application/atom+xml;q=0.9, \
+     application/xml;q=0.8, */*;q=0.5";
+
+const REL_ACQUISITION: &str = "http://opds-spec.org/acquisition";
+const REL_IMAGE: &str = "http://opds-spec.org/image";
+const REL_THUMBNAIL: &str = "http://opds-spec.org/image/thumbnail";
+const CATALOG_TYPE: &str = "application/atom+xml;profile=opds-catalog";
+
+/// Port of the desktop OPDS server unless the user picks another
+pub const DEFAULT_SERVER_PORT: u16 = 8585;
+
+/// Placeholder in [`SearchLink::Template`], as in OpenSearch
+const SEARCH_TERMS: &str = "{searchTerms}";
+
+/// =======================
+/// Data Model
+/// =======================
+///
+/// A saved catalog, shown in the Discover view
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OpdsCatalog {
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+/// The desktop app's own OPDS server, for e-readers on the same network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpdsServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for OpdsServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_SERVER_PORT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpdsFeed {
+    pub title: String,
+    /// Absolute URL the feed was loaded from; links below are resolved against it
+    pub url: String,
+    /// Sub-catalogs (OPDS navigation feeds, or groups in OPDS 2.0)
+    pub navigation: Vec<NavigationLink>,
+    /// Books (OPDS acquisition feeds)
+    pub entries: Vec<OpdsEntry>,
+    pub next: Option<String>,
+    pub previous: Option<String>,
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub search: Option<SearchLink>,
+    /// Number of results across all pages, when the server says
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavigationLink {
+    pub title: String,
+    pub href: String,
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchLink {
+    /// URL with `{searchTerms}` where the query goes
+    Template(String),
+    /// OpenSearch description document that holds the template
+    Description(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpdsEntry {
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub summary: Option<String>,
+    pub language: Option<String>,
+    pub cover: Option<String>,
+    pub thumbnail: Option<String>,
+    pub acquisitions: Vec<Acquisition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Acquisition {
+    pub href: String,
+    pub mime_type: String,
+    pub kind: AcquisitionKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcquisitionKind {
+    /// Plain download (`acquisition` / `acquisition/open-access`)
+    Download,
+    Sample,
+    /// Borrow, buy or subscribe; needs the catalog's own website
+    Other,
+}
+
+impl OpdsEntry {
+    /// The download we'd import: a free one in a format we can read,
+    /// EPUB before PDF before plain text
+    pub fn best_download(&self) -> Option<&Acquisition> {
+        let rank = |mime: &str| match detect_book_format(mime, "").as_deref() {
+            Some("epub") => Some(0),
+            Some("pdf") => Some(1),
+            Some("txt") => Some(2),
+            _ => None,
+        };
+        self.acquisitions
+            .iter()
+            .filter(|a| a.kind == AcquisitionKind::Download)
+            .filter_map(|a| rank(&a.mime_type).map(|r| (r, a)))
+            .min_by_key(|(r, _)| *r)
+            .map(|(_, a)| a)
+    }
+}
+
+/// =======================
+/// Client
+/// =======================
+
+#[derive(Debug, Clone)]
+pub struct OpdsClient {
+    catalog: OpdsCatalog,
+    http: Client,
+}
+
+impl OpdsClient {
+    pub fn new(catalog: OpdsCatalog) -> Self {
+        Self {
+            catalog,
+            http: Client::new(),
+        }
+    }
+
+    /// The catalog's root feed
+    pub async fn root(&self) -> Result<OpdsFeed, String> {
+        self.fetch(&self.catalog.url.clone()).await
+    }
+
+    /// Any feed of this catalog: a navigation link, or a page link
+    pub async fn fetch(&self, url: &str) -> Result<OpdsFeed, String> {
+        let response = self
+            .request(url)?
+            .header(ACCEPT, ACCEPT_FEEDS)
+            .send()
+            .await
+            .map_err(network_error)?;
+        let response = check_status(response)?;
+
+        let final_url = response.url().to_string();
+        let bytes = response.bytes().await.map_err(network_error)?;
+        parse_feed(&bytes, &final_url)
+    }
+
+    /// Run a search through the feed's OpenSearch link
+    pub async fn search(&self, feed: &OpdsFeed, query: &str) -> Result<OpdsFeed, String> {
+        let template = match &feed.search {
+            Some(SearchLink::Template(template)) => template.clone(),
+            Some(SearchLink::Description(url)) => {
+                let response = self.request(url)?.send().await.map_err(network_error)?;
+                let text = check_status(response)?
+                    .text()
+                    .await
+                    .map_err(network_error)?;
+                let template = parse_opensearch(&text)
+                    .ok_or("The catalog's search description has no usable URL")?;
+                resolve(url, &template).ok_or("Invalid search URL")?
+            }
+            None => return Err("This catalog doesn't support search".to_string()),
+        };
+
+        self.fetch(&search_url(&template, query)).await
+    }
+
+    /// Download an entry's best file, ready for
+    /// [`Library::add_book_from_file`](crate::library::Library::add_book_from_file)
+    pub async fn download(&self, entry: &OpdsEntry) -> Result<FileSelection, String> {
+        let acquisition = entry
+            .best_download()
+            .ok_or("No free EPUB, PDF or text download for this book")?;
+        info!("Downloading '{}' from {}", entry.title, acquisition.href);
+
+        let response = self
+            .request(&acquisition.href)?
+            .send()
+            .await
+            .map_err(network_error)?;
+        let response = check_status(response)?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        // Server kadang mengirim application/octet-stream; tipe di feed lebih bisa dipercaya
+        let mime_type = header(CONTENT_TYPE)
+            .map(|t| t.split(';').next().unwrap_or_default().trim().to_string())
+            .filter(|t| detect_book_format(t, "").is_some())
+            .unwrap_or_else(|| acquisition.mime_type.clone());
+        let name = header(CONTENT_DISPOSITION)
+            .and_then(|d| disposition_file_name(&d))
+            .unwrap_or_else(|| file_name_for(entry, &mime_type));
+
+        let bytes = response.bytes().await.map_err(network_error)?;
+        Ok(FileSelection {
+            data: encode_data_url(&mime_type, &bytes),
+            size: bytes.len() as u64,
+            r#type: mime_type,
+            name,
+        })
+    }
+
+    /* =========================
+       HTTP HELPERS
+    ========================= */
+
+    fn request(&self, url: &str) -> Result<RequestBuilder, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid URL '{url}': {e}"))?;
+        let builder = self.http.get(url);
+        Ok(if self.catalog.username.is_empty() {
+            builder
+        } else {
+            builder.basic_auth(&self.catalog.username, Some(&self.catalog.password))
+        })
+    }
+}
+
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response, String> {
+    match response.status() {
+        s if s.is_success() => Ok(response),
+        StatusCode::UNAUTHORIZED => Err("OPDS: wrong username or password".to_string()),
+        StatusCode::NOT_FOUND => Err("OPDS: not found".to_string()),
+        status => Err(format!("OPDS server returned {status}")),
+    }
+}
+
+fn network_error(e: reqwest::Error) -> String {
+    format!("OPDS error: {e}")
+}
+
+/// =======================
+/// Parsing
+/// =======================
+///
+/// Parse an OPDS 1.2 or 2.0 feed; `url` is where it came from
+pub fn parse_feed(bytes: &[u8], url: &str) -> Result<OpdsFeed, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "The feed is not valid UTF-8")?;
+    if text.trim_start().starts_with('{') {
+        let json: Value =
+            serde_json::from_str(text).map_err(|e| format!("Invalid OPDS 2.0 feed: {e}"))?;
+        Ok(parse_json_feed(&json, url))
+    } else {
+        parse_atom_feed(text, url)
+    }
+}
+
+/// Search URL template from an OpenSearch description document,
+/// preferring the one that returns an OPDS feed
+pub fn parse_opensearch(xml: &str) -> Option<String> {
+    let doc = parse_xml(xml).ok()?;
+    let urls: Vec<Node> = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "Url" && n.attribute("template").is_some())
+        .collect();
+    let preferred = urls
+        .iter()
+        .find(|n| {
+            n.attribute("type")
+                .is_some_and(|t| t.contains("atom") || t.contains("opds"))
+        })
+        .or(urls.first())?;
+    preferred.attribute("template").map(str::to_string)
+}
+
+/// Fill in a `{searchTerms}` template; other OpenSearch parameters are optional
+pub fn search_url(template: &str, query: &str) -> String {
+    let mut url = template.replace(SEARCH_TERMS, &encode_query(query));
+    while let Some(start) = url.find('{') {
+        match url[start..].find('}') {
+            Some(end) => url.replace_range(start..start + end + 1, ""),
+            None => break,
+        }
+    }
+    url
+}
+
+/* =========================
+   OPDS 1.2 (ATOM)
+========================= */
+
+fn parse_atom_feed(text: &str, url: &str) -> Result<OpdsFeed, String> {
+    let doc = parse_xml(text).map_err(|e| format!("Invalid OPDS feed: {e}"))?;
+    let root = doc.root_element();
+    let mut feed = OpdsFeed {
+        url: url.to_string(),
+        ..OpdsFeed::default()
+    };
+
+    // Dokumen satu entry (OPDS "complete entry")
+    if root.tag_name().name() == "entry" {
+        feed.title = child_text(root, "title").unwrap_or_default();
+        read_atom_entry(root, url, &mut feed);
+        return Ok(feed);
+    }
+    if root.tag_name().name() != "feed" {
+        return Err("Not an OPDS feed".to_string());
+    }
+
+    feed.title = child_text(root, "title").unwrap_or_default();
+    feed.total = child_text(root, "totalResults").and_then(|t| t.parse().ok());
+
+    for link in root.children().filter(|n| n.tag_name().name() == "link") {
+        let (Some(rel), Some(href)) = (link.attribute("rel"), link.attribute("href")) else {
+            continue;
+        };
+        let Some(href) = resolve(url, href) else {
+            continue;
+        };
+        let kind = link.attribute("type").unwrap_or_default();
+        match rel {
+            "next" => feed.next = Some(href),
+            "previous" | "prev" => feed.previous = Some(href),
+            "first" => feed.first = Some(href),
+            "last" => feed.last = Some(href),
+            "search" if kind.contains("opensearchdescription") => {
+                feed.search = Some(SearchLink::Description(href))
+            }
+            // Template langsung; jangan ditimpa oleh link description
+            "search" if href.contains(SEARCH_TERMS) && feed.search.is_none() => {
+                feed.search = Some(SearchLink::Template(href))
+            }
+            _ => {}
+        }
+    }
+
+    for entry in root.children().filter(|n| n.tag_name().name() == "entry") {
+        read_atom_entry(entry, url, &mut feed);
+    }
+
+    Ok(feed)
+}
+
+/// A book, or (without acquisition links) a link to a sub-catalog
+fn read_atom_entry(node: Node, url: &str, feed: &mut OpdsFeed) {
+    let mut entry = OpdsEntry {
+        id: child_text(node, "id").unwrap_or_default(),
+        title: child_text(node, "title").unwrap_or_default(),
+        authors: node
+            .children()
+            .filter(|n| n.tag_name().name() == "author")
+            .filter_map(|a| child_text(a, "name"))
+            .collect(),
+        summary: child_text(node, "summary").or_else(|| child_text(node, "content")),
+        language: child_text(node, "language"),
+        ..OpdsEntry::default()
+    };
+    let mut catalog_link = None;
+
+    for link in node.children().filter(|n| n.tag_name().name() == "link") {
+        let Some(href) = link.attribute("href").and_then(|h| resolve(url, h)) else {
+            continue;
+        };
+        let rel = link.attribute("rel").unwrap_or_default();
+        let kind = link.attribute("type").unwrap_or_default();
+
+        if rel.starts_with(REL_ACQUISITION) {
+            entry.acquisitions.push(Acquisition {
+                href,
+                mime_type: kind.to_string(),
+                kind: acquisition_kind(rel),
+            });
+        } else if rel == REL_THUMBNAIL || rel == "x-stanza-cover-image-thumbnail" {
+            entry.thumbnail = Some(href);
+        } else if rel == REL_IMAGE || rel == "x-stanza-cover-image" {
+            entry.cover = Some(href);
+        } else if kind.contains("profile=opds-catalog") || rel == "subsection" {
+            catalog_link.get_or_insert(href);
+        }
+    }
+
+    match catalog_link {
+        Some(href) if entry.acquisitions.is_empty() => feed.navigation.push(NavigationLink {
+            title: entry.title,
+            href,
+            summary: entry.summary,
+        }),
+        _ => feed.entries.push(entry),
+    }
+}
+
+fn acquisition_kind(rel: &str) -> AcquisitionKind {
+    match rel.strip_prefix(REL_ACQUISITION).unwrap_or_default() {
+        "" | "/open-access" => AcquisitionKind::Download,
+        "/sample" | "/preview" => AcquisitionKind::Sample,
+        _ => AcquisitionKind::Other,
+    }
+}
+
+/// Teks dari child langsung (termasuk konten XHTML bersarang)
+fn child_text(node: Node, name: &str) -> Option<String> {
+    let child = node.children().find(|n| n.tag_name().name() == name)?;
+    let text: String = child
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/* =========================
+   OPDS 2.0 (JSON)
+========================= */
+
+fn parse_json_feed(json: &Value, url: &str) -> OpdsFeed {
+    let metadata = &json["metadata"];
+    let mut feed = OpdsFeed {
+        title: metadata["title"].as_str().unwrap_or_default().to_string(),
+        url: url.to_string(),
+        total: metadata["numberOfItems"].as_u64(),
+        ..OpdsFeed::default()
+    };
+
+    for link in array(&json["links"]) {
+        let Some(href) = link["href"].as_str().and_then(|h| resolve(url, h)) else {
+            continue;
+        };
+        for rel in rels(link) {
+            match rel {
+                "next" => feed.next = Some(href.clone()),
+                "previous" | "prev" => feed.previous = Some(href.clone()),
+                "first" => feed.first = Some(href.clone()),
+                "last" => feed.last = Some(href.clone()),
+                "search" => {
+                    feed.search = Some(match link["templated"].as_bool() {
+                        Some(true) => SearchLink::Template(uri_template_to_search(&href)),
+                        _ if link["type"]
+                            .as_str()
+                            .is_some_and(|t| t.contains("opensearchdescription")) =>
+                        {
+                            SearchLink::Description(href.clone())
+                        }
+                        _ => SearchLink::Template(href.clone()),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    read_json_collection(json, url, &mut feed);
+    for group in array(&json["groups"]) {
+        // Grup dengan link "self" punya halaman sendiri yang lebih lengkap
+        let title = group["metadata"]["title"].as_str().unwrap_or_default();
+        let more = array(&group["links"])
+            .iter()
+            .find(|l| rels(l).any(|r| r == "self"))
+            .and_then(|l| l["href"].as_str())
+            .and_then(|h| resolve(url, h));
+        if let Some(href) = more {
+            feed.navigation.push(NavigationLink {
+                title: title.to_string(),
+                href,
+                summary: None,
+            });
+        }
+        read_json_collection(group, url, &mut feed);
+    }
+
+    feed
+}
+
+fn read_json_collection(json: &Value, url: &str, feed: &mut OpdsFeed) {
+    for link in array(&json["navigation"]) {
+        if let Some(href) = link["href"].as_str().and_then(|h| resolve(url, h)) {
+            feed.navigation.push(NavigationLink {
+                title: link["title"].as_str().unwrap_or(&href).to_string(),
+                href,
+                summary: None,
+            });
+        }
+    }
+    for publication in array(&json["publications"]) {
+        feed.entries.push(read_publication(publication, url));
+    }
+}
+
+fn read_publication(json: &Value, url: &str) -> OpdsEntry {
+    let metadata = &json["metadata"];
+    let mut entry = OpdsEntry {
+        id: metadata["identifier"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        title: metadata["title"].as_str().unwrap_or_default().to_string(),
+        authors: contributors(&metadata["author"]),
+        summary: metadata["description"].as_str().map(str::to_string),
+        language: match &metadata["language"] {
+            Value::String(lang) => Some(lang.clone()),
+            Value::Array(langs) => langs.first().and_then(Value::as_str).map(str::to_string),
+            _ => None,
+        },
+        ..OpdsEntry::default()
+    };
+
+    for link in array(&json["links"]) {
+        let Some(href) = link["href"].as_str().and_then(|h| resolve(url, h)) else {
+            continue;
+        };
+        if let Some(rel) = rels(link).find(|r| r.starts_with(REL_ACQUISITION)) {
+            entry.acquisitions.push(Acquisition {
+                href,
+                mime_type: link["type"].as_str().unwrap_or_default().to_string(),
+                kind: acquisition_kind(rel),
+            });
+        }
+    }
+
+    // Gambar pertama sebagai cover, yang terkecil sebagai thumbnail
+    let images: Vec<(u64, String)> = array(&json["images"])
+        .iter()
+        .filter_map(|image| {
+            let href = resolve(url, image["href"].as_str()?)?;
+            Some((image["width"].as_u64().unwrap_or(u64::MAX), href))
+        })
+        .collect();
+    entry.cover = images.first().map(|(_, href)| href.clone());
+    entry.thumbnail = images
+        .iter()
+        .min_by_key(|(width, _)| *width)
+        .map(|(_, href)| href.clone());
+
+    entry
+}
+
+/// `"Name"`, `{ "name": "Name" }`, or an array of either
+fn contributors(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(name) => vec![name.clone()],
+        Value::Object(_) => value["name"]
+            .as_str()
+            .map(|n| vec![n.to_string()])
+            .unwrap_or_default(),
+        Value::Array(items) => items.iter().flat_map(contributors).collect(),
+        _ => vec![],
+    }
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// `rel` is a string or an array of strings
+fn rels(link: &Value) -> impl Iterator<Item = &str> {
+    let rel = &link["rel"];
+    rel.as_str()
+        .into_iter()
+        .chain(array(rel).iter().filter_map(Value::as_str))
+}
+
+/// OPDS 2.0 search links are URI templates (`/search{?query,title}`);
+/// only `query` is filled in
+fn uri_template_to_search(template: &str) -> String {
+    match template.find("{?") {
+        Some(start) => {
+            let end = template[start..]
+                .find('}')
+                .map_or(template.len(), |e| start + e + 1);
+            let separator = if template[..start].contains('?') {
+                '&'
+            } else {
+                '?'
+            };
+            format!(
+                "{}{}query={}{}",
+                &template[..start],
+                separator,
+                SEARCH_TERMS,
+                &template[end..]
+            )
+        }
+        None => template.replace("{query}", SEARCH_TERMS),
+    }
+}
+
+/// =======================
+/// Writing
+/// =======================
+///
+/// Feed OPDS 1.2 untuk katalog yang kita sajikan sendiri (server OPDS di
+/// desktop). Link ditulis apa adanya, biasanya path absolut seperti
+/// `/opds/books`; `feed.url` jadi link `self` dan id feed.
+pub fn write_atom_feed(feed: &OpdsFeed, start: &str, updated: &str) -> String {
+    let kind = if feed.entries.is_empty() {
+        "navigation"
+    } else {
+        "acquisition"
+    };
+    let link = |xml: &mut String, rel: &str, kind: &str, href: &str| {
+        let _ = writeln!(
+            xml,
+            r#"  <link rel="{}" type="{}" href="{}"/>"#,
+            rel,
+            kind,
+            escape_xml(href)
+        );
+    };
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\" \
+         xmlns:dc=\"http://purl.org/dc/terms/\" \
+         xmlns:opensearch=\"http://a9.com/-/spec/opensearch/1.1/\">\n",
+    );
+    let _ = writeln!(xml, "  <id>{}</id>", escape_xml(&feed.url));
+    let _ = writeln!(xml, "  <title>{}</title>", escape_xml(&feed.title));
+    let _ = writeln!(xml, "  <updated>{}</updated>", escape_xml(updated));
+    if let Some(total) = feed.total {
+        let _ = writeln!(
+            xml,
+            "  <opensearch:totalResults>{total}</opensearch:totalResults>"
+        );
+    }
+
+    let page_type = format!("{CATALOG_TYPE};kind={kind}");
+    link(&mut xml, "self", &page_type, &feed.url);
+    link(
+        &mut xml,
+        "start",
+        &format!("{CATALOG_TYPE};kind=navigation"),
+        start,
+    );
+    for (rel, href) in [
+        ("first", &feed.first),
+        ("previous", &feed.previous),
+        ("next", &feed.next),
+        ("last", &feed.last),
+    ] {
+        if let Some(href) = href {
+            link(&mut xml, rel, &page_type, href);
+        }
+    }
+    match &feed.search {
+        Some(SearchLink::Description(href)) => link(
+            &mut xml,
+            "search",
+            "application/opensearchdescription+xml",
+            href,
+        ),
+        Some(SearchLink::Template(href)) => link(&mut xml, "search", "application/atom+xml", href),
+        None => {}
+    }
+
+    for nav in &feed.navigation {
+        xml.push_str("  <entry>\n");
+        let _ = writeln!(xml, "    <title>{}</title>", escape_xml(&nav.title));
+        let _ = writeln!(xml, "    <id>{}</id>", escape_xml(&nav.href));
+        let _ = writeln!(xml, "    <updated>{}</updated>", escape_xml(updated));
+        if let Some(summary) = &nav.summary {
+            let _ = writeln!(
+                xml,
+                r#"    <content type="text">{}</content>"#,
+                escape_xml(summary)
+            );
+        }
+        let _ = writeln!(
+            xml,
+            r#"    <link rel="subsection" type="{}" href="{}"/>"#,
+            CATALOG_TYPE,
+            escape_xml(&nav.href)
+        );
+        xml.push_str("  </entry>\n");
+    }
+
+    for entry in &feed.entries {
+        write_atom_entry(&mut xml, entry, updated);
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn write_atom_entry(xml: &mut String, entry: &OpdsEntry, updated: &str) {
+    xml.push_str("  <entry>\n");
+    let _ = writeln!(xml, "    <title>{}</title>", escape_xml(&entry.title));
+    let _ = writeln!(xml, "    <id>{}</id>", escape_xml(&entry.id));
+    let _ = writeln!(xml, "    <updated>{}</updated>", escape_xml(updated));
+    for author in &entry.authors {
+        let _ = writeln!(
+            xml,
+            "    <author><name>{}</name></author>",
+            escape_xml(author)
+        );
+    }
+    if let Some(language) = &entry.language {
+        let _ = writeln!(
+            xml,
+            "    <dc:language>{}</dc:language>",
+            escape_xml(language)
+        );
+    }
+    if let Some(summary) = &entry.summary {
+        let _ = writeln!(
+            xml,
+            r#"    <summary type="text">{}</summary>"#,
+            escape_xml(summary)
+        );
+    }
+    for acquisition in &entry.acquisitions {
+        let rel = match acquisition.kind {
+            AcquisitionKind::Download => REL_ACQUISITION.to_string(),
+            AcquisitionKind::Sample => format!("{REL_ACQUISITION}/sample"),
+            AcquisitionKind::Other => format!("{REL_ACQUISITION}/buy"),
+        };
+        let _ = writeln!(
+            xml,
+            r#"    <link rel="{}" type="{}" href="{}"/>"#,
+            rel,
+            escape_xml(&acquisition.mime_type),
+            escape_xml(&acquisition.href)
+        );
+    }
+    for (rel, href) in [(REL_IMAGE, &entry.cover), (REL_THUMBNAIL, &entry.thumbnail)] {
+        if let Some(href) = href {
+            let _ = writeln!(
+                xml,
+                r#"    <link rel="{}" href="{}"/>"#,
+                rel,
+                escape_xml(href)
+            );
+        }
+    }
+    xml.push_str("  </entry>\n");
+}
+
+/// OpenSearch description pointing at a `{searchTerms}` template
+pub fn write_opensearch(title: &str, template: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <OpenSearchDescription xmlns=\"http://a9.com/-/spec/opensearch/1.1/\">\n  \
+         <ShortName>{}</ShortName>\n  \
+         <Description>Search {}</Description>\n  \
+         <Url type=\"{};kind=acquisition\" template=\"{}\"/>\n\
+         </OpenSearchDescription>\n",
+        escape_xml(title),
+        escape_xml(title),
+        CATALOG_TYPE,
+        escape_xml(template)
+    )
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+/// Resolve a link against the feed URL; template braces are kept as they are
+fn resolve(base: &str, href: &str) -> Option<String> {
+    let joined = Url::parse(base).ok()?.join(href).ok()?;
+    Some(joined.to_string().replace("%7B", "{").replace("%7D", "}"))
+}
+
+/// Percent-encode a query value (`+` for spaces)
+pub fn encode_query(query: &str) -> String {
+    query
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Inverse of [`encode_query`]; invalid escapes are kept as they are
+pub fn decode_query(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `attachment; filename="Book.epub"`
+fn disposition_file_name(disposition: &str) -> Option<String> {
+    let value = disposition
+        .split(';')
+        .map(str::trim)
+        .find_map(|part| part.strip_prefix("filename="))?;
+    let name = value.trim_matches('"').rsplit(['/', '\\']).next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// `<title>.<ext>` when the server doesn't name the file
+fn file_name_for(entry: &OpdsEntry, mime_type: &str) -> String {
+    let extension = detect_book_format(mime_type, "").unwrap_or_else(|| "epub".to_string());
+    let title: String = entry
+        .title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let title = title.trim();
+    format!(
+        "{}.{}",
+        if title.is_empty() { "book" } else { title },
+        extension
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACQUISITION_FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:opensearch="http://a9.com/-/spec/opensearch/1.1/">
+  <id>urn:calibre:recent</id>
+  <title>Newest</title>
+  <opensearch:totalResults>41</opensearch:totalResults>
+  <link rel="next" type="application/atom+xml;profile=opds-catalog;kind=acquisition" href="/opds/new?offset=25"/>
+  <link rel="search" type="application/opensearchdescription+xml" href="/opds/search"/>
+  <link rel="search" type="application/atom+xml" href="/opds/search/{searchTerms}"/>
+  <entry>
+    <title>Riyad as-Salihin</title>
+    <id>urn:uuid:1</id>
+    <author><name>An-Nawawi</name></author>
+    <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>Gardens of the righteous</p></div></content>
+    <link rel="http://opds-spec.org/acquisition" type="application/pdf" href="/get/pdf/1"/>
+    <link rel="http://opds-spec.org/acquisition" type="application/epub+zip" href="/get/epub/1"/>
+    <link rel="http://opds-spec.org/acquisition/buy" type="application/epub+zip" href="https://shop.example/1"/>
+    <link rel="http://opds-spec.org/image/thumbnail" type="image/jpeg" href="/thumb/1"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parses_an_acquisition_feed() {
+        let feed = parse_feed(
+            ACQUISITION_FEED.as_bytes(),
+            "http://localhost:8080/opds/new",
+        )
+        .unwrap();
+        assert_eq!(feed.title, "Newest");
+        assert_eq!(feed.total, Some(41));
+        assert_eq!(
+            feed.next.as_deref(),
+            Some("http://localhost:8080/opds/new?offset=25")
+        );
+        assert_eq!(
+            feed.search,
+            Some(SearchLink::Description(
+                "http://localhost:8080/opds/search".to_string()
+            ))
+        );
+
+        let entry = &feed.entries[0];
+        assert_eq!(entry.authors, ["An-Nawawi"]);
+        assert_eq!(entry.summary.as_deref(), Some("Gardens of the righteous"));
+        assert_eq!(
+            entry.thumbnail.as_deref(),
+            Some("http://localhost:8080/thumb/1")
+        );
+        assert_eq!(entry.acquisitions.len(), 3);
+        assert_eq!(
+            entry.best_download().unwrap().href,
+            "http://localhost:8080/get/epub/1"
+        );
+    }
+
+    #[test]
+    fn parses_a_navigation_feed() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Catalog</title>
+  <entry>
+    <title>By Author</title>
+    <id>authors</id>
+    <content type="text">Browse by author</content>
+    <link rel="subsection" type="application/atom+xml;profile=opds-catalog;kind=navigation" href="authors"/>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(xml.as_bytes(), "http://example.org/opds/").unwrap();
+        assert!(feed.entries.is_empty());
+        assert_eq!(
+            feed.navigation,
+            [NavigationLink {
+                title: "By Author".to_string(),
+                href: "http://example.org/opds/authors".to_string(),
+                summary: Some("Browse by author".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_an_opds2_feed() {
+        let json = r#"{
+  "metadata": { "title": "Hadith", "numberOfItems": 2 },
+  "links": [
+    { "rel": "self", "href": "/opds2/hadith", "type": "application/opds+json" },
+    { "rel": "next", "href": "/opds2/hadith?page=2" },
+    { "rel": "search", "href": "/opds2/search{?query,author}", "templated": true }
+  ],
+  "navigation": [ { "href": "/opds2/fiqh", "title": "Fiqh" } ],
+  "publications": [ {
+    "metadata": { "title": "Sahih Muslim", "identifier": "urn:isbn:1",
+                  "author": [ "Muslim", { "name": "Translator" } ], "language": ["ar", "en"] },
+    "links": [ { "rel": "http://opds-spec.org/acquisition/open-access",
+                 "href": "/files/muslim.epub", "type": "application/epub+zip" } ],
+    "images": [ { "href": "/covers/large.jpg", "width": 600 }, { "href": "/covers/small.jpg", "width": 120 } ]
+  } ]
+}"#;
+        let feed = parse_feed(json.as_bytes(), "https://hadith.example/opds2/hadith").unwrap();
+        assert_eq!(feed.title, "Hadith");
+        assert_eq!(feed.navigation[0].href, "https://hadith.example/opds2/fiqh");
+        assert_eq!(
+            feed.next.as_deref(),
+            Some("https://hadith.example/opds2/hadith?page=2")
+        );
+
+        let Some(SearchLink::Template(template)) = &feed.search else {
+            panic!("no search template: {:?}", feed.search);
+        };
+        assert_eq!(
+            search_url(template, "night prayer"),
+            "https://hadith.example/opds2/search?query=night+prayer"
+        );
+
+        let entry = &feed.entries[0];
+        assert_eq!(entry.authors, ["Muslim", "Translator"]);
+        assert_eq!(entry.language.as_deref(), Some("ar"));
+        assert_eq!(
+            entry.cover.as_deref(),
+            Some("https://hadith.example/covers/large.jpg")
+        );
+        assert_eq!(
+            entry.thumbnail.as_deref(),
+            Some("https://hadith.example/covers/small.jpg")
+        );
+        assert!(entry.best_download().is_some());
+    }
+
+    #[test]
+    fn reads_opensearch_descriptions() {
+        let xml = r#"<OpenSearchDescription xmlns="http://a9.com/-/spec/opensearch/1.1/">
+  <Url type="text/html" template="/search.html?q={searchTerms}"/>
+  <Url type="application/atom+xml" template="/opds/search?q={searchTerms}&amp;page={startPage?}"/>
+</OpenSearchDescription>"#;
+        let template = parse_opensearch(xml).unwrap();
+        assert_eq!(template, "/opds/search?q={searchTerms}&page={startPage?}");
+        assert_eq!(
+            search_url(&template, "bukhari"),
+            "/opds/search?q=bukhari&page="
+        );
+    }
+
+    #[test]
+    fn written_feeds_parse_back() {
+        let feed = OpdsFeed {
+            title: "All books".to_string(),
+            url: "/opds/books?page=2".to_string(),
+            navigation: vec![NavigationLink {
+                title: "Fiqh & Usul".to_string(),
+                href: "/opds/collections?name=Fiqh+%26+Usul".to_string(),
+                summary: Some("3 books".to_string()),
+            }],
+            entries: vec![OpdsEntry {
+                id: "urn:uuid:1".to_string(),
+                title: "Bulugh <al-Maram>".to_string(),
+                authors: vec!["Ibn Hajar".to_string()],
+                summary: None,
+                language: Some("ar".to_string()),
+                cover: Some("/opds/books/1/cover".to_string()),
+                thumbnail: Some("/opds/books/1/cover".to_string()),
+                acquisitions: vec![Acquisition {
+                    href: "/opds/books/1/file".to_string(),
+                    mime_type: "application/epub+zip".to_string(),
+                    kind: AcquisitionKind::Download,
+                }],
+            }],
+            previous: Some("/opds/books?page=1".to_string()),
+            search: Some(SearchLink::Description("/opds/opensearch.xml".to_string())),
+            total: Some(51),
+            ..OpdsFeed::default()
+        };
+        let xml = write_atom_feed(&feed, "/opds", "2026-01-01T00:00:00Z");
+
+        let base = "http://192.168.1.5:8585/opds/books?page=2";
+        let parsed = parse_feed(xml.as_bytes(), base).unwrap();
+        let absolute = |path: &str| format!("http://192.168.1.5:8585{path}");
+        assert_eq!(parsed.title, feed.title);
+        assert_eq!(parsed.total, Some(51));
+        assert_eq!(parsed.previous, Some(absolute("/opds/books?page=1")));
+        assert_eq!(
+            parsed.search,
+            Some(SearchLink::Description(absolute("/opds/opensearch.xml")))
+        );
+        assert_eq!(parsed.navigation[0].title, "Fiqh & Usul");
+        assert_eq!(
+            parsed.navigation[0].href,
+            absolute("/opds/collections?name=Fiqh+%26+Usul")
+        );
+
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.title, "Bulugh <al-Maram>");
+        assert_eq!(entry.language.as_deref(), Some("ar"));
+        assert_eq!(
+            entry.best_download().unwrap().href,
+            absolute("/opds/books/1/file")
+        );
+
+        let description = write_opensearch("Bayt Alhikmah", "/opds/search?q={searchTerms}");
+        let template = parse_opensearch(&description).unwrap();
+        assert_eq!(search_url(&template, "sahih"), "/opds/search?q=sahih");
+    }
+
+    #[test]
+    fn query_values_round_trip() {
+        for value in ["night prayer", "صحيح البخاري", "a+b&c=100%"] {
+            assert_eq!(decode_query(&encode_query(value)), value);
+        }
+        assert_eq!(decode_query("50%"), "50%");
+    }
+}
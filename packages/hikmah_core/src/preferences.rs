@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Allowed import limits, in MB
+pub const MAX_IMPORT_MB: RangeInclusive<u32> = 1..=1024;
+
+/// =======================
+/// Preferences
+/// =======================
+///
+/// Pengaturan umum aplikasi, disimpan sebagai JSON di preferences library
+/// (seperti [`crate::reader_settings::ReaderSettings`]). Field yang belum ada
+/// di file lama memakai default.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Preferences {
+    /// Largest book read into memory as a data URL (browser, file picker
+    /// and drops on web); imports from a path on desktop have no limit.
+    /// See [`MAX_IMPORT_MB`].
+    pub max_import_mb: u32,
+    /// Order the library opens in
+    pub default_sort: SortOrder,
+    /// `None` follows the system language
+    pub language: Option<Language>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            max_import_mb: 50,
+            default_sort: SortOrder::Added,
+            language: None,
+        }
+    }
+}
+
+impl Preferences {
+    pub fn validate(&self) -> Result<(), String> {
+        if !MAX_IMPORT_MB.contains(&self.max_import_mb) {
+            return Err(format!(
+                "Import limit must be between {} and {} MB",
+                MAX_IMPORT_MB.start(),
+                MAX_IMPORT_MB.end()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn max_import_bytes(&self) -> u64 {
+        u64::from(self.max_import_mb) * 1024 * 1024
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Title,
+    Author,
+    /// Newest first
+    Added,
+    /// Most recently read first, unread books last
+    LastRead,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [
+        SortOrder::Title,
+        SortOrder::Author,
+        SortOrder::Added,
+        SortOrder::LastRead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Title => "title",
+            SortOrder::Author => "author",
+            SortOrder::Added => "added",
+            SortOrder::LastRead => "last_read",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Title => "Title",
+            SortOrder::Author => "Author",
+            SortOrder::Added => "Recently added",
+            SortOrder::LastRead => "Recently read",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "id")]
+    Indonesian,
+    #[serde(rename = "ar")]
+    Arabic,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::Indonesian, Language::Arabic];
+
+    /// BCP 47 code, also used as the name in the preferences file
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Indonesian => "id",
+            Language::Arabic => "ar",
+        }
+    }
+
+    /// The language for a code like "id" or "ar-EG"
+    pub fn from_code(code: &str) -> Option<Self> {
+        let primary = code.split(['-', '_']).next()?.to_lowercase();
+        Self::ALL.into_iter().find(|l| l.code() == primary)
+    }
+
+    /// Name in the language itself
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Indonesian => "Bahasa Indonesia",
+            Language::Arabic => "العربية",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_limit_must_be_in_range() {
+        let with_limit = |max_import_mb| Preferences {
+            max_import_mb,
+            ..Preferences::default()
+        };
+        assert!(Preferences::default().validate().is_ok());
+        assert!(with_limit(*MAX_IMPORT_MB.start()).validate().is_ok());
+        assert!(with_limit(*MAX_IMPORT_MB.end()).validate().is_ok());
+        assert!(with_limit(0).validate().is_err());
+        assert!(with_limit(MAX_IMPORT_MB.end() + 1).validate().is_err());
+
+        assert_eq!(with_limit(2).max_import_bytes(), 2 * 1024 * 1024);
+    }
+
+    #[test]
+    fn languages_from_system_locales() {
+        assert_eq!(Language::from_code("ar-EG"), Some(Language::Arabic));
+        assert_eq!(Language::from_code("id_ID"), Some(Language::Indonesian));
+        assert_eq!(Language::from_code("EN-us"), Some(Language::English));
+        assert_eq!(Language::from_code("fr-FR"), None);
+        assert_eq!(Language::from_code(""), None);
+
+        for language in Language::ALL {
+            assert_eq!(Language::from_code(language.code()), Some(language));
+        }
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let preferences: Preferences =
+            serde_json::from_str(r#"{"language":"ar","default_sort":"title"}"#).unwrap();
+        assert_eq!(preferences.language, Some(Language::Arabic));
+        assert_eq!(preferences.default_sort, SortOrder::Title);
+        assert_eq!(
+            preferences.max_import_mb,
+            Preferences::default().max_import_mb
+        );
+    }
+}
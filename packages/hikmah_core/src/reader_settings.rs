@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Font size in pixels
+pub const FONT_SIZES: RangeInclusive<u8> = 12..=32;
+/// Line height as a multiple of the font size
+pub const LINE_HEIGHTS: RangeInclusive<f32> = 1.0..=2.5;
+/// Space left and right of the text, in percent of the reader width
+pub const MARGINS: RangeInclusive<u8> = 0..=25;
+
+/// =======================
+/// Reader Settings
+/// =======================
+///
+/// Tampilan teks di reader EPUB & TXT. Ada satu set global, dan tiap buku
+/// boleh punya [`ReaderOverrides`] yang hanya menyimpan field yang berbeda;
+/// jadi mengganti tema global tetap berlaku untuk buku yang cuma mengubah
+/// ukuran huruf. Keduanya disimpan sebagai JSON di preferences.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct ReaderSettings {
+    pub font_family: FontFamily,
+    /// See [`FONT_SIZES`]
+    pub font_size: u8,
+    /// See [`LINE_HEIGHTS`]
+    pub line_height: f32,
+    /// See [`MARGINS`]
+    pub margin: u8,
+    pub align: TextAlign,
+    /// Stretch lines to both edges; `align` then only applies to the last
+    /// line of a paragraph
+    pub justify: bool,
+    pub hyphenate: bool,
+    pub theme: ReaderTheme,
+}
+
+impl Default for ReaderSettings {
+    fn default() -> Self {
+        Self {
+            font_family: FontFamily::Serif,
+            font_size: 18,
+            line_height: 1.6,
+            margin: 5,
+            align: TextAlign::Start,
+            justify: false,
+            hyphenate: false,
+            theme: ReaderTheme::Light,
+        }
+    }
+}
+
+impl ReaderSettings {
+    /// Values brought back into range, for settings from an older or
+    /// hand-edited library file
+    pub fn clamped(mut self) -> Self {
+        self.font_size = self.font_size.clamp(*FONT_SIZES.start(), *FONT_SIZES.end());
+        self.line_height = if self.line_height.is_finite() {
+            self.line_height
+                .clamp(*LINE_HEIGHTS.start(), *LINE_HEIGHTS.end())
+        } else {
+            Self::default().line_height
+        };
+        self.margin = self.margin.clamp(*MARGINS.start(), *MARGINS.end());
+        self
+    }
+
+    /// These settings with a book's overrides on top
+    pub fn with(&self, overrides: &ReaderOverrides) -> Self {
+        Self {
+            font_family: overrides.font_family.unwrap_or(self.font_family),
+            font_size: overrides.font_size.unwrap_or(self.font_size),
+            line_height: overrides.line_height.unwrap_or(self.line_height),
+            margin: overrides.margin.unwrap_or(self.margin),
+            align: overrides.align.unwrap_or(self.align),
+            justify: overrides.justify.unwrap_or(self.justify),
+            hyphenate: overrides.hyphenate.unwrap_or(self.hyphenate),
+            theme: overrides.theme.unwrap_or(self.theme),
+        }
+    }
+
+    /// The fields of `settings` that differ from these
+    pub fn overrides_for(&self, settings: &ReaderSettings) -> ReaderOverrides {
+        fn differs<T: PartialEq + Copy>(global: T, book: T) -> Option<T> {
+            (global != book).then_some(book)
+        }
+        ReaderOverrides {
+            font_family: differs(self.font_family, settings.font_family),
+            font_size: differs(self.font_size, settings.font_size),
+            line_height: differs(self.line_height, settings.line_height),
+            margin: differs(self.margin, settings.margin),
+            align: differs(self.align, settings.align),
+            justify: differs(self.justify, settings.justify),
+            hyphenate: differs(self.hyphenate, settings.hyphenate),
+            theme: differs(self.theme, settings.theme),
+        }
+    }
+}
+
+/// Per-book settings; `None` follows the global setting
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(default)]
+pub struct ReaderOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<FontFamily>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_height: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<TextAlign>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub justify: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hyphenate: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ReaderTheme>,
+}
+
+impl ReaderOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FontFamily {
+    Serif,
+    SansSerif,
+    Monospace,
+}
+
+impl FontFamily {
+    pub const ALL: [FontFamily; 3] = [
+        FontFamily::Serif,
+        FontFamily::SansSerif,
+        FontFamily::Monospace,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FontFamily::Serif => "serif",
+            FontFamily::SansSerif => "sans_serif",
+            FontFamily::Monospace => "monospace",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FontFamily::Serif => "Serif",
+            FontFamily::SansSerif => "Sans-serif",
+            FontFamily::Monospace => "Monospace",
+        }
+    }
+}
+
+/// Start and end follow the text direction, so Arabic lines up on the right
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextAlign {
+    Start,
+    Center,
+    End,
+}
+
+impl TextAlign {
+    pub const ALL: [TextAlign; 3] = [TextAlign::Start, TextAlign::Center, TextAlign::End];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextAlign::Start => "start",
+            TextAlign::Center => "center",
+            TextAlign::End => "end",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextAlign::Start => "Start",
+            TextAlign::Center => "Center",
+            TextAlign::End => "End",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReaderTheme {
+    Light,
+    Dark,
+    Sepia,
+}
+
+impl ReaderTheme {
+    pub const ALL: [ReaderTheme; 3] = [ReaderTheme::Light, ReaderTheme::Dark, ReaderTheme::Sepia];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReaderTheme::Light => "light",
+            ReaderTheme::Dark => "dark",
+            ReaderTheme::Sepia => "sepia",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReaderTheme::Light => "Light",
+            ReaderTheme::Dark => "Dark",
+            ReaderTheme::Sepia => "Sepia",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_only_their_fields() {
+        let global = ReaderSettings {
+            theme: ReaderTheme::Dark,
+            ..ReaderSettings::default()
+        };
+        let overrides = ReaderOverrides {
+            font_size: Some(24),
+            align: Some(TextAlign::End),
+            ..ReaderOverrides::default()
+        };
+
+        let book = global.with(&overrides);
+        assert_eq!(book.font_size, 24);
+        assert_eq!(book.align, TextAlign::End);
+        // Tema global tetap berlaku untuk buku ini
+        assert_eq!(book.theme, ReaderTheme::Dark);
+        assert_eq!(book.line_height, global.line_height);
+
+        assert_eq!(global.with(&ReaderOverrides::default()), global);
+        assert_eq!(global.overrides_for(&book), overrides);
+        assert!(global.overrides_for(&global).is_empty());
+    }
+
+    #[test]
+    fn clamped_brings_values_back_into_range() {
+        let wild = ReaderSettings {
+            font_size: 200,
+            line_height: 0.2,
+            margin: 90,
+            ..ReaderSettings::default()
+        }
+        .clamped();
+        assert_eq!(wild.font_size, *FONT_SIZES.end());
+        assert_eq!(wild.line_height, *LINE_HEIGHTS.start());
+        assert_eq!(wild.margin, *MARGINS.end());
+
+        let tiny = ReaderSettings {
+            font_size: 1,
+            line_height: 9.0,
+            ..ReaderSettings::default()
+        }
+        .clamped();
+        assert_eq!(tiny.font_size, *FONT_SIZES.start());
+        assert_eq!(tiny.line_height, *LINE_HEIGHTS.end());
+
+        let valid = ReaderSettings::default();
+        assert_eq!(valid.clamped(), valid);
+    }
+
+    #[test]
+    fn non_finite_line_height_falls_back_to_the_default() {
+        let default = ReaderSettings::default().line_height;
+        for line_height in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let overrides = ReaderOverrides {
+                line_height: Some(line_height),
+                ..ReaderOverrides::default()
+            };
+            let settings = ReaderSettings::default().with(&overrides).clamped();
+            assert_eq!(settings.line_height, default, "{line_height}");
+        }
+    }
+}
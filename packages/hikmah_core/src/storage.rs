@@ -0,0 +1,710 @@
+use crate::crdt::ReadingState;
+use crate::crypto::EncryptionState;
+use crate::models::{Annotation, Author, AuthorRole, BookMetadata, Bookmark};
+use crate::sync::{now_millis, SyncState};
+use crate::watch::WatchState;
+use crate::webdav::WebDavState;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Versi skema file library. Setiap perubahan pada `BookMetadata` (atau data
+/// lain yang disimpan) harus menaikkan angka ini dan menambah satu langkah
+/// di [`MIGRATIONS`].
+pub const SCHEMA_VERSION: u32 = 10;
+
+/// File tanpa `schema_version` berasal dari format awal (satu `author` string)
+const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+const APP_DIR: &str = "bayt-alhikmah";
+const LIBRARY_FILE: &str = "library.json";
+#[cfg(not(target_arch = "wasm32"))]
+const LOCK_FILE: &str = "library.lock";
+
+/// =======================
+/// Data Model
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredLibrary {
+    pub schema_version: u32,
+    pub books: Vec<BookMetadata>,
+    pub annotations: Vec<Annotation>,
+    pub bookmarks: Vec<Bookmark>,
+    pub preferences: BTreeMap<String, String>,
+    pub sync: SyncState,
+    pub reading_state: ReadingState,
+    pub watch: WatchState,
+}
+
+/// Borrowed form of [`StoredLibrary`], so saving doesn't clone every book
+#[derive(Serialize)]
+pub struct StoredLibraryRef<'a> {
+    pub books: &'a [BookMetadata],
+    pub annotations: &'a [Annotation],
+    pub bookmarks: &'a [Bookmark],
+    pub preferences: &'a BTreeMap<String, String>,
+    pub sync: &'a SyncState,
+    pub reading_state: &'a ReadingState,
+    pub watch: &'a WatchState,
+}
+
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageError {
+    Io(String),
+    /// The file is not valid JSON or doesn't match the schema it claims
+    Corrupt(String),
+    /// Written by a newer app version; we must not touch it
+    NewerSchema {
+        found: u32,
+        supported: u32,
+    },
+    /// A migration step failed. The original file is still in place and a copy
+    /// is kept at `backup`.
+    Migration {
+        from: u32,
+        reason: String,
+        backup: Option<PathBuf>,
+    },
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "Library storage error: {e}"),
+            StorageError::Corrupt(e) => write!(f, "Library file is damaged: {e}"),
+            StorageError::NewerSchema { found, supported } => write!(
+                f,
+                "Library was saved by a newer version (schema {found}, supported {supported}). \
+                 Please update the app."
+            ),
+            StorageError::Migration {
+                from,
+                reason,
+                backup,
+            } => {
+                write!(f, "Could not upgrade library from schema {from}: {reason}")?;
+                if let Some(backup) = backup {
+                    write!(f, " (backup kept at {})", backup.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// =======================
+/// Locations
+/// =======================
+///
+/// `~/.local/share/bayt-alhikmah` on Linux, `%APPDATA%\bayt-alhikmah` on Windows, ...
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(test)]
+    if let Some(dir) = TEST_DATA_DIR.with(|dir| dir.borrow().clone()) {
+        return Some(dir);
+    }
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Test pakai folder sementara sendiri, bukan library milik user
+    static TEST_DATA_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Point [`data_dir`] at a new empty folder, for the current test only
+#[cfg(test)]
+pub(crate) fn use_test_data_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hikmah-test-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    TEST_DATA_DIR.with(|d| *d.borrow_mut() = Some(dir.clone()));
+    dir
+}
+
+/// Lets a thread the test starts (the import worker) use the same folder
+#[cfg(test)]
+pub(crate) fn share_test_data_dir() -> impl FnOnce() + Send {
+    let dir = TEST_DATA_DIR.with(|d| d.borrow().clone());
+    move || TEST_DATA_DIR.with(|d| *d.borrow_mut() = dir)
+}
+
+pub fn library_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(LIBRARY_FILE))
+}
+
+/// =======================
+/// Lock
+/// =======================
+///
+/// Hanya satu proses (aplikasi desktop atau CLI `hikmah`) yang boleh menulis
+/// library sekaligus; kalau tidak, yang terakhir menyimpan menimpa perubahan
+/// yang lain. Lock dilepas saat di-drop, atau oleh OS saat prosesnya mati.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct LibraryLock {
+    _file: fs::File,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LibraryLock {
+    /// Take the lock, waiting up to `wait` for another process to let go
+    pub fn acquire(wait: std::time::Duration) -> Result<Self, String> {
+        let dir = data_dir().ok_or("No data directory to keep the library in")?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(LOCK_FILE);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+        let deadline = std::time::Instant::now() + wait;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(fs::TryLockError::WouldBlock) if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+                Err(fs::TryLockError::WouldBlock) => {
+                    return Err("The library is in use by another Bayt Alhikmah process".to_string())
+                }
+                Err(fs::TryLockError::Error(e)) => {
+                    return Err(format!("Failed to lock {}: {}", path.display(), e))
+                }
+            }
+        }
+    }
+}
+
+/// =======================
+/// Load / Save
+/// =======================
+///
+/// Load the library file, upgrading it to [`SCHEMA_VERSION`] when needed.
+/// Returns `Ok(None)` if there is no file yet.
+///
+/// The upgrade is only written back with `persist`, which needs the
+/// [`LibraryLock`]; otherwise it stays in memory and the file is left as
+/// it is. Before writing, the original file is copied next to it so nothing
+/// is lost if an upgrade step misbehaves.
+pub fn load_library(path: &Path, persist: bool) -> Result<Option<StoredLibrary>, StorageError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(path).map_err(|e| StorageError::Io(e.to_string()))?;
+    let doc: Value =
+        serde_json::from_str(&text).map_err(|e| StorageError::Corrupt(e.to_string()))?;
+
+    let version = schema_version(&doc);
+    if version > SCHEMA_VERSION {
+        return Err(StorageError::NewerSchema {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    if version == SCHEMA_VERSION {
+        return serde_json::from_value(doc)
+            .map(Some)
+            .map_err(|e| StorageError::Corrupt(e.to_string()));
+    }
+
+    if !persist {
+        let stored = migrate(doc).map_err(|reason| StorageError::Migration {
+            from: version,
+            reason,
+            backup: None,
+        })?;
+        info!(
+            "Library upgraded from schema {} in memory only; the file is written once the library is opened for changes",
+            version
+        );
+        return Ok(Some(stored));
+    }
+
+    let backup = backup_before_migration(path, version).map_err(|e| StorageError::Migration {
+        from: version,
+        reason: format!("could not back up the old file: {e}"),
+        backup: None,
+    })?;
+
+    let stored = migrate(doc).map_err(|reason| StorageError::Migration {
+        from: version,
+        reason,
+        backup: Some(backup.clone()),
+    })?;
+
+    let library = StoredLibraryRef {
+        books: &stored.books,
+        annotations: &stored.annotations,
+        bookmarks: &stored.bookmarks,
+        preferences: &stored.preferences,
+        sync: &stored.sync,
+        reading_state: &stored.reading_state,
+        watch: &stored.watch,
+    };
+    save_library(path, &library).map_err(|e| StorageError::Migration {
+        from: version,
+        reason: e.to_string(),
+        backup: Some(backup),
+    })?;
+
+    info!(
+        "Library upgraded from schema {} to {}",
+        version, SCHEMA_VERSION
+    );
+    Ok(Some(stored))
+}
+
+/// Tulis ke file sementara lalu rename, supaya crash di tengah jalan tidak
+/// meninggalkan file library yang setengah jadi.
+pub fn save_library(path: &Path, library: &StoredLibraryRef) -> Result<(), StorageError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| StorageError::Io(e.to_string()))?;
+    }
+
+    let json = to_json(library)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| StorageError::Io(e.to_string()))?;
+    fs::rename(&tmp, path).map_err(|e| StorageError::Io(e.to_string()))
+}
+
+/// The library document as written to disk, tagged with [`SCHEMA_VERSION`]
+pub fn to_json(library: &StoredLibraryRef) -> Result<String, StorageError> {
+    let library = Versioned {
+        schema_version: SCHEMA_VERSION,
+        data: library,
+    };
+    serde_json::to_string(&library).map_err(|e| StorageError::Corrupt(e.to_string()))
+}
+
+/// Same document as [`to_json`], for the web store which takes the book
+/// files out before saving
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn to_value(library: &StoredLibraryRef) -> Result<Value, StorageError> {
+    let library = Versioned {
+        schema_version: SCHEMA_VERSION,
+        data: library,
+    };
+    serde_json::to_value(&library).map_err(|e| StorageError::Corrupt(e.to_string()))
+}
+
+/// =======================
+/// Migrations
+/// =======================
+///
+/// Upgrade a stored document of any older schema, one step at a time
+pub fn migrate(mut doc: Value) -> Result<StoredLibrary, String> {
+    // Format paling awal hanya berupa array buku
+    if doc.is_array() {
+        doc = json!({ "books": doc });
+    }
+
+    let mut version = schema_version(&doc);
+    for (from, step) in MIGRATIONS {
+        if *from < version {
+            continue;
+        }
+        let obj = doc
+            .as_object_mut()
+            .ok_or("library document is not an object")?;
+        step(obj).map_err(|e| format!("step {} -> {}: {}", from, from + 1, e))?;
+        version = from + 1;
+        obj.insert("schema_version".into(), json!(version));
+    }
+
+    if version != SCHEMA_VERSION {
+        return Err(format!("no migration path from schema {version}"));
+    }
+
+    serde_json::from_value(doc).map_err(|e| e.to_string())
+}
+
+type MigrationStep = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// (from_version, step) — setiap step menaikkan versi tepat satu
+const MIGRATIONS: &[(u32, MigrationStep)] = &[
+    (1, v1_single_author_to_authors),
+    (2, v2_add_series_tags_rating),
+    (3, v3_add_identifiers),
+    (4, v4_add_bookmarks_and_sync_state),
+    (5, v5_add_reading_state),
+    (6, v6_add_webdav_state),
+    (7, v7_add_encryption_state),
+    (8, v8_add_book_blob),
+    (9, v9_add_watch_state),
+];
+
+/// v1 -> v2: `author: String` menjadi `authors: [Author]`,
+/// dan annotations/preferences ditambahkan
+fn v1_single_author_to_authors(doc: &mut Map<String, Value>) -> Result<(), String> {
+    for book in books_mut(doc)? {
+        let book = book.as_object_mut().ok_or("book is not an object")?;
+        let authors = match book.remove("author").as_ref().and_then(Value::as_str) {
+            Some(name) if !name.trim().is_empty() && name != "Unknown Author" => {
+                let author = Author::new(name, AuthorRole::Author);
+                vec![serde_json::to_value(author).map_err(|e| e.to_string())?]
+            }
+            _ => vec![],
+        };
+        book.entry("authors").or_insert(Value::Array(authors));
+    }
+
+    doc.entry("annotations").or_insert(json!([]));
+    doc.entry("preferences").or_insert(json!({}));
+    Ok(())
+}
+
+/// v2 -> v3: series, tags & rating (dari import Calibre)
+fn v2_add_series_tags_rating(doc: &mut Map<String, Value>) -> Result<(), String> {
+    for book in books_mut(doc)? {
+        let book = book.as_object_mut().ok_or("book is not an object")?;
+        book.entry("series").or_insert(Value::Null);
+        book.entry("tags").or_insert(json!([]));
+        book.entry("rating").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// v3 -> v4: identifiers (ISBN dll.) untuk export OPF
+fn v3_add_identifiers(doc: &mut Map<String, Value>) -> Result<(), String> {
+    for book in books_mut(doc)? {
+        let book = book.as_object_mut().ok_or("book is not an object")?;
+        book.entry("identifiers").or_insert(json!({}));
+    }
+    Ok(())
+}
+
+/// v4 -> v5: bookmarks dan status sinkronisasi
+fn v4_add_bookmarks_and_sync_state(doc: &mut Map<String, Value>) -> Result<(), String> {
+    doc.entry("bookmarks").or_insert(json!([]));
+    doc.entry("sync")
+        .or_insert(serde_json::to_value(SyncState::default()).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+/// v5 -> v6: CRDT reading state (posisi, tag, anotasi, koleksi) dan device id.
+/// Anotasi kini disinkronkan per buku, jadi perubahan yang belum terkirim ikut dipindah.
+fn v5_add_reading_state(doc: &mut Map<String, Value>) -> Result<(), String> {
+    let books: Vec<BookMetadata> =
+        serde_json::from_value(doc.get("books").cloned().unwrap_or(json!([])))
+            .map_err(|e| e.to_string())?;
+    let annotations: Vec<Annotation> =
+        serde_json::from_value(doc.get("annotations").cloned().unwrap_or(json!([])))
+            .map_err(|e| e.to_string())?;
+
+    let sync = doc
+        .get_mut("sync")
+        .and_then(Value::as_object_mut)
+        .ok_or("missing sync state")?;
+    let device_id = uuid::Uuid::new_v4().to_string();
+    sync.insert("device_id".into(), json!(device_id));
+
+    if let Some(pending) = sync.get_mut("pending").and_then(Value::as_object_mut) {
+        let moved: Vec<String> = pending
+            .iter()
+            .filter(|(_, change)| change["kind"] == "annotation")
+            .filter_map(|(_, change)| {
+                annotations
+                    .iter()
+                    .find(|a| change["id"] == a.id.as_str())
+                    .map(|a| a.book_id.clone())
+            })
+            .collect();
+        pending.retain(|_, change| change["kind"] != "annotation");
+        for book_id in moved {
+            pending.insert(
+                format!("Annotations/{book_id}"),
+                json!({ "kind": "annotations", "id": book_id, "modified": now_millis() }),
+            );
+        }
+    }
+
+    let state = ReadingState::seed(&device_id, &books, &annotations);
+    doc.insert(
+        "reading_state".into(),
+        serde_json::to_value(state).map_err(|e| e.to_string())?,
+    );
+    Ok(())
+}
+
+/// v6 -> v7: ETag & daftar buku yang diketahui dari folder WebDAV
+fn v6_add_webdav_state(doc: &mut Map<String, Value>) -> Result<(), String> {
+    let sync = doc
+        .get_mut("sync")
+        .and_then(Value::as_object_mut)
+        .ok_or("missing sync state")?;
+    sync.entry("webdav")
+        .or_insert(serde_json::to_value(WebDavState::default()).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+/// v7 -> v8: enkripsi end-to-end (belum aktif) untuk sync & backup
+fn v7_add_encryption_state(doc: &mut Map<String, Value>) -> Result<(), String> {
+    let sync = doc
+        .get_mut("sync")
+        .and_then(Value::as_object_mut)
+        .ok_or("missing sync state")?;
+    sync.entry("encryption")
+        .or_insert(serde_json::to_value(EncryptionState::default()).map_err(|e| e.to_string())?);
+    sync.entry("retired_collections").or_insert(json!([]));
+    if let Some(webdav) = sync.get_mut("webdav").and_then(Value::as_object_mut) {
+        webdav.entry("key_salt").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// v8 -> v9: file buku bisa disimpan di blob store, bukan data URL
+fn v8_add_book_blob(doc: &mut Map<String, Value>) -> Result<(), String> {
+    for book in books_mut(doc)? {
+        let book = book.as_object_mut().ok_or("book is not an object")?;
+        book.entry("blob").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// v9 -> v10: folder yang dipantau untuk import otomatis (desktop)
+fn v9_add_watch_state(doc: &mut Map<String, Value>) -> Result<(), String> {
+    doc.entry("watch")
+        .or_insert(serde_json::to_value(WatchState::default()).map_err(|e| e.to_string())?);
+    Ok(())
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+fn books_mut(doc: &mut Map<String, Value>) -> Result<&mut Vec<Value>, String> {
+    doc.get_mut("books")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "missing books array".to_string())
+}
+
+pub(crate) fn schema_version(doc: &Value) -> u32 {
+    doc.get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(LEGACY_SCHEMA_VERSION)
+}
+
+/// `library.json` -> `library.schema1.20250131T120000.json`
+fn backup_before_migration(path: &Path, version: u32) -> std::io::Result<PathBuf> {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "library".to_string());
+    let backup = path.with_file_name(format!(
+        "{}.schema{}.{}.json",
+        stem,
+        version,
+        chrono::Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+
+    fs::copy(path, &backup)?;
+    info!("Backed up library to {}", backup.display());
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Satu buku dalam format paling awal: `author` berupa satu string
+    fn v1_book(id: &str, author: &str) -> Value {
+        json!({
+            "id": id,
+            "title": "Al-Arba'in an-Nawawiyyah",
+            "author": author,
+            "file_name": "arbain.epub",
+            "format": "EPUB",
+            "size": 1024,
+            "file_data": "data:application/epub+zip;base64,",
+            "cover_image": null,
+            "last_read_position": 3,
+            "total_pages": 42,
+            "added_date": "2024-01-31T12:00:00+00:00"
+        })
+    }
+
+    fn library_file(doc: &Value) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("hikmah-storage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LIBRARY_FILE);
+        fs::write(&path, doc.to_string()).unwrap();
+        path
+    }
+
+    fn migration_backups(path: &Path) -> Vec<PathBuf> {
+        fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p.to_string_lossy().contains(".schema"))
+            .collect()
+    }
+
+    #[test]
+    fn legacy_bare_array_is_upgraded_to_the_current_schema() {
+        let stored = migrate(json!([v1_book("a", "Imam an-Nawawi")])).unwrap();
+        assert_eq!(stored.schema_version, SCHEMA_VERSION);
+        assert_eq!(stored.books.len(), 1);
+        assert_eq!(stored.books[0].last_read_position, 3);
+        assert!(stored.annotations.is_empty() && stored.bookmarks.is_empty());
+        assert!(!stored.sync.device_id.is_empty());
+        assert!(stored.reading_state.books.contains_key("a"));
+    }
+
+    #[test]
+    fn v1_author_becomes_an_author_list() {
+        let mut doc = json!({
+            "books": [
+                v1_book("a", "Imam an-Nawawi"),
+                v1_book("b", "Unknown Author"),
+                v1_book("c", "  "),
+            ]
+        });
+        v1_single_author_to_authors(doc.as_object_mut().unwrap()).unwrap();
+
+        let books = doc["books"].as_array().unwrap();
+        assert!(books.iter().all(|b| b.get("author").is_none()));
+        assert_eq!(books[0]["authors"][0]["name"], "Imam an-Nawawi");
+        assert_eq!(books[0]["authors"][0]["sort_name"], "an-Nawawi, Imam");
+        assert_eq!(books[0]["authors"][0]["role"], "Author");
+        assert_eq!(books[1]["authors"], json!([]));
+        assert_eq!(books[2]["authors"], json!([]));
+        assert_eq!(doc["annotations"], json!([]));
+        assert_eq!(doc["preferences"], json!({}));
+    }
+
+    #[test]
+    fn every_step_adds_its_fields() {
+        let mut doc = json!({ "books": [v1_book("a", "Ibn Hajar")] });
+        let obj = doc.as_object_mut().unwrap();
+        let book = |obj: &Map<String, Value>| obj["books"][0].clone();
+
+        v1_single_author_to_authors(obj).unwrap();
+        v2_add_series_tags_rating(obj).unwrap();
+        assert_eq!(book(obj)["series"], Value::Null);
+        assert_eq!(book(obj)["tags"], json!([]));
+        assert_eq!(book(obj)["rating"], Value::Null);
+
+        v3_add_identifiers(obj).unwrap();
+        assert_eq!(book(obj)["identifiers"], json!({}));
+
+        v4_add_bookmarks_and_sync_state(obj).unwrap();
+        assert_eq!(obj["bookmarks"], json!([]));
+        assert_eq!(obj["sync"]["last_version"], 0);
+
+        v5_add_reading_state(obj).unwrap();
+        assert!(!obj["sync"]["device_id"].as_str().unwrap().is_empty());
+        assert!(obj["reading_state"]["books"].get("a").is_some());
+
+        v6_add_webdav_state(obj).unwrap();
+        assert!(obj["sync"]["webdav"].is_object());
+
+        v7_add_encryption_state(obj).unwrap();
+        assert_eq!(obj["sync"]["encryption"]["key"], Value::Null);
+        assert_eq!(obj["sync"]["retired_collections"], json!([]));
+        assert_eq!(obj["sync"]["webdav"]["key_salt"], Value::Null);
+
+        v8_add_book_blob(obj).unwrap();
+        assert_eq!(book(obj)["blob"], Value::Null);
+
+        v9_add_watch_state(obj).unwrap();
+        assert!(obj["watch"].is_object());
+
+        obj.insert("schema_version".into(), json!(SCHEMA_VERSION));
+        let stored: StoredLibrary = serde_json::from_value(doc).unwrap();
+        assert_eq!(stored.books[0].authors[0].name, "Ibn Hajar");
+    }
+
+    #[test]
+    fn steps_keep_what_is_already_there() {
+        let mut doc = json!({ "books": [v1_book("a", "Ibn Hajar")] });
+        doc["books"][0]["tags"] = json!(["hadith"]);
+        v2_add_series_tags_rating(doc.as_object_mut().unwrap()).unwrap();
+        assert_eq!(doc["books"][0]["tags"], json!(["hadith"]));
+    }
+
+    #[test]
+    fn newer_schema_is_rejected_and_left_alone() {
+        let doc = json!({ "schema_version": SCHEMA_VERSION + 1, "books": [] });
+        let path = library_file(&doc);
+
+        assert_eq!(
+            load_library(&path, true),
+            Err(StorageError::NewerSchema {
+                found: SCHEMA_VERSION + 1,
+                supported: SCHEMA_VERSION,
+            })
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), doc.to_string());
+        assert!(migration_backups(&path).is_empty());
+    }
+
+    #[test]
+    fn upgrade_keeps_a_copy_of_the_old_file() {
+        let doc = json!([v1_book("a", "Imam an-Nawawi")]);
+        let path = library_file(&doc);
+
+        let stored = load_library(&path, true).unwrap().unwrap();
+        assert_eq!(stored.books[0].authors[0].name, "Imam an-Nawawi");
+
+        let backups = migration_backups(&path);
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("library.schema1."));
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), doc.to_string());
+
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(schema_version(&written), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn upgrade_without_the_lock_stays_in_memory() {
+        let doc = json!([v1_book("a", "Imam an-Nawawi")]);
+        let path = library_file(&doc);
+
+        let stored = load_library(&path, false).unwrap().unwrap();
+        assert_eq!(stored.schema_version, SCHEMA_VERSION);
+        assert_eq!(fs::read_to_string(&path).unwrap(), doc.to_string());
+        assert!(migration_backups(&path).is_empty());
+    }
+
+    #[test]
+    fn failed_upgrade_leaves_the_original_file_intact() {
+        // Buku yang bukan object membuat langkah v1 -> v2 gagal
+        let doc = json!({ "books": [v1_book("a", "Imam an-Nawawi"), 42] });
+        let path = library_file(&doc);
+
+        let Err(StorageError::Migration {
+            from,
+            reason,
+            backup,
+        }) = load_library(&path, true)
+        else {
+            panic!("migration must fail");
+        };
+        assert_eq!(from, LEGACY_SCHEMA_VERSION);
+        assert!(reason.contains("step 1 -> 2"), "{reason}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), doc.to_string());
+        assert_eq!(
+            fs::read_to_string(backup.unwrap()).unwrap(),
+            doc.to_string()
+        );
+    }
+}
@@ -0,0 +1,752 @@
+use crate::crdt::{AnnotationSet, OrSet, ReadingPosition};
+use crate::crypto::{login_key, CryptoError, EncryptionState, LibraryKey};
+use crate::library::Library;
+use crate::models::{BookMetadata, Bookmark};
+use crate::webdav::WebDavState;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// =======================
+/// Protocol
+/// =======================
+///
+/// Shared by the client below and the `sync_server` crate.
+/// Setiap record adalah satu entitas (buku, posisi baca, bookmark, anotasi).
+/// Server memberi nomor `version` yang terus naik, sehingga client cukup
+/// meminta "semua perubahan sejak version N".
+///
+/// Record yang membawa CRDT (lihat [`RecordKind::is_mergeable`]) digabung,
+/// bukan ditimpa; sisanya last-write-wins berdasarkan `modified`.
+///
+/// Kalau enkripsi aktif, `data` berisi envelope (lihat [`crate::crypto`]) dan
+/// server tidak bisa membacanya: record seperti itu selalu last-write-wins di
+/// server, dan client yang menggabungkannya lalu mengirim balik hasilnya.
+pub const API_PREFIX: &str = "/api/v1";
+
+/// Push/pull rounds per [`sync_library`]
+pub const MAX_ROUNDS: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordKind {
+    /// Book metadata without the file blob
+    Book,
+    /// [`ReadingPosition`] of a book (id = book id)
+    Position,
+    Bookmark,
+    /// [`AnnotationSet`] of a book (id = book id)
+    Annotations,
+    /// Tag [`OrSet`] of a book (id = book id)
+    Tags,
+    /// Book id [`OrSet`] of a collection (id = collection name)
+    Collection,
+}
+
+impl RecordKind {
+    pub fn is_mergeable(&self) -> bool {
+        matches!(
+            self,
+            RecordKind::Position
+                | RecordKind::Annotations
+                | RecordKind::Tags
+                | RecordKind::Collection
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncRecord {
+    pub kind: RecordKind,
+    pub id: String,
+    /// None = deleted (tombstone)
+    pub data: Option<Value>,
+    /// Client clock, unix millis. Newer wins.
+    pub modified: i64,
+    /// Assigned by the server; 0 for records that weren't pushed yet
+    #[serde(default)]
+    pub version: u64,
+    /// `data` is a base64 envelope holding the real id and data
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RegisterRequest {
+    pub username: String,
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PushRequest {
+    pub records: Vec<SyncRecord>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PushResponse {
+    /// Latest version on the server after applying the push
+    pub version: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PullResponse {
+    pub version: u64,
+    pub records: Vec<SyncRecord>,
+}
+
+/// =======================
+/// Local Change Tracking
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingChange {
+    pub kind: RecordKind,
+    pub id: String,
+    pub modified: i64,
+}
+
+/// Disimpan bersama library: versi terakhir yang sudah di-pull dan daftar
+/// entitas yang berubah sejak push terakhir.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SyncState {
+    /// Identifies this device in CRDT clocks
+    pub device_id: String,
+    pub last_version: u64,
+    /// "kind/id" -> change
+    pub pending: BTreeMap<String, PendingChange>,
+    pub webdav: WebDavState,
+    pub encryption: EncryptionState,
+    /// Collection record ids the server still holds under an old key (or in
+    /// plaintext); deleted on the next push
+    pub retired_collections: BTreeSet<String>,
+}
+
+impl SyncState {
+    pub fn track(&mut self, kind: RecordKind, id: &str) {
+        self.pending.insert(
+            pending_key(kind, id),
+            PendingChange {
+                kind,
+                id: id.to_string(),
+                modified: now_millis(),
+            },
+        );
+    }
+
+    pub fn pending_change(&self, kind: RecordKind, id: &str) -> Option<&PendingChange> {
+        self.pending.get(&pending_key(kind, id))
+    }
+
+    /// Forget pushed changes, unless the entity was edited again in the meantime
+    pub fn mark_pushed(&mut self, pushed: &[SyncRecord]) {
+        for record in pushed {
+            if record.encrypted && record.kind == RecordKind::Collection {
+                self.retired_collections.remove(&record.id);
+                continue;
+            }
+            let key = pending_key(record.kind, &record.id);
+            if self
+                .pending
+                .get(&key)
+                .is_some_and(|p| p.modified <= record.modified)
+            {
+                self.pending.remove(&key);
+            }
+        }
+    }
+}
+
+fn pending_key(kind: RecordKind, id: &str) -> String {
+    format!("{:?}/{}", kind, id)
+}
+
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// A pulled record, decoded into the entity it carries
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteChange {
+    Book(Box<BookMetadata>),
+    Position(String, ReadingPosition),
+    Bookmark(Bookmark),
+    Annotations(String, AnnotationSet),
+    Tags(String, OrSet<String>),
+    Collection(String, OrSet<String>),
+    Deleted(RecordKind, String),
+}
+
+impl SyncRecord {
+    pub fn decode(&self) -> Result<RemoteChange, String> {
+        let Some(data) = self.data.clone() else {
+            return Ok(RemoteChange::Deleted(self.kind, self.id.clone()));
+        };
+
+        let invalid =
+            |e: serde_json::Error| format!("Invalid {:?} record {}: {}", self.kind, self.id, e);
+        let id = self.id.clone();
+        Ok(match self.kind {
+            RecordKind::Book => {
+                RemoteChange::Book(Box::new(serde_json::from_value(data).map_err(invalid)?))
+            }
+            RecordKind::Position => {
+                RemoteChange::Position(id, serde_json::from_value(data).map_err(invalid)?)
+            }
+            RecordKind::Bookmark => {
+                RemoteChange::Bookmark(serde_json::from_value(data).map_err(invalid)?)
+            }
+            RecordKind::Annotations => {
+                RemoteChange::Annotations(id, serde_json::from_value(data).map_err(invalid)?)
+            }
+            RecordKind::Tags => {
+                RemoteChange::Tags(id, serde_json::from_value(data).map_err(invalid)?)
+            }
+            RecordKind::Collection => {
+                RemoteChange::Collection(id, serde_json::from_value(data).map_err(invalid)?)
+            }
+        })
+    }
+
+    /// Combine two versions of the same entity. CRDT records are merged,
+    /// so concurrent edits from different devices are all kept; deletions
+    /// and plain records fall back to last-write-wins on `modified`.
+    /// Used by the server on push and by the client on pull.
+    pub fn merge(&self, incoming: &SyncRecord) -> Result<SyncRecord, String> {
+        let newest = if incoming.modified >= self.modified {
+            incoming
+        } else {
+            self
+        };
+
+        let (Some(ours), Some(theirs)) = (&self.data, &incoming.data) else {
+            return Ok(newest.clone());
+        };
+        // Isinya tidak terbaca di sini; client yang menggabungkan
+        if self.encrypted || incoming.encrypted {
+            return Ok(newest.clone());
+        }
+        let data = match self.kind {
+            RecordKind::Position => {
+                merge_values::<ReadingPosition>(ours, theirs, ReadingPosition::merge)?
+            }
+            RecordKind::Annotations => {
+                merge_values::<AnnotationSet>(ours, theirs, AnnotationSet::merge)?
+            }
+            RecordKind::Tags | RecordKind::Collection => {
+                merge_values::<OrSet<String>>(ours, theirs, OrSet::merge)?
+            }
+            RecordKind::Book | RecordKind::Bookmark => return Ok(newest.clone()),
+        };
+
+        Ok(SyncRecord {
+            data: Some(data),
+            ..newest.clone()
+        })
+    }
+}
+
+/// What an encrypted record's envelope holds
+#[derive(Serialize, Deserialize)]
+struct SealedPayload {
+    id: String,
+    data: Value,
+}
+
+impl SyncRecord {
+    /// The record as it goes to the server when encryption is on: id and
+    /// data become one envelope, and collection names (the only ids the user
+    /// types) are replaced by a keyed hash. Tombstones keep no data.
+    pub fn seal(&self, key: &LibraryKey) -> Result<SyncRecord, CryptoError> {
+        if self.encrypted {
+            return Ok(self.clone());
+        }
+
+        let id = match self.kind {
+            RecordKind::Collection => key.blind_id(&self.id),
+            _ => self.id.clone(),
+        };
+        let data = match &self.data {
+            Some(data) => {
+                let payload = serde_json::to_vec(&SealedPayload {
+                    id: self.id.clone(),
+                    data: data.clone(),
+                })
+                .map_err(|_| CryptoError::Corrupt)?;
+                Some(Value::String(STANDARD.encode(key.encrypt(&payload)?)))
+            }
+            None => None,
+        };
+
+        Ok(SyncRecord {
+            id,
+            data,
+            encrypted: true,
+            ..self.clone()
+        })
+    }
+
+    /// Reverse of [`SyncRecord::seal`]. Plaintext records are returned as is
+    /// while encryption is off; once a key is set they are refused, so a
+    /// server can't slip in records that were never sealed.
+    pub fn open(&self, encryption: &EncryptionState) -> Result<SyncRecord, CryptoError> {
+        if !self.encrypted {
+            return match encryption.key {
+                Some(_) => Err(CryptoError::NotEncrypted),
+                None => Ok(self.clone()),
+            };
+        }
+        let Some(data) = &self.data else {
+            return Ok(self.clone());
+        };
+
+        let envelope = data
+            .as_str()
+            .and_then(|s| STANDARD.decode(s).ok())
+            .ok_or(CryptoError::Corrupt)?;
+        let payload: SealedPayload = serde_json::from_slice(&encryption.decrypt(&envelope)?)
+            .map_err(|_| CryptoError::Corrupt)?;
+
+        Ok(SyncRecord {
+            id: payload.id,
+            data: Some(payload.data),
+            encrypted: false,
+            ..self.clone()
+        })
+    }
+}
+
+fn merge_values<T>(ours: &Value, theirs: &Value, merge: fn(&mut T, &T)) -> Result<Value, String>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let mut ours: T = serde_json::from_value(ours.clone()).map_err(|e| e.to_string())?;
+    let theirs: T = serde_json::from_value(theirs.clone()).map_err(|e| e.to_string())?;
+    merge(&mut ours, &theirs);
+    serde_json::to_value(ours).map_err(|e| e.to_string())
+}
+
+/// =======================
+/// Client
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SyncConfig {
+    pub server_url: String,
+    pub username: String,
+    /// Derived from the password; the password itself is never stored
+    pub key: String,
+}
+
+impl SyncConfig {
+    /// Fails only if the password is empty
+    pub fn new(server_url: &str, username: &str, password: &str) -> Result<Self, String> {
+        Ok(Self {
+            server_url: server_url.trim_end_matches('/').to_string(),
+            username: username.to_string(),
+            key: login_key(username, password).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncClient {
+    config: SyncConfig,
+    http: Client,
+}
+
+impl SyncClient {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            http: Client::new(),
+        }
+    }
+
+    pub async fn register(&self) -> Result<(), String> {
+        let response = self
+            .http
+            .post(self.url("/register"))
+            .json(&RegisterRequest {
+                username: self.config.username.clone(),
+                key: self.config.key.clone(),
+            })
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::CREATED | StatusCode::OK => Ok(()),
+            StatusCode::CONFLICT => Err("Username is already registered".to_string()),
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// Upload local changes; returns the server version afterwards
+    pub async fn push(&self, records: &[SyncRecord]) -> Result<u64, String> {
+        let response = self
+            .authed(self.http.post(self.url("/push")))
+            .json(&PushRequest {
+                records: records.to_vec(),
+            })
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::OK => {
+                let pushed: PushResponse = response.json().await.map_err(network_error)?;
+                Ok(pushed.version)
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// Every record changed on the server after `since`
+    pub async fn pull(&self, since: u64) -> Result<PullResponse, String> {
+        let response = self
+            .authed(self.http.get(self.url(&format!("/pull?since={since}"))))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::OK => response.json().await.map_err(network_error),
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// One incremental round: push pending records, then pull everything
+    /// newer than `since` (which includes what we just pushed; applying it
+    /// again is harmless).
+    pub async fn sync(&self, pending: &[SyncRecord], since: u64) -> Result<PullResponse, String> {
+        if !pending.is_empty() {
+            self.push(pending).await?;
+        }
+        self.pull(since).await
+    }
+
+    /* =========================
+       HTTP HELPERS
+    ========================= */
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}{}", self.config.server_url, API_PREFIX, path)
+    }
+
+    fn authed(&self, builder: RequestBuilder) -> RequestBuilder {
+        builder
+            .header("x-auth-user", &self.config.username)
+            .header("x-auth-key", &self.config.key)
+    }
+}
+
+/// What one push/pull sends, taken from the library up front so the request
+/// itself doesn't need it (the apps keep it in a signal that can't stay
+/// borrowed while waiting on the network)
+#[derive(Debug, Clone)]
+pub struct SyncRound {
+    /// Records before sealing, for [`Library::apply_sync`]
+    pub pending: Vec<SyncRecord>,
+    /// The same records as they go to the server (encrypted when a key is set)
+    pub outgoing: Vec<SyncRecord>,
+    pub since: u64,
+}
+
+impl SyncRound {
+    pub fn new(library: &Library) -> Result<Self, String> {
+        let pending = library.pending_records();
+        let outgoing = match &library.sync.encryption.key {
+            Some(key) => pending
+                .iter()
+                .map(|record| record.seal(key))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?,
+            None => pending.clone(),
+        };
+        Ok(Self {
+            pending,
+            outgoing,
+            since: library.sync.last_version,
+        })
+    }
+}
+
+/// Sync with the server: push what changed (encrypted when a key is set),
+/// pull and merge everything newer than the last sync. Merging can leave
+/// changes for the server (it can't merge encrypted records itself), so
+/// those are sent right away in a second round.
+pub async fn sync_library(client: &SyncClient, library: &mut Library) -> Result<(), String> {
+    for _ in 0..MAX_ROUNDS {
+        let round = SyncRound::new(library)?;
+        let pulled = client.sync(&round.outgoing, round.since).await?;
+        library.apply_sync(&round.pending, pulled)?;
+        if library.sync.pending.is_empty() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn network_error(e: reqwest::Error) -> String {
+    format!("Sync server error: {e}")
+}
+
+fn status_error(status: StatusCode) -> String {
+    match status {
+        StatusCode::UNAUTHORIZED => "Unauthorized: wrong username or password".to_string(),
+        status => format!("Sync server returned {status}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn tags_record(tags: &OrSet<String>, modified: i64) -> SyncRecord {
+        SyncRecord {
+            kind: RecordKind::Tags,
+            id: "book".to_string(),
+            data: Some(serde_json::to_value(tags).unwrap()),
+            modified,
+            version: 0,
+            encrypted: false,
+        }
+    }
+
+    fn tag_names(record: &SyncRecord) -> Vec<String> {
+        let tags: OrSet<String> = serde_json::from_value(record.data.clone().unwrap()).unwrap();
+        tags.iter().cloned().collect()
+    }
+
+    #[test]
+    fn concurrent_pushes_of_crdt_records_are_merged() {
+        let mut phone = OrSet::default();
+        phone.insert("fiqh".to_string(), "phone");
+        let mut laptop = OrSet::default();
+        laptop.insert("tafsir".to_string(), "laptop");
+
+        // Push yang lebih lama tetap dihitung, tidak ditimpa
+        let stored = tags_record(&laptop, 2_000);
+        let merged = stored.merge(&tags_record(&phone, 1_000)).unwrap();
+        assert_eq!(tag_names(&merged), ["fiqh", "tafsir"]);
+        assert_eq!(merged.modified, 2_000);
+    }
+
+    #[test]
+    fn plain_records_are_last_write_wins() {
+        let bookmark = |label: &str, modified| SyncRecord {
+            kind: RecordKind::Bookmark,
+            id: "bm".to_string(),
+            data: Some(serde_json::json!({ "label": label })),
+            modified,
+            version: 0,
+            encrypted: false,
+        };
+
+        let stored = bookmark("newer", 2_000);
+        assert_eq!(stored.merge(&bookmark("older", 1_000)).unwrap(), stored);
+        let newest = bookmark("newest", 3_000);
+        assert_eq!(stored.merge(&newest).unwrap(), newest);
+    }
+
+    #[test]
+    fn deletion_of_a_crdt_record_is_last_write_wins() {
+        let mut tags = OrSet::default();
+        tags.insert("fiqh".to_string(), "phone");
+        let stored = tags_record(&tags, 1_000);
+        let deleted = SyncRecord {
+            data: None,
+            modified: 2_000,
+            ..stored.clone()
+        };
+
+        assert_eq!(stored.merge(&deleted).unwrap().data, None);
+        assert_eq!(deleted.merge(&stored).unwrap().data, None);
+    }
+
+    fn test_key() -> LibraryKey {
+        // Parameter Argon2 sekecil mungkin supaya test cepat
+        let header = crate::crypto::KeyHeader {
+            salt: STANDARD.encode([7u8; 16]),
+            params: crate::crypto::KdfParams {
+                m_cost: 64,
+                t_cost: 1,
+                p_cost: 1,
+            },
+        };
+        LibraryKey::derive("correct horse", &header).unwrap()
+    }
+
+    #[test]
+    fn sealed_records_hide_collection_names_and_round_trip() {
+        let key = test_key();
+        let mut members = OrSet::default();
+        members.insert("book".to_string(), "phone");
+        let record = SyncRecord {
+            kind: RecordKind::Collection,
+            id: "Tafsir".to_string(),
+            data: Some(serde_json::to_value(&members).unwrap()),
+            modified: 1_000,
+            version: 0,
+            encrypted: false,
+        };
+
+        let sealed = record.seal(&key).unwrap();
+        assert!(sealed.encrypted);
+        assert_ne!(sealed.id, "Tafsir");
+        assert!(!serde_json::to_string(&sealed).unwrap().contains("Tafsir"));
+
+        let state = EncryptionState {
+            key: Some(key),
+            ..Default::default()
+        };
+        assert_eq!(sealed.open(&state).unwrap(), record);
+        assert!(matches!(
+            sealed.open(&EncryptionState::default()),
+            Err(CryptoError::OtherKey(_))
+        ));
+    }
+
+    #[test]
+    fn plaintext_records_are_refused_once_encryption_is_on() {
+        let mut set = OrSet::default();
+        set.insert("fiqh".to_string(), "server");
+        let plain = tags_record(&set, 1_000);
+        let tombstone = SyncRecord {
+            data: None,
+            ..plain.clone()
+        };
+        assert_eq!(plain.open(&EncryptionState::default()).unwrap(), plain);
+
+        let state = EncryptionState {
+            key: Some(test_key()),
+            ..Default::default()
+        };
+        for record in [&plain, &tombstone] {
+            assert!(matches!(
+                record.open(&state),
+                Err(CryptoError::NotEncrypted)
+            ));
+        }
+        let sealed = tombstone.seal(state.key.as_ref().unwrap()).unwrap();
+        assert_eq!(sealed.open(&state).unwrap(), sealed);
+    }
+
+    #[test]
+    fn sync_keys_are_salted_with_the_username() {
+        let ahmad = SyncConfig::new("http://host/", "ahmad", "rahasia").unwrap();
+        assert_eq!(ahmad.server_url, "http://host");
+        assert_eq!(ahmad.key.len(), 64);
+        assert_eq!(
+            ahmad,
+            SyncConfig::new("http://host", "ahmad", "rahasia").unwrap()
+        );
+
+        let fatimah = SyncConfig::new("http://host", "fatimah", "rahasia").unwrap();
+        assert_ne!(ahmad.key, fatimah.key);
+        let unsalted = Sha256::digest(b"ahmad:rahasia");
+        assert_ne!(ahmad.key, format!("{unsalted:x}"));
+    }
+
+    #[test]
+    fn encrypted_records_are_last_write_wins_on_the_server() {
+        let key = test_key();
+        let mut phone = OrSet::default();
+        phone.insert("fiqh".to_string(), "phone");
+        let mut laptop = OrSet::default();
+        laptop.insert("tafsir".to_string(), "laptop");
+
+        let older = tags_record(&phone, 1_000).seal(&key).unwrap();
+        let newer = tags_record(&laptop, 2_000).seal(&key).unwrap();
+        assert_eq!(older.merge(&newer).unwrap(), newer);
+        assert_eq!(newer.merge(&older).unwrap(), newer);
+    }
+
+    #[tokio::test]
+    async fn sync_library_pushes_pending_changes_and_applies_pulled_ones() {
+        use crate::file_picker::FileSelection;
+        use crate::utils::encode_data_url;
+        use mockito::{Matcher, Server};
+
+        crate::storage::use_test_data_dir();
+        let mut library = Library::new();
+        library
+            .add_book_from_file(FileSelection {
+                name: "arbain.txt".to_string(),
+                r#type: "text/plain".to_string(),
+                size: 5,
+                data: encode_data_url("text/plain", b"hadis"),
+            })
+            .unwrap();
+        let book_id = library.books[0].id.clone();
+        library.add_bookmark(&book_id, 3, None).unwrap();
+        let ours = library.bookmarks[0].id.clone();
+
+        let theirs = Bookmark {
+            id: "from-phone".to_string(),
+            book_id: book_id.clone(),
+            position: 7,
+            label: Some("Hadis 7".to_string()),
+            created_date: "2024-01-31T12:00:00+00:00".to_string(),
+        };
+        let remote = SyncRecord {
+            kind: RecordKind::Bookmark,
+            id: theirs.id.clone(),
+            data: Some(serde_json::to_value(&theirs).unwrap()),
+            modified: 1_000,
+            version: 4,
+            encrypted: false,
+        };
+
+        let mut server = Server::new_async().await;
+        let config = SyncConfig::new(&server.url(), "ahmad", "rahasia").unwrap();
+        let pushed = server
+            .mock("POST", "/api/v1/push")
+            .match_header("x-auth-user", "ahmad")
+            .match_header("x-auth-key", config.key.as_str())
+            .match_body(Matcher::Regex(format!("\"id\":\"{ours}\"")))
+            .with_body(r#"{"version":4}"#)
+            .create_async()
+            .await;
+        let pulled = server
+            .mock("GET", "/api/v1/pull?since=0")
+            .with_body(
+                serde_json::to_string(&PullResponse {
+                    version: 4,
+                    records: vec![remote],
+                })
+                .unwrap(),
+            )
+            .create_async()
+            .await;
+
+        library
+            .set_sync_server_config(Some(config.clone()))
+            .unwrap();
+        sync_library(&SyncClient::new(config), &mut library)
+            .await
+            .unwrap();
+        pushed.assert_async().await;
+        pulled.assert_async().await;
+
+        assert!(library.sync.pending.is_empty());
+        assert_eq!(library.sync.last_version, 4);
+        assert!(library.bookmarks.contains(&theirs));
+        assert!(library.bookmarks.iter().any(|b| b.id == ours));
+        // Bookmark dari server tidak dikirim balik
+        assert!(library.pending_records().is_empty());
+
+        // Akun lain di server yang sama mulai dari awal
+        let config = library.sync_server_config().unwrap();
+        library
+            .set_sync_server_config(Some(config.clone()))
+            .unwrap();
+        assert_eq!(library.sync.last_version, 4);
+        let other = SyncConfig::new(&config.server_url, "fatimah", "rahasia").unwrap();
+        library.set_sync_server_config(Some(other)).unwrap();
+        assert_eq!(library.sync.last_version, 0);
+    }
+}
@@ -1,3 +1,6 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
 pub fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -22,3 +25,33 @@ pub fn format_date(date_str: &str) -> String {
         "Unknown date".to_string()
     }
 }
+
+/// Decode a `data:<mime>;base64,<payload>` URL (as produced by the file picker)
+/// into raw bytes. Plain base64 without the header is accepted as well.
+pub fn decode_data_url(data: &str) -> Result<Vec<u8>, String> {
+    let payload = match data.split_once(',') {
+        Some((header, payload)) if header.starts_with("data:") => {
+            if !header.ends_with(";base64") {
+                return Err("Data URL is not base64 encoded".to_string());
+            }
+            payload
+        }
+        _ => data,
+    };
+
+    STANDARD
+        .decode(payload.trim())
+        .map_err(|e| format!("Invalid base64 data: {e}"))
+}
+
+/// Inverse of [`decode_data_url`]
+pub fn encode_data_url(mime_type: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime_type, STANDARD.encode(bytes))
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,182 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::file_picker::book_files;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use tracing::warn;
+
+/// =======================
+/// Watched Folders
+/// =======================
+///
+/// Folder yang dipantau aplikasi desktop (misalnya folder Downloads): file
+/// EPUB/PDF/TXT baru di dalamnya diimpor otomatis. `files` mengingat setiap
+/// file yang sudah dilihat, supaya scan berikutnya (termasuk saat aplikasi
+/// baru dibuka) bisa membedakan file baru, berubah, diganti nama, dan dihapus.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WatchState {
+    /// Absolute paths, in the order they were added
+    pub folders: Vec<String>,
+    /// Path of every book file seen in those folders
+    pub files: BTreeMap<String, WatchedFile>,
+}
+
+/// What a file looked like when it was last imported
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WatchedFile {
+    /// Empty when the import failed; it's retried once the file changes
+    pub book_id: String,
+    pub size: u64,
+    /// Seconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// A file from a watched folder handed to the import queue. `file.book_id`
+/// is set when the file changed and replaces that book's file; otherwise it
+/// is imported as a new book.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchedItem {
+    pub path: PathBuf,
+    pub file: WatchedFile,
+}
+
+/// What is in the watched folders right now, from [`scan_folders`]
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderScan {
+    /// The watched folders that exist; missing ones (an unplugged drive)
+    /// are left out, so their files aren't taken as deleted
+    pub folders: Vec<PathBuf>,
+    /// Every book file found, with its size and modification time
+    pub files: BTreeMap<String, (u64, u64)>,
+}
+
+/// Look through the watched folders. This walks the disk, so run it off
+/// the UI thread and hand the result to
+/// [`Library::apply_folder_scan`](crate::library::Library::apply_folder_scan).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn scan_folders(folders: &[String]) -> FolderScan {
+    let folders: Vec<PathBuf> = (folders.iter())
+        .map(PathBuf::from)
+        .filter(|f| f.is_dir())
+        .collect();
+
+    let mut files = BTreeMap::new();
+    for path in book_files(&folders) {
+        match fs::metadata(&path) {
+            Ok(meta) => {
+                let modified = (meta.modified().ok())
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                files.insert(path.to_string_lossy().into_owned(), (meta.len(), modified));
+            }
+            Err(e) => warn!("Skipping {}: {}", path.display(), e),
+        }
+    }
+    FolderScan { folders, files }
+}
+
+/// Changes found by one rescan that were applied right away; new and
+/// changed files go through the import queue
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WatchReport {
+    pub renamed: usize,
+    /// Files deleted from a folder; their books stay in the library
+    pub removed: usize,
+    /// Files that are already in the library under the same name and size
+    pub linked: usize,
+}
+
+impl WatchReport {
+    pub fn is_empty(&self) -> bool {
+        self.renamed == 0 && self.removed == 0 && self.linked == 0
+    }
+}
+
+impl fmt::Display for WatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Watched folders: {} renamed, {} removed, {} already in the library",
+            self.renamed, self.removed, self.linked
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::ImportItem;
+    use crate::library::Library;
+
+    async fn import(library: &mut Library, items: Vec<ImportItem>) {
+        let mut queue = library.start_import(items).unwrap();
+        while let Some(events) = queue.next_batch().await {
+            library.apply_import_events(events).unwrap();
+        }
+    }
+
+    fn rescan(library: &mut Library) -> (WatchReport, Vec<ImportItem>) {
+        let scan = scan_folders(library.watched_folders());
+        library.apply_folder_scan(scan).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rescans_go_through_the_import_queue() {
+        let folder = crate::storage::use_test_data_dir().join("Downloads");
+        fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("arbain.txt");
+        fs::write(&file, "Innamal a'malu binniyat").unwrap();
+
+        let mut library = Library::new();
+        library
+            .add_watched_folder(&folder.to_string_lossy())
+            .unwrap();
+
+        // File baru: diimpor lewat antrean, lalu diingat untuk scan berikutnya
+        let (_, items) = rescan(&mut library);
+        assert_eq!(items.len(), 1);
+        import(&mut library, items).await;
+        assert_eq!(library.books.len(), 1);
+        let book_id = library.books[0].id.clone();
+        let path = file.to_string_lossy().into_owned();
+        assert_eq!(library.watch.files[&path].book_id, book_id);
+
+        let (report, items) = rescan(&mut library);
+        assert!(report.is_empty() && items.is_empty());
+
+        // File diubah: buku yang sama mendapat file baru
+        fs::write(
+            &file,
+            "Innamal a'malu binniyat, wa innama likulli imri'in ma nawa",
+        )
+        .unwrap();
+        let (_, items) = rescan(&mut library);
+        assert_eq!(items.len(), 1);
+        import(&mut library, items).await;
+        assert_eq!(library.books.len(), 1);
+        assert_eq!(library.books[0].id, book_id);
+        assert_eq!(library.books[0].size, fs::metadata(&file).unwrap().len());
+        assert!(library.import_results[0].result.is_ok());
+
+        // Diganti nama: tidak ada yang perlu diimpor
+        let renamed = folder.join("arbain nawawi.txt");
+        fs::rename(&file, &renamed).unwrap();
+        let (report, items) = rescan(&mut library);
+        assert_eq!(report.renamed, 1);
+        assert!(items.is_empty());
+        assert_eq!(library.books[0].file_name, "arbain nawawi.txt");
+
+        fs::remove_file(&renamed).unwrap();
+        let (report, _) = rescan(&mut library);
+        assert_eq!(report.removed, 1);
+        assert_eq!(library.books.len(), 1);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::storage::{
+    migrate, schema_version, to_value, StorageError, StoredLibrary, StoredLibraryRef,
+    SCHEMA_VERSION,
+};
+use js_sys::{Array, Function, Promise};
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{error, info};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{IdbDatabase, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+const DB_NAME: &str = "bayt-alhikmah";
+const DB_VERSION: u32 = 1;
+const LIBRARY_STORE: &str = "library";
+const FILES_STORE: &str = "files";
+
+/// =======================
+/// IndexedDB Storage
+/// =======================
+///
+/// Penyimpanan library di browser. Dokumen library (format yang sama dengan
+/// `library.json` di desktop) disimpan tanpa isi file buku; data URL tiap
+/// buku disimpan terpisah di store `files` dengan id buku sebagai key, dan
+/// hanya ditulis sekali. Jadi menyimpan posisi baca tidak ikut menulis ulang
+/// ratusan MB buku.
+///
+/// IndexedDB asinkron sedangkan `Library` menyimpan secara sinkron, jadi
+/// penulisan diantrekan: save yang datang selagi menulis digabung, dan yang
+/// terakhir yang menang.
+///
+/// Key of the library document, named like the desktop file
+pub const LIBRARY_KEY: &str = "library.json";
+
+thread_local! {
+    static DB: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+    /// Books whose file is already in the files store
+    static STORED_FILES: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+    static PENDING: RefCell<Option<Pending>> = const { RefCell::new(None) };
+    static WRITING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Writes not yet committed
+struct Pending {
+    document: String,
+    /// Book id -> data URL
+    files: BTreeMap<String, String>,
+    removed: HashSet<String>,
+}
+
+impl Pending {
+    fn merge(&mut self, newer: Pending) {
+        self.document = newer.document;
+        for id in newer.removed {
+            self.files.remove(&id);
+            self.removed.insert(id);
+        }
+        for (id, data) in newer.files {
+            self.removed.remove(&id);
+            self.files.insert(id, data);
+        }
+    }
+}
+
+/// Load the library saved in this browser, upgrading older schemas like
+/// [`crate::storage::load_library`] does. `Ok(None)` when nothing is saved yet.
+pub async fn load_library() -> Result<Option<StoredLibrary>, StorageError> {
+    let db = database().await?;
+    let tx = db
+        .transaction_with_str_sequence_and_mode(&store_names(), IdbTransactionMode::Readonly)
+        .map_err(js_error)?;
+    let library = tx.object_store(LIBRARY_STORE).map_err(js_error)?;
+    let files = tx.object_store(FILES_STORE).map_err(js_error)?;
+
+    // Semua request dikirim sebelum ditunggu, supaya transaksinya tidak
+    // keburu selesai di antaranya
+    let document = library.get(&LIBRARY_KEY.into()).map_err(js_error)?;
+    let keys = files.get_all_keys().map_err(js_error)?;
+    let values = files.get_all().map_err(js_error)?;
+
+    let Some(text) = request(&document).await?.as_string() else {
+        return Ok(None);
+    };
+    let keys = Array::from(&request(&keys).await?);
+    let values = Array::from(&request(&values).await?);
+    let mut files: HashMap<String, String> = (keys.iter().zip(values.iter()))
+        .filter_map(|(key, value)| Some((key.as_string()?, value.as_string()?)))
+        .collect();
+
+    let mut stored = parse_document(&text).await?;
+    for book in &mut stored.books {
+        if let Some(data) = files.remove(&book.id) {
+            book.file_data = data;
+        }
+    }
+    STORED_FILES.with(|stored_files| {
+        *stored_files.borrow_mut() = (stored.books.iter())
+            .filter(|b| !b.file_data.is_empty())
+            .map(|b| b.id.clone())
+            .collect();
+    });
+
+    info!("Loaded {} books from IndexedDB", stored.books.len());
+    Ok(Some(stored))
+}
+
+/// Older schemas are migrated and written back, keeping the original under
+/// another key first
+async fn parse_document(text: &str) -> Result<StoredLibrary, StorageError> {
+    let doc: Value =
+        serde_json::from_str(text).map_err(|e| StorageError::Corrupt(e.to_string()))?;
+
+    let version = schema_version(&doc);
+    if version > SCHEMA_VERSION {
+        return Err(StorageError::NewerSchema {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    if version == SCHEMA_VERSION {
+        return serde_json::from_value(doc).map_err(|e| StorageError::Corrupt(e.to_string()));
+    }
+
+    let backup = format!("library.schema{version}.json");
+    let migration_error = |reason: String| StorageError::Migration {
+        from: version,
+        reason,
+        backup: Some(backup.clone().into()),
+    };
+    let stored = migrate(doc).map_err(migration_error)?;
+    let upgraded = to_value(&StoredLibraryRef {
+        books: &stored.books,
+        annotations: &stored.annotations,
+        bookmarks: &stored.bookmarks,
+        preferences: &stored.preferences,
+        sync: &stored.sync,
+        reading_state: &stored.reading_state,
+        watch: &stored.watch,
+    })?;
+
+    let db = database().await?;
+    let tx = db
+        .transaction_with_str_and_mode(LIBRARY_STORE, IdbTransactionMode::Readwrite)
+        .map_err(js_error)?;
+    let store = tx.object_store(LIBRARY_STORE).map_err(js_error)?;
+    store
+        .put_with_key(&text.into(), &backup.as_str().into())
+        .map_err(js_error)?;
+    store
+        .put_with_key(&upgraded.to_string().into(), &LIBRARY_KEY.into())
+        .map_err(js_error)?;
+    committed(&tx)
+        .await
+        .map_err(|e| migration_error(e.to_string()))?;
+
+    info!(
+        "Library upgraded from schema {} to {}",
+        version, SCHEMA_VERSION
+    );
+    Ok(stored)
+}
+
+/// Queue the library for writing. Only book files not stored yet are sent;
+/// files of books that are gone are deleted.
+pub fn save_library(library: &StoredLibraryRef) -> Result<(), StorageError> {
+    let mut doc = to_value(library)?;
+
+    let mut files = BTreeMap::new();
+    let mut with_file = HashSet::new();
+    let books = doc.get_mut("books").and_then(Value::as_array_mut);
+    for book in books.into_iter().flatten().filter_map(Value::as_object_mut) {
+        let Some(id) = book.get("id").and_then(Value::as_str).map(str::to_string) else {
+            continue;
+        };
+        let Some(Value::String(data)) =
+            book.insert("file_data".into(), Value::String(String::new()))
+        else {
+            continue;
+        };
+        if data.is_empty() {
+            continue;
+        }
+        if !STORED_FILES.with(|stored| stored.borrow().contains(&id)) {
+            files.insert(id.clone(), data);
+        }
+        with_file.insert(id);
+    }
+
+    let removed = STORED_FILES.with(|stored| {
+        let mut stored = stored.borrow_mut();
+        let removed: HashSet<String> = stored.difference(&with_file).cloned().collect();
+        *stored = with_file;
+        removed
+    });
+
+    enqueue(Pending {
+        document: doc.to_string(),
+        files,
+        removed,
+    });
+    Ok(())
+}
+
+fn enqueue(pending: Pending) {
+    PENDING.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        match slot.as_mut() {
+            Some(queued) => queued.merge(pending),
+            None => *slot = Some(pending),
+        }
+    });
+    if !WRITING.with(|writing| writing.replace(true)) {
+        spawn_local(write_pending());
+    }
+}
+
+async fn write_pending() {
+    while let Some(pending) = PENDING.with(|slot| slot.borrow_mut().take()) {
+        if let Err(e) = write(&pending).await {
+            error!("Failed to save the library: {}", e);
+            // File yang gagal ditulis dikirim lagi oleh save berikutnya
+            STORED_FILES.with(|stored| {
+                let mut stored = stored.borrow_mut();
+                for id in pending.files.keys() {
+                    stored.remove(id);
+                }
+            });
+        }
+    }
+    WRITING.with(|writing| writing.set(false));
+}
+
+async fn write(pending: &Pending) -> Result<(), StorageError> {
+    let db = database().await?;
+    let tx = db
+        .transaction_with_str_sequence_and_mode(&store_names(), IdbTransactionMode::Readwrite)
+        .map_err(js_error)?;
+
+    let library = tx.object_store(LIBRARY_STORE).map_err(js_error)?;
+    library
+        .put_with_key(&pending.document.as_str().into(), &LIBRARY_KEY.into())
+        .map_err(js_error)?;
+
+    let files = tx.object_store(FILES_STORE).map_err(js_error)?;
+    for (id, data) in &pending.files {
+        files
+            .put_with_key(&data.as_str().into(), &id.as_str().into())
+            .map_err(js_error)?;
+    }
+    for id in &pending.removed {
+        files.delete(&id.as_str().into()).map_err(js_error)?;
+    }
+
+    committed(&tx).await
+}
+
+/* =========================
+   INDEXEDDB HELPERS
+========================= */
+
+/// The database, opened (and created on first use) once per page
+async fn database() -> Result<IdbDatabase, StorageError> {
+    if let Some(db) = DB.with(|db| db.borrow().clone()) {
+        return Ok(db);
+    }
+
+    let factory = (web_sys::window())
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .ok_or_else(|| StorageError::Io("This browser can't store data (no IndexedDB)".into()))?;
+    let open = factory
+        .open_with_u32(DB_NAME, DB_VERSION)
+        .map_err(js_error)?;
+
+    let on_upgrade = Closure::once_into_js({
+        let open = open.clone();
+        move || {
+            let Ok(db) = open.result() else {
+                return;
+            };
+            let db: IdbDatabase = db.unchecked_into();
+            for name in [LIBRARY_STORE, FILES_STORE] {
+                if !db.object_store_names().contains(name) {
+                    if let Err(e) = db.create_object_store(name) {
+                        error!("Failed to create {} store: {:?}", name, e);
+                    }
+                }
+            }
+        }
+    });
+    open.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+    let db: IdbDatabase = request(&open).await?.unchecked_into();
+    DB.with(|cell| *cell.borrow_mut() = Some(db.clone()));
+    Ok(db)
+}
+
+fn store_names() -> JsValue {
+    Array::of2(&LIBRARY_STORE.into(), &FILES_STORE.into()).into()
+}
+
+/// Result of a request once it succeeds
+async fn request(req: &IdbRequest) -> Result<JsValue, StorageError> {
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let on_success = Closure::once_into_js({
+            let req = req.clone();
+            move || {
+                let result = req.result().unwrap_or(JsValue::UNDEFINED);
+                let _ = resolve.call1(&JsValue::NULL, &result);
+            }
+        });
+        let on_error = Closure::once_into_js({
+            let req = req.clone();
+            move || {
+                let message = (req.error().ok().flatten())
+                    .map(|e| e.message())
+                    .unwrap_or_else(|| "IndexedDB request failed".to_string());
+                let _ = reject.call1(&JsValue::NULL, &message.into());
+            }
+        });
+        req.set_onsuccess(Some(on_success.unchecked_ref()));
+        req.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    JsFuture::from(promise).await.map_err(js_error)
+}
+
+/// Resolves once everything in the transaction is written
+async fn committed(tx: &IdbTransaction) -> Result<(), StorageError> {
+    let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+        let on_complete = Closure::once_into_js(move || {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+        // Quota penuh muncul sebagai abort
+        let on_abort = Closure::once_into_js(move || {
+            let _ = reject.call1(&JsValue::NULL, &"Browser storage is full".into());
+        });
+        tx.set_oncomplete(Some(on_complete.unchecked_ref()));
+        tx.set_onabort(Some(on_abort.unchecked_ref()));
+    });
+    JsFuture::from(promise).await.map(|_| ()).map_err(js_error)
+}
+
+fn js_error(e: JsValue) -> StorageError {
+    StorageError::Io(e.as_string().unwrap_or_else(|| format!("{e:?}")))
+}
@@ -0,0 +1,732 @@
+use crate::formats::opf::parse_xml;
+use crate::library::Library;
+use crate::models::BookMetadata;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::{info, warn};
+
+/// Layout folder WebDAV, sama seperti isi arsip backup:
+/// - `<url>/library.json` (index tanpa file buku)
+/// - `<url>/books/<id>.<ext>`
+const INDEX_FILE: &str = "library.json";
+const BOOKS_DIR: &str = "books";
+
+/// Percobaan ulang jika index diubah perangkat lain di tengah sinkronisasi
+const MAX_ATTEMPTS: usize = 3;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:getetag/></d:prop></d:propfind>"#;
+
+/// =======================
+/// Data Model
+/// =======================
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WebDavConfig {
+    /// Folder URL, e.g. `https://cloud.example.com/remote.php/dav/files/me/Hikmah`
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// What this device knows about the remote folder since the last sync.
+/// Stored with the library so the next sync only transfers what changed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WebDavState {
+    /// ETag of `library.json` as we last wrote or read it
+    pub index_etag: Option<String>,
+    /// Book id -> ETag of its file in `books/`
+    pub blob_etags: BTreeMap<String, String>,
+    /// Books and bookmarks present at the last sync; tells a local delete
+    /// apart from something added on another device
+    pub known_books: BTreeSet<String>,
+    pub known_bookmarks: BTreeSet<String>,
+    /// Salt of the key the folder was last written with (None = plaintext).
+    /// When it differs from ours, every file is uploaded again.
+    pub key_salt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebDavReport {
+    pub downloaded: usize,
+    pub uploaded: usize,
+    pub deleted: usize,
+    pub index_changed: bool,
+}
+
+impl std::fmt::Display for WebDavReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "WebDAV sync: {} downloaded, {} uploaded, {} removed",
+            self.downloaded, self.uploaded, self.deleted
+        )?;
+        if !self.index_changed {
+            write!(f, " (no changes from other devices)")?;
+        }
+        Ok(())
+    }
+}
+
+enum RemoteIndex {
+    Missing,
+    NotModified,
+    Changed {
+        bytes: Vec<u8>,
+        etag: Option<String>,
+    },
+}
+
+enum IndexWrite {
+    Written {
+        etag: Option<String>,
+    },
+    /// Another device wrote the index after we read it
+    Conflict,
+}
+
+/// =======================
+/// Client
+/// =======================
+
+#[derive(Debug, Clone)]
+pub struct WebDavClient {
+    config: WebDavConfig,
+    http: Client,
+}
+
+impl WebDavClient {
+    pub fn new(mut config: WebDavConfig) -> Self {
+        config.url = config.url.trim_end_matches('/').to_string();
+        Self {
+            config,
+            http: Client::new(),
+        }
+    }
+
+    /// Check the URL and credentials; creates the folder if it doesn't exist
+    pub async fn check(&self) -> Result<(), String> {
+        self.ensure_folders().await
+    }
+
+    async fn ensure_folders(&self) -> Result<(), String> {
+        for url in [self.url(""), self.url(BOOKS_DIR)] {
+            let response = self
+                .request(method("MKCOL"), &format!("{url}/"))
+                .send()
+                .await
+                .map_err(network_error)?;
+
+            // 405 = folder sudah ada
+            match response.status() {
+                s if s.is_success() || s == StatusCode::METHOD_NOT_ALLOWED => {}
+                status => return Err(status_error(status)),
+            }
+        }
+        Ok(())
+    }
+
+    /// `If-None-Match` with the last known ETag, so an unchanged index isn't downloaded
+    async fn get_index(&self, etag: Option<&str>) -> Result<RemoteIndex, String> {
+        let mut request = self.request(Method::GET, &self.url(INDEX_FILE));
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await.map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(RemoteIndex::Missing),
+            StatusCode::NOT_MODIFIED => Ok(RemoteIndex::NotModified),
+            s if s.is_success() => {
+                let etag = etag_of(&response);
+                let bytes = response.bytes().await.map_err(network_error)?;
+                Ok(RemoteIndex::Changed {
+                    bytes: bytes.to_vec(),
+                    etag,
+                })
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// Only overwrites the index we last saw (`If-Match`), or creates it if
+    /// there is none yet
+    async fn put_index(&self, bytes: Vec<u8>, etag: Option<&str>) -> Result<IndexWrite, String> {
+        let request = self
+            .request(Method::PUT, &self.url(INDEX_FILE))
+            .header(CONTENT_TYPE, "application/json")
+            .body(bytes);
+        let request = match etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+        let response = request.send().await.map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(IndexWrite::Conflict),
+            s if s.is_success() => {
+                // Tidak semua server mengirim ETag setelah PUT
+                let etag = match etag_of(&response) {
+                    Some(etag) => Some(etag),
+                    None => self.head_etag(&self.url(INDEX_FILE)).await?,
+                };
+                Ok(IndexWrite::Written { etag })
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    /// File name -> ETag of everything in `books/`
+    async fn list_books(&self) -> Result<BTreeMap<String, String>, String> {
+        let response = self
+            .request(method("PROPFIND"), &format!("{}/", self.url(BOOKS_DIR)))
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(BTreeMap::new()),
+            s if s.is_success() => {}
+            status => return Err(status_error(status)),
+        }
+
+        let xml = response.text().await.map_err(network_error)?;
+        parse_multistatus(&xml)
+    }
+
+    async fn get_book(&self, name: &str) -> Result<(Vec<u8>, Option<String>), String> {
+        let response = self
+            .request(Method::GET, &self.book_url(name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() => {
+                let etag = etag_of(&response);
+                let bytes = response.bytes().await.map_err(network_error)?;
+                Ok((bytes.to_vec(), etag))
+            }
+            status => Err(status_error(status)),
+        }
+    }
+
+    async fn put_book(
+        &self,
+        name: &str,
+        mime: &str,
+        bytes: Vec<u8>,
+    ) -> Result<Option<String>, String> {
+        let url = self.book_url(name);
+        let response = self
+            .request(Method::PUT, &url)
+            .header(CONTENT_TYPE, mime)
+            .body(bytes)
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() => match etag_of(&response) {
+                Some(etag) => Ok(Some(etag)),
+                None => self.head_etag(&url).await,
+            },
+            status => Err(status_error(status)),
+        }
+    }
+
+    async fn delete_book(&self, name: &str) -> Result<(), String> {
+        let response = self
+            .request(Method::DELETE, &self.book_url(name))
+            .send()
+            .await
+            .map_err(network_error)?;
+
+        match response.status() {
+            s if s.is_success() || s == StatusCode::NOT_FOUND => Ok(()),
+            status => Err(status_error(status)),
+        }
+    }
+
+    async fn head_etag(&self, url: &str) -> Result<Option<String>, String> {
+        let response = self
+            .request(Method::HEAD, url)
+            .send()
+            .await
+            .map_err(network_error)?;
+        Ok(etag_of(&response))
+    }
+
+    /* =========================
+       HTTP HELPERS
+    ========================= */
+
+    fn url(&self, path: &str) -> String {
+        if path.is_empty() {
+            self.config.url.clone()
+        } else {
+            format!("{}/{}", self.config.url, path)
+        }
+    }
+
+    fn book_url(&self, name: &str) -> String {
+        self.url(&format!("{BOOKS_DIR}/{name}"))
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        self.http
+            .request(method, url)
+            .basic_auth(&self.config.username, Some(&self.config.password))
+    }
+}
+
+/// =======================
+/// Sync
+/// =======================
+///
+/// Satu putaran penuh:
+/// 1. unduh index jika ETag-nya berubah, lalu gabungkan ke library
+/// 2. unduh file buku yang belum ada (atau berubah), unggah yang belum ada di server
+/// 3. unggah index dengan `If-Match`; jika perangkat lain lebih dulu, ulangi
+pub async fn sync_library(
+    client: &WebDavClient,
+    library: &mut Library,
+) -> Result<WebDavReport, String> {
+    client.ensure_folders().await?;
+    let mut report = WebDavReport::default();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let etag = library.sync.webdav.index_etag.clone();
+        match client.get_index(etag.as_deref()).await? {
+            RemoteIndex::Missing => library.sync.webdav.index_etag = None,
+            RemoteIndex::NotModified => {}
+            RemoteIndex::Changed { bytes, etag } => {
+                let bytes = library.decrypt_synced(&bytes)?;
+                library.merge_webdav_index(&bytes)?;
+                library.sync.webdav.index_etag = etag;
+                report.index_changed = true;
+            }
+        }
+
+        sync_blobs(client, library, &mut report).await?;
+
+        let index = library.encrypt_synced(library.webdav_index()?)?;
+        let etag = library.sync.webdav.index_etag.clone();
+        match client.put_index(index, etag.as_deref()).await? {
+            IndexWrite::Written { etag } => {
+                library.finish_webdav_sync(etag)?;
+                info!("{}", report);
+                return Ok(report);
+            }
+            IndexWrite::Conflict => warn!(
+                "library.json changed on the server during sync (attempt {})",
+                attempt
+            ),
+        }
+    }
+
+    Err("The library on the server keeps changing; try again later".to_string())
+}
+
+async fn sync_blobs(
+    client: &WebDavClient,
+    library: &mut Library,
+    report: &mut WebDavReport,
+) -> Result<(), String> {
+    let remote = client.list_books().await?;
+    let mut local_names = BTreeSet::new();
+    let current_salt = (library.sync.encryption.key.as_ref()).map(|k| k.header.salt.clone());
+    // Key berubah (atau enkripsi baru dinyalakan/dimatikan): tulis ulang semua file
+    let rekey = library.sync.webdav.key_salt != current_salt;
+
+    for book in library.books.clone() {
+        let name = blob_name(&book);
+        local_names.insert(name.clone());
+        let has_file = book.has_file();
+        let known_etag = library.sync.webdav.blob_etags.get(&book.id).cloned();
+
+        match remote.get(&name) {
+            // Sama-sama punya file tapi belum pernah sinkron: cukup catat ETag-nya
+            Some(remote_etag) if has_file && known_etag.is_none() && !rekey => {
+                library
+                    .sync
+                    .webdav
+                    .blob_etags
+                    .insert(book.id.clone(), remote_etag.clone());
+            }
+            // Belum ada filenya di perangkat ini, atau file di server sudah diganti
+            Some(remote_etag)
+                if !has_file || (!rekey && known_etag.as_ref() != Some(remote_etag)) =>
+            {
+                let (bytes, etag) = client.get_book(&name).await?;
+                // File dengan key yang tidak kita punya tidak mengunci sinkron;
+                // perangkat pemiliknya akan menulisnya ulang
+                let bytes = match library.sync.encryption.decrypt(&bytes) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        warn!("Skipping the file of '{}': {}", book.title, e);
+                        continue;
+                    }
+                };
+                library.attach_book_file(&book.id, &bytes)?;
+                let etag = etag.unwrap_or_else(|| remote_etag.clone());
+                library.sync.webdav.blob_etags.insert(book.id.clone(), etag);
+                report.downloaded += 1;
+            }
+            Some(_) if !rekey => {}
+            _ if has_file => {
+                let bytes = book.read_file()?;
+                let mime = match library.sync.encryption.key {
+                    Some(_) => "application/octet-stream",
+                    None => book.format.mime_type(),
+                };
+                let bytes = library.encrypt_synced(bytes)?;
+                let etag = client.put_book(&name, mime, bytes).await?;
+                match etag {
+                    Some(etag) => library.sync.webdav.blob_etags.insert(book.id.clone(), etag),
+                    None => library.sync.webdav.blob_etags.remove(&book.id),
+                };
+                report.uploaded += 1;
+            }
+            _ => warn!("'{}' has no file on this device or the server", book.title),
+        }
+    }
+
+    // File di server untuk buku yang sudah dihapus di perangkat ini
+    let known = &library.sync.webdav.known_books;
+    for name in remote.keys().filter(|n| !local_names.contains(*n)) {
+        let book_id = name.split('.').next().unwrap_or_default();
+        if known.contains(book_id) {
+            client.delete_book(name).await?;
+            report.deleted += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+/// `books/<id>.<ext>`, sama seperti di arsip backup
+pub fn blob_name(book: &BookMetadata) -> String {
+    format!("{}.{}", book.id, book.format.to_extension())
+}
+
+fn method(name: &str) -> Method {
+    Method::from_bytes(name.as_bytes()).expect("valid HTTP method")
+}
+
+fn etag_of(response: &reqwest::Response) -> Option<String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// `<d:multistatus>` -> file name -> ETag (folders are skipped)
+fn parse_multistatus(xml: &str) -> Result<BTreeMap<String, String>, String> {
+    let doc = parse_xml(xml).map_err(|e| format!("Invalid PROPFIND response: {e}"))?;
+    let mut files = BTreeMap::new();
+
+    for response in doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "response")
+    {
+        let text = |name: &str| {
+            response
+                .descendants()
+                .find(|n| n.tag_name().name() == name)
+                .and_then(|n| n.text())
+                .map(str::trim)
+        };
+
+        let (Some(href), Some(etag)) = (text("href"), text("getetag")) else {
+            continue;
+        };
+        if href.ends_with('/') {
+            continue;
+        }
+        if let Some(name) = href.rsplit('/').next().filter(|n| !n.is_empty()) {
+            files.insert(name.to_string(), etag.to_string());
+        }
+    }
+
+    Ok(files)
+}
+
+fn network_error(e: reqwest::Error) -> String {
+    format!("WebDAV error: {e}")
+}
+
+fn status_error(status: StatusCode) -> String {
+    match status {
+        StatusCode::UNAUTHORIZED => "WebDAV: wrong username or password".to_string(),
+        StatusCode::NOT_FOUND => "WebDAV: folder not found".to_string(),
+        status => format!("WebDAV server returned {status}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// WebDAV server kecil di memori: MKCOL, PROPFIND, GET/HEAD dengan
+    /// `If-None-Match`, PUT dengan `If-Match` / `If-None-Match: *`, DELETE.
+    /// Setiap PUT memberi ETag baru, seperti server sungguhan.
+    fn stub_server() -> WebDavClient {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let address = server.server_addr().to_ip().unwrap();
+
+        std::thread::spawn(move || {
+            // path -> (isi, ETag)
+            let mut files: BTreeMap<String, (Vec<u8>, String)> = BTreeMap::new();
+            let mut next_etag = 0;
+
+            for mut request in server.incoming_requests() {
+                let path = request.url().to_string();
+                let header = |name: &'static str| {
+                    request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv(name))
+                        .map(|h| h.value.to_string())
+                };
+                let (if_match, if_none_match) = (header("If-Match"), header("If-None-Match"));
+                let current = files.get(&path).map(|(_, etag)| etag.clone());
+
+                let (status, body, etag) = match request.method().as_str() {
+                    "MKCOL" => (201, vec![], None),
+                    "PROPFIND" => {
+                        let responses: String = files
+                            .iter()
+                            .filter(|(name, _)| name.starts_with(&path))
+                            .map(|(name, (_, etag))| {
+                                format!(
+                                    "<d:response><d:href>{name}</d:href><d:propstat><d:prop>\
+                                     <d:getetag>{etag}</d:getetag></d:prop></d:propstat></d:response>"
+                                )
+                            })
+                            .collect();
+                        let xml = format!(
+                            "<?xml version=\"1.0\"?><d:multistatus xmlns:d=\"DAV:\">\
+                             <d:response><d:href>{path}</d:href></d:response>{responses}</d:multistatus>"
+                        );
+                        (207, xml.into_bytes(), None)
+                    }
+                    "GET" | "HEAD" => match files.get(&path) {
+                        None => (404, vec![], None),
+                        Some((_, etag)) if if_none_match.as_ref() == Some(etag) => {
+                            (304, vec![], Some(etag.clone()))
+                        }
+                        Some((bytes, etag)) => (200, bytes.clone(), Some(etag.clone())),
+                    },
+                    "PUT" => {
+                        let allowed = match (&if_match, if_none_match.as_deref()) {
+                            (Some(expected), _) => current.as_ref() == Some(expected),
+                            (None, Some("*")) => current.is_none(),
+                            _ => true,
+                        };
+                        if allowed {
+                            let mut bytes = Vec::new();
+                            request.as_reader().read_to_end(&mut bytes).unwrap();
+                            next_etag += 1;
+                            let etag = format!("\"{next_etag}\"");
+                            files.insert(path, (bytes, etag.clone()));
+                            (201, vec![], Some(etag))
+                        } else {
+                            (412, vec![], None)
+                        }
+                    }
+                    "DELETE" => match files.remove(&path) {
+                        Some(_) => (204, vec![], None),
+                        None => (404, vec![], None),
+                    },
+                    _ => (405, vec![], None),
+                };
+
+                let mut response = tiny_http::Response::from_data(body).with_status_code(status);
+                if let Some(etag) = etag {
+                    response.add_header(tiny_http::Header::from_bytes("ETag", etag).unwrap());
+                }
+                let _ = request.respond(response);
+            }
+        });
+
+        WebDavClient::new(WebDavConfig {
+            url: format!("http://{address}/dav/Hikmah"),
+            username: "me".to_string(),
+            password: "secret".to_string(),
+        })
+    }
+
+    #[test]
+    fn parses_propfind_listing() {
+        let xml = r#"<?xml version="1.0"?>
+            <d:multistatus xmlns:d="DAV:">
+              <d:response><d:href>/dav/Hikmah/books/</d:href>
+                <d:propstat><d:prop><d:getetag>"dir"</d:getetag></d:prop></d:propstat></d:response>
+              <d:response><d:href>/dav/Hikmah/books/abc.epub</d:href>
+                <d:propstat><d:prop><d:getetag>"123"</d:getetag></d:prop></d:propstat></d:response>
+            </d:multistatus>"#;
+
+        let files = parse_multistatus(xml).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files["abc.epub"], "\"123\"");
+    }
+
+    #[test]
+    fn snapshot_sync_keeps_edits_made_meanwhile() {
+        use crate::file_picker::FileSelection;
+        use crate::utils::encode_data_url;
+
+        crate::storage::use_test_data_dir();
+        let text_book = |name: &str| FileSelection {
+            name: name.to_string(),
+            r#type: "text/plain".to_string(),
+            size: 5,
+            data: encode_data_url("text/plain", b"hadis"),
+        };
+
+        // Perangkat lain menambahkan satu buku ke folder WebDAV
+        let mut other = Library::new();
+        other.add_book_from_file(text_book("other.txt")).unwrap();
+        let remote_book = other.books[0].clone();
+        let remote_index = other.webdav_index().unwrap();
+
+        crate::storage::use_test_data_dir();
+        let mut library = Library::new();
+        library.add_book_from_file(text_book("mine.txt")).unwrap();
+        let mine = library.books[0].id.clone();
+
+        let mut snapshot = library.sync_snapshot();
+        // Selama request berjalan, pengguna menambah bookmark
+        library.add_bookmark(&mine, 2, None).unwrap();
+
+        snapshot.merge_webdav_index(&remote_index).unwrap();
+        snapshot
+            .attach_book_file(&remote_book.id, b"hadis")
+            .unwrap();
+        snapshot
+            .sync
+            .webdav
+            .blob_etags
+            .insert(remote_book.id.clone(), "\"b1\"".to_string());
+        snapshot
+            .finish_webdav_sync(Some("\"i1\"".to_string()))
+            .unwrap();
+
+        library.apply_webdav_sync(snapshot).unwrap();
+        assert_eq!(library.bookmarks.len(), 1);
+        let added = library.get_book(&remote_book.id).unwrap();
+        assert_eq!(added.read_file().unwrap(), b"hadis");
+        assert_eq!(library.sync.webdav.index_etag.as_deref(), Some("\"i1\""));
+        // Bookmark baru belum dikenal server, jadi ikut sync berikutnya
+        assert!(!library
+            .sync
+            .webdav
+            .known_bookmarks
+            .contains(&library.bookmarks[0].id));
+        assert!(library.sync.webdav.known_books.contains(&mine));
+    }
+
+    #[tokio::test]
+    async fn index_writes_use_etags() {
+        let client = stub_server();
+        client.check().await.unwrap();
+        assert!(matches!(
+            client.get_index(None).await.unwrap(),
+            RemoteIndex::Missing
+        ));
+
+        let IndexWrite::Written { etag: first } =
+            client.put_index(b"{}".to_vec(), None).await.unwrap()
+        else {
+            panic!("first write must create the index");
+        };
+        let first = first.expect("server sends an ETag");
+
+        // Index yang belum berubah tidak diunduh ulang
+        assert!(matches!(
+            client.get_index(Some(&first)).await.unwrap(),
+            RemoteIndex::NotModified
+        ));
+
+        // Perangkat lain menulis lebih dulu: tulisan dengan ETag lama ditolak
+        let IndexWrite::Written { etag: second } = client
+            .put_index(b"{\"a\":1}".to_vec(), Some(&first))
+            .await
+            .unwrap()
+        else {
+            panic!("write with the current ETag must succeed");
+        };
+        assert_ne!(second.as_deref(), Some(first.as_str()));
+        assert!(matches!(
+            client
+                .put_index(b"{}".to_vec(), Some(&first))
+                .await
+                .unwrap(),
+            IndexWrite::Conflict
+        ));
+        assert!(matches!(
+            client.put_index(b"{}".to_vec(), None).await.unwrap(),
+            IndexWrite::Conflict
+        ));
+    }
+
+    #[tokio::test]
+    async fn book_files_round_trip() {
+        let client = stub_server();
+        client.check().await.unwrap();
+
+        let etag = client
+            .put_book("b1.txt", "text/plain", b"hello".to_vec())
+            .await
+            .unwrap();
+        let listing = client.list_books().await.unwrap();
+        assert_eq!(listing.get("b1.txt"), etag.as_ref());
+
+        let (bytes, _) = client.get_book("b1.txt").await.unwrap();
+        assert_eq!(bytes, b"hello");
+
+        client.delete_book("b1.txt").await.unwrap();
+        assert!(client.list_books().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn libraries_sync_through_the_folder() {
+        use crate::file_picker::FileSelection;
+        use crate::utils::encode_data_url;
+
+        let client = stub_server();
+        crate::storage::use_test_data_dir();
+        let mut phone = Library::new();
+        phone
+            .add_book_from_file(FileSelection {
+                name: "arbain.txt".to_string(),
+                r#type: "text/plain".to_string(),
+                size: 5,
+                data: encode_data_url("text/plain", b"hadis"),
+            })
+            .unwrap();
+        let report = sync_library(&client, &mut phone).await.unwrap();
+        assert_eq!((report.uploaded, report.downloaded), (1, 0));
+
+        crate::storage::use_test_data_dir();
+        let mut laptop = Library::new();
+        let report = sync_library(&client, &mut laptop).await.unwrap();
+        assert_eq!((report.uploaded, report.downloaded), (0, 1));
+        assert_eq!(laptop.books[0].read_file().unwrap(), b"hadis");
+
+        // Tidak ada yang berubah: index tidak diunduh ataupun ditulis ulang
+        let report = sync_library(&client, &mut laptop).await.unwrap();
+        assert_eq!(report, WebDavReport::default());
+    }
+}
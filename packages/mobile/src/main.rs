@@ -1,6 +1,6 @@
 use dioxus::prelude::*;
 use hikmah_core::library::Library;
-use ui::component::library_view::LibraryView;
+use ui::route::Route;
 use ui::MAIN_CSS;
 
 fn main() {
@@ -11,6 +11,7 @@ fn main() {
 #[component]
 fn App() -> Element {
     let library = use_signal(Library::new);
+    use_context_provider(|| library);
 
     rsx! {
         link {
@@ -18,6 +19,6 @@ fn App() -> Element {
             href: MAIN_CSS
         }
 
-        LibraryView{library}
+        Router::<Route> {}
     }
 }
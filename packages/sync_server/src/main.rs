@@ -0,0 +1,197 @@
+mod routes;
+mod store;
+
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use store::Store;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8765";
+const APP_DIR: &str = "bayt-alhikmah-sync";
+
+const USAGE: &str = "\
+Usage: hikmah-sync-server [--addr <host:port>] [--data-dir <path>]
+
+  --addr       Address to listen on (default 127.0.0.1:8765)
+  --data-dir   Where user data is stored (default <data dir>/bayt-alhikmah-sync)";
+
+struct Args {
+    addr: SocketAddr,
+    data_dir: PathBuf,
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let store = match Store::new(&args.data_dir) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            error!("Cannot use {}: {}", args.data_dir.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let listener = match TcpListener::bind(args.addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Cannot listen on {}: {}", args.addr, e);
+            std::process::exit(1);
+        }
+    };
+    info!(
+        "Sync server listening on http://{} (data in {})",
+        args.addr,
+        args.data_dir.display()
+    );
+    serve(listener, store).await
+}
+
+async fn serve(listener: TcpListener, store: Arc<Store>) -> ! {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Accept failed: {}", e);
+                continue;
+            }
+        };
+
+        let store = store.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| routes::handle(req, store.clone()));
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut addr = DEFAULT_ADDR.to_string();
+    let mut data_dir = dirs::data_dir().map(|dir| dir.join(APP_DIR));
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().ok_or("--addr needs a value")?,
+            "--data-dir" => {
+                data_dir = Some(PathBuf::from(
+                    args.next().ok_or("--data-dir needs a value")?,
+                ))
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            other => return Err(format!("Unknown argument: {other}")),
+        }
+    }
+
+    Ok(Args {
+        addr: addr
+            .parse()
+            .map_err(|e| format!("Invalid address '{addr}': {e}"))?,
+        data_dir: data_dir.ok_or("No data directory found; pass --data-dir")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hikmah_core::crdt::OrSet;
+    use hikmah_core::sync::{RecordKind, SyncClient, SyncConfig, SyncRecord};
+
+    /// Server sungguhan di port acak, dengan data di folder sementara
+    async fn start_server() -> String {
+        let dir = std::env::temp_dir().join(format!(
+            "hikmah-sync-server-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let store = Arc::new(Store::new(&dir).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, store));
+        url
+    }
+
+    fn tags(tag: &str, device: &str, modified: i64) -> SyncRecord {
+        let mut set = OrSet::default();
+        set.insert(tag.to_string(), device);
+        SyncRecord {
+            kind: RecordKind::Tags,
+            id: "book".to_string(),
+            data: Some(serde_json::to_value(&set).unwrap()),
+            modified,
+            version: 0,
+            encrypted: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn clients_sync_through_the_server() {
+        let url = start_server().await;
+        let phone = SyncClient::new(SyncConfig::new(&url, "ahmad", "rahasia").unwrap());
+        let laptop =
+            SyncClient::new(SyncConfig::new(&format!("{url}/"), "ahmad", "rahasia").unwrap());
+
+        phone.register().await.unwrap();
+        assert_eq!(
+            laptop.register().await,
+            Err("Username is already registered".to_string())
+        );
+
+        let pulled = phone
+            .sync(&[tags("fiqh", "phone", 1_000)], 0)
+            .await
+            .unwrap();
+        assert_eq!(pulled.version, 1);
+        assert_eq!(pulled.records.len(), 1);
+
+        // Laptop belum pernah pull; push-nya digabung, bukan menimpa
+        let pulled = laptop
+            .sync(&[tags("tafsir", "laptop", 2_000)], 0)
+            .await
+            .unwrap();
+        assert_eq!(pulled.version, 2);
+        let merged: OrSet<String> =
+            serde_json::from_value(pulled.records[0].data.clone().unwrap()).unwrap();
+        assert_eq!(merged.iter().collect::<Vec<_>>(), ["fiqh", "tafsir"]);
+
+        // Phone hanya menerima yang berubah sejak version 1
+        let pulled = phone.pull(1).await.unwrap();
+        assert_eq!(pulled.records.len(), 1);
+        assert_eq!(pulled.records[0].version, 2);
+        assert!(phone.pull(2).await.unwrap().records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn wrong_credentials_are_rejected() {
+        let url = start_server().await;
+        SyncClient::new(SyncConfig::new(&url, "ahmad", "rahasia").unwrap())
+            .register()
+            .await
+            .unwrap();
+
+        let intruder = SyncClient::new(SyncConfig::new(&url, "ahmad", "tebakan").unwrap());
+        let unauthorized = Err("Unauthorized: wrong username or password".to_string());
+        assert_eq!(intruder.pull(0).await.map(|p| p.version), unauthorized);
+        assert_eq!(intruder.push(&[tags("fiqh", "x", 1)]).await, unauthorized);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::store::{Store, StoreError};
+use bytes::Bytes;
+use hikmah_core::sync::{PullResponse, PushRequest, PushResponse, RegisterRequest, API_PREFIX};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Push berisi metadata saja (tanpa file buku), jadi 16 MB sudah lebih dari cukup
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+type HttpResponse = Response<Full<Bytes>>;
+
+/// Status + pesan error, diubah jadi response JSON di [`handle`]
+type Rejection = (StatusCode, String);
+
+/// =======================
+/// Router
+/// =======================
+///
+/// - `POST /api/v1/register` `{ username, key }`
+/// - `POST /api/v1/push` `{ records }` (auth)
+/// - `GET  /api/v1/pull?since=N` (auth)
+pub async fn handle(req: Request<Incoming>, store: Arc<Store>) -> Result<HttpResponse, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+
+    let result = match path.strip_prefix(API_PREFIX) {
+        Some("/register") if method == Method::POST => register(req, &store).await,
+        Some("/push") if method == Method::POST => push(req, &store).await,
+        Some("/pull") if method == Method::GET => pull(req, &store),
+        Some("/register" | "/push" | "/pull") => Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Method not allowed".to_string(),
+        )),
+        _ => Err((StatusCode::NOT_FOUND, "Not found".to_string())),
+    };
+    let response =
+        result.unwrap_or_else(|(status, message)| respond(status, &json!({ "error": message })));
+
+    info!("{} {} -> {}", method, path, response.status());
+    Ok(response)
+}
+
+/* =========================
+   HANDLERS
+========================= */
+
+async fn register(req: Request<Incoming>, store: &Store) -> Result<HttpResponse, Rejection> {
+    let body: RegisterRequest = read_json(req).await?;
+    store
+        .register(&body.username, &body.key)
+        .map_err(store_error)?;
+
+    info!("Registered user {}", body.username);
+    Ok(respond(
+        StatusCode::CREATED,
+        &json!({ "username": body.username }),
+    ))
+}
+
+async fn push(req: Request<Incoming>, store: &Store) -> Result<HttpResponse, Rejection> {
+    let username = authorize(&req, store)?;
+    let body: PushRequest = read_json(req).await?;
+
+    let count = body.records.len();
+    let version = store.push(&username, body.records).map_err(store_error)?;
+
+    info!(
+        "{} pushed {} records (version {})",
+        username, count, version
+    );
+    Ok(respond(StatusCode::OK, &PushResponse { version }))
+}
+
+fn pull(req: Request<Incoming>, store: &Store) -> Result<HttpResponse, Rejection> {
+    let username = authorize(&req, store)?;
+
+    let since = req
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("since="))
+        .map(|v| v.parse::<u64>())
+        .unwrap_or(Ok(0))
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                "Invalid 'since' parameter".to_string(),
+            )
+        })?;
+
+    let (version, records) = store.pull(&username, since).map_err(store_error)?;
+    Ok(respond(StatusCode::OK, &PullResponse { version, records }))
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+/// Header `x-auth-user` / `x-auth-key`, sama seperti client di hikmah_core
+fn authorize(req: &Request<Incoming>, store: &Store) -> Result<String, Rejection> {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    };
+
+    let (Some(username), Some(key)) = (header("x-auth-user"), header("x-auth-key")) else {
+        return Err((StatusCode::UNAUTHORIZED, "Missing credentials".to_string()));
+    };
+
+    store.authorize(&username, &key).map_err(store_error)?;
+    Ok(username)
+}
+
+async fn read_json<T: for<'de> serde::Deserialize<'de>>(
+    req: Request<Incoming>,
+) -> Result<T, Rejection> {
+    let bytes = Limited::new(req.into_body(), MAX_BODY)
+        .collect()
+        .await
+        .map_err(|e| (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()))?
+        .to_bytes();
+
+    serde_json::from_slice(&bytes).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+fn store_error(e: StoreError) -> Rejection {
+    let status = match e {
+        StoreError::UserExists => StatusCode::CONFLICT,
+        StoreError::InvalidUsername | StoreError::InvalidRecord(_) => StatusCode::BAD_REQUEST,
+        StoreError::Unauthorized => StatusCode::UNAUTHORIZED,
+        StoreError::Io(_) => {
+            warn!("{}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, e.to_string())
+}
+
+fn respond<T: Serialize>(status: StatusCode, body: &T) -> HttpResponse {
+    let json = serde_json::to_vec(body).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Full::new(Bytes::from(json)))
+        .unwrap_or_else(|_| Response::new(Full::new(Bytes::new())))
+}
@@ -0,0 +1,379 @@
+use hikmah_core::sync::{RecordKind, SyncRecord};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use subtle::ConstantTimeEq;
+
+const USERS_DIR: &str = "users";
+const ACCOUNT_FILE: &str = "account.json";
+const RECORDS_FILE: &str = "records.json";
+
+/// =======================
+/// Data Model
+/// =======================
+///
+/// Satu folder per user:
+/// - `<data-dir>/users/<username>/account.json`
+/// - `<data-dir>/users/<username>/records.json`
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Account {
+    username: String,
+    /// HMAC-SHA256 of the client key under `key_salt`, so a leaked data dir
+    /// doesn't leak logins and equal keys don't give equal hashes
+    key_hash: String,
+    /// Random per account
+    key_salt: String,
+    created_date: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct UserRecords {
+    /// Version of the newest record
+    version: u64,
+    /// "kind/id" -> newest record (deletions are kept as tombstones)
+    records: BTreeMap<String, SyncRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreError {
+    UserExists,
+    InvalidUsername,
+    Unauthorized,
+    InvalidRecord(String),
+    Io(String),
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::UserExists => write!(f, "Username is already registered"),
+            StoreError::InvalidUsername => write!(f, "Invalid username"),
+            StoreError::Unauthorized => write!(f, "Unauthorized"),
+            StoreError::InvalidRecord(e) => write!(f, "Invalid record: {e}"),
+            StoreError::Io(e) => write!(f, "Storage error: {e}"),
+        }
+    }
+}
+
+/// =======================
+/// Store
+/// =======================
+pub struct Store {
+    root: PathBuf,
+    /// Semua akses file lewat satu lock; cukup untuk server pribadi/keluarga
+    lock: Mutex<()>,
+}
+
+impl Store {
+    pub fn new(data_dir: &Path) -> Result<Self, StoreError> {
+        let root = data_dir.join(USERS_DIR);
+        fs::create_dir_all(&root).map_err(io_error)?;
+        Ok(Self {
+            root,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn register(&self, username: &str, key: &str) -> Result<(), StoreError> {
+        let dir = self.user_dir(username)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if dir.join(ACCOUNT_FILE).exists() {
+            return Err(StoreError::UserExists);
+        }
+
+        fs::create_dir_all(&dir).map_err(io_error)?;
+        let key_salt = new_salt()?;
+        let account = Account {
+            username: username.to_string(),
+            key_hash: hash_key(&key_salt, key),
+            key_salt,
+            created_date: chrono::Utc::now().to_rfc3339(),
+        };
+        write_json(&dir.join(ACCOUNT_FILE), &account)?;
+        write_json(&dir.join(RECORDS_FILE), &UserRecords::default())
+    }
+
+    pub fn authorize(&self, username: &str, key: &str) -> Result<(), StoreError> {
+        let dir = self
+            .user_dir(username)
+            .map_err(|_| StoreError::Unauthorized)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let account: Account =
+            read_json(&dir.join(ACCOUNT_FILE)).map_err(|_| StoreError::Unauthorized)?;
+        let expected = hash_key(&account.key_salt, key);
+        if !bool::from(account.key_hash.as_bytes().ct_eq(expected.as_bytes())) {
+            return Err(StoreError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// CRDT records are merged with what is stored; other records are
+    /// last-write-wins by the client's `modified` timestamp (see
+    /// [`SyncRecord::merge`]). Every record that changes gets the next
+    /// version number.
+    pub fn push(&self, username: &str, records: Vec<SyncRecord>) -> Result<u64, StoreError> {
+        let path = self.user_dir(username)?.join(RECORDS_FILE);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut stored: UserRecords = read_json(&path)?;
+        for record in records {
+            let key = record_key(record.kind, &record.id);
+            let mut record = match stored.records.get(&key) {
+                Some(existing) => {
+                    let merged = existing.merge(&record).map_err(StoreError::InvalidRecord)?;
+                    if merged.data == existing.data && merged.modified == existing.modified {
+                        continue;
+                    }
+                    merged
+                }
+                None => record,
+            };
+
+            stored.version += 1;
+            record.version = stored.version;
+            stored.records.insert(key, record);
+        }
+
+        write_json(&path, &stored)?;
+        Ok(stored.version)
+    }
+
+    /// Records newer than `since`, oldest first
+    pub fn pull(&self, username: &str, since: u64) -> Result<(u64, Vec<SyncRecord>), StoreError> {
+        let path = self.user_dir(username)?.join(RECORDS_FILE);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let stored: UserRecords = read_json(&path)?;
+        let mut records: Vec<SyncRecord> = stored
+            .records
+            .into_values()
+            .filter(|r| r.version > since)
+            .collect();
+        records.sort_by_key(|r| r.version);
+        Ok((stored.version, records))
+    }
+
+    /// Username jadi nama folder, jadi hanya karakter aman yang boleh
+    fn user_dir(&self, username: &str) -> Result<PathBuf, StoreError> {
+        let valid = !username.is_empty()
+            && username.len() <= 64
+            && !username.starts_with('.')
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid {
+            return Err(StoreError::InvalidUsername);
+        }
+        Ok(self.root.join(username))
+    }
+}
+
+/* =========================
+   HELPERS
+========================= */
+
+fn record_key(kind: RecordKind, id: &str) -> String {
+    format!("{:?}/{}", kind, id)
+}
+
+fn hash_key(salt: &str, key: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC takes any key");
+    mac.update(key.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+fn new_salt() -> Result<String, StoreError> {
+    let mut salt = [0u8; 16];
+    getrandom::getrandom(&mut salt).map_err(io_error)?;
+    Ok(salt.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn io_error(e: impl std::fmt::Display) -> StoreError {
+    StoreError::Io(e.to_string())
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, StoreError> {
+    let text = fs::read_to_string(path).map_err(io_error)?;
+    serde_json::from_str(&text).map_err(io_error)
+}
+
+/// Tulis ke file sementara lalu rename, sama seperti library.json di client
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), StoreError> {
+    let json = serde_json::to_string(value).map_err(io_error)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(io_error)?;
+    fs::rename(&tmp, path).map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hikmah_core::crdt::OrSet;
+    use serde_json::json;
+
+    fn store() -> (Store, PathBuf) {
+        let dir = std::env::temp_dir().join(format!(
+            "hikmah-sync-store-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let store = Store::new(&dir).unwrap();
+        store.register("ahmad", "key").unwrap();
+        (store, dir)
+    }
+
+    fn bookmark(id: &str, label: &str, modified: i64) -> SyncRecord {
+        SyncRecord {
+            kind: RecordKind::Bookmark,
+            id: id.to_string(),
+            data: Some(json!({ "label": label })),
+            modified,
+            version: 0,
+            encrypted: false,
+        }
+    }
+
+    fn tags(tag: &str, device: &str, modified: i64) -> SyncRecord {
+        let mut set = OrSet::default();
+        set.insert(tag.to_string(), device);
+        SyncRecord {
+            kind: RecordKind::Tags,
+            id: "book".to_string(),
+            data: Some(serde_json::to_value(&set).unwrap()),
+            modified,
+            version: 0,
+            encrypted: false,
+        }
+    }
+
+    #[test]
+    fn keys_are_salted_and_checked() {
+        let (store, dir) = store();
+        store.register("fatimah", "key").unwrap();
+        assert_eq!(
+            store.register("ahmad", "other"),
+            Err(StoreError::UserExists)
+        );
+
+        let account = |name: &str| -> Account {
+            read_json(&dir.join(USERS_DIR).join(name).join(ACCOUNT_FILE)).unwrap()
+        };
+        let (ahmad, fatimah) = (account("ahmad"), account("fatimah"));
+        // Key yang sama, hash berbeda
+        assert_ne!(ahmad.key_salt, fatimah.key_salt);
+        assert_ne!(ahmad.key_hash, fatimah.key_hash);
+
+        assert_eq!(store.authorize("ahmad", "key"), Ok(()));
+        assert_eq!(
+            store.authorize("ahmad", "wrong"),
+            Err(StoreError::Unauthorized)
+        );
+        assert_eq!(
+            store.authorize("nobody", "key"),
+            Err(StoreError::Unauthorized)
+        );
+        assert_eq!(
+            store.authorize("../ahmad", "key"),
+            Err(StoreError::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn every_change_gets_the_next_version() {
+        let (store, _dir) = store();
+        assert_eq!(store.pull("ahmad", 0), Ok((0, vec![])));
+
+        let version = store
+            .push(
+                "ahmad",
+                vec![bookmark("a", "one", 1_000), bookmark("b", "two", 1_000)],
+            )
+            .unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(
+            store.push("ahmad", vec![bookmark("a", "three", 2_000)]),
+            Ok(3)
+        );
+
+        let (version, records) = store.pull("ahmad", 0).unwrap();
+        assert_eq!(version, 3);
+        let pulled: Vec<(&str, u64)> = records.iter().map(|r| (r.id.as_str(), r.version)).collect();
+        assert_eq!(pulled, [("b", 2), ("a", 3)]);
+
+        let (_, newer) = store.pull("ahmad", 2).unwrap();
+        assert_eq!(newer.len(), 1);
+        assert_eq!(newer[0].data, Some(json!({ "label": "three" })));
+    }
+
+    #[test]
+    fn pushing_what_is_stored_does_not_bump_the_version() {
+        let (store, _dir) = store();
+        store
+            .push("ahmad", vec![bookmark("a", "one", 1_000)])
+            .unwrap();
+        assert_eq!(
+            store.push("ahmad", vec![bookmark("a", "one", 1_000)]),
+            Ok(1)
+        );
+        // Lebih lama dari yang tersimpan: kalah, tidak ada versi baru
+        assert_eq!(
+            store.push("ahmad", vec![bookmark("a", "stale", 500)]),
+            Ok(1)
+        );
+        let (_, records) = store.pull("ahmad", 0).unwrap();
+        assert_eq!(records[0].data, Some(json!({ "label": "one" })));
+    }
+
+    #[test]
+    fn conflicting_crdt_pushes_are_merged() {
+        let (store, _dir) = store();
+        store
+            .push("ahmad", vec![tags("tafsir", "laptop", 2_000)])
+            .unwrap();
+        assert_eq!(
+            store.push("ahmad", vec![tags("fiqh", "phone", 1_000)]),
+            Ok(2)
+        );
+
+        let (_, records) = store.pull("ahmad", 0).unwrap();
+        let merged: OrSet<String> =
+            serde_json::from_value(records[0].data.clone().unwrap()).unwrap();
+        let names: Vec<&String> = merged.iter().collect();
+        assert_eq!(names, ["fiqh", "tafsir"]);
+        assert_eq!(records[0].modified, 2_000);
+    }
+
+    #[test]
+    fn tombstones_are_kept_for_later_pulls() {
+        let (store, _dir) = store();
+        store
+            .push("ahmad", vec![bookmark("a", "one", 1_000)])
+            .unwrap();
+        let deleted = SyncRecord {
+            data: None,
+            ..bookmark("a", "", 2_000)
+        };
+        assert_eq!(store.push("ahmad", vec![deleted]), Ok(2));
+
+        let (_, records) = store.pull("ahmad", 1).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data, None);
+    }
+
+    #[test]
+    fn users_only_see_their_own_records() {
+        let (store, _dir) = store();
+        store.register("fatimah", "key").unwrap();
+        store
+            .push("ahmad", vec![bookmark("a", "one", 1_000)])
+            .unwrap();
+        assert_eq!(store.pull("fatimah", 0), Ok((0, vec![])));
+    }
+}
@@ -0,0 +1,74 @@
+app-name = بيت الحكمة
+
+## Navbar
+
+nav-home = الرئيسية
+nav-library = المكتبة
+nav-discover = استكشاف
+nav-settings = الإعدادات
+
+## Landing page
+
+hero-title = مكتبتك الرقمية
+hero-highlight = في أي مكان وفي أي وقت
+hero-description = اقرأ كتبك المفضلة على جميع أجهزتك مع قارئ كتب إلكترونية قوي متعدد المنصات. زامن تقدمك وتظليلاتك وملاحظاتك بسلاسة.
+hero-start-reading = ابدأ القراءة
+hero-browse = تصفح المكتبة
+
+features-title = لماذا { app-name }؟
+feature-sync-title = مزامنة عبر المنصات
+feature-sync-description = زامن تقدم قراءتك بسلاسة على جميع أجهزتك
+feature-custom-title = قراءة قابلة للتخصيص
+feature-custom-description = اضبط الخطوط والسمات والتخطيط كما تفضّل
+feature-notes-title = ملاحظات وتظليلات
+feature-notes-description = دوّن الملاحظات وظلّل المقاطع المهمة
+feature-formats-title = دعم صيغ متعددة
+feature-formats-description = يدعم EPUB وPDF وMOBI والمزيد
+
+footer-text = © 2025 { app-name }. صُنع باستخدام Dioxus و Rust 🦀
+
+## Library
+
+library-title = 📚 المكتبة
+library-collection-title = 🗂️ { $name }
+# Bahasa Arab punya bentuk nol, satu, dua, sedikit (3-10) dan banyak (11-99)
+library-book-count =
+    { $count ->
+        [zero] لا كتب
+        [one] كتاب واحد
+        [two] كتابان
+        [few] { $count } كتب
+        [many] { $count } كتابًا
+       *[other] { $count } كتاب
+    }
+library-tab-books = 📚 الكتب
+library-tab-authors = 👤 المؤلفون
+library-export = 📤 تصدير { $count }
+library-add-folder = 📁 إضافة مجلد
+library-add-book = ➕ إضافة كتاب
+library-all-books = كل الكتب
+library-picker-error = خطأ في اختيار الملفات: { $error }
+library-loading = جارٍ التحميل...
+
+sort-title = العنوان
+sort-author = المؤلف
+sort-added = المضافة حديثًا
+sort-last_read = المقروءة حديثًا
+
+book-by = تأليف { $author }
+book-read = 📖 اقرأ
+book-delete = 🗑️ حذف
+book-remove-failed = تعذّر الحذف: { $error }
+
+empty-title = مكتبتك فارغة
+empty-hint = انقر على «إضافة كتاب» أو أفلت الملفات هنا لاستيراد أول كتاب إلكتروني
+empty-formats = يدعم EPUB وPDF وTXT
+
+## Authors
+
+authors-empty-title = لا يوجد مؤلفون بعد
+authors-empty-hint = تُقرأ أسماء المؤلفين من بيانات EPUB وPDF الوصفية لكتبك
+authors-role-editor = محرر
+authors-role-translator = مترجم
+authors-role-commentator = شارح
+authors-role-other = مساهم
@@ -0,0 +1,72 @@
+# Pesan antarmuka (Fluent). Setiap id harus ada di en.ftl; bahasa lain
+# memakai teks bahasa Inggris untuk id yang belum diterjemahkan.
+
+app-name = Bayt Alhikmah
+
+## Navbar
+
+nav-home = Home
+nav-library = Library
+nav-discover = Discover
+nav-settings = Settings
+
+## Landing page
+
+hero-title = Your Digital Library
+hero-highlight = Anywhere, Anytime
+hero-description = Read your favorite books across all your devices with our powerful multi-platform ebook reader. Sync your progress, highlights, and notes seamlessly.
+hero-start-reading = Start Reading
+hero-browse = Browse Library
+
+features-title = Why Choose { app-name }?
+feature-sync-title = Cross-Platform Sync
+feature-sync-description = Seamlessly sync your reading progress across all devices
+feature-custom-title = Customizable Reading
+feature-custom-description = Adjust fonts, themes, and layout to your preference
+feature-notes-title = Notes & Highlights
+feature-notes-description = Take notes and highlight important passages
+feature-formats-title = Large Format Support
+feature-formats-description = Support for EPUB, PDF, MOBI, and more
+
+footer-text = © 2025 { app-name }. Built with Dioxus & Rust 🦀
+
+## Library
+
+library-title = 📚 Library
+library-collection-title = 🗂️ { $name }
+library-book-count =
+    { $count ->
+        [one] { $count } book
+       *[other] { $count } books
+    }
+library-tab-books = 📚 Books
+library-tab-authors = 👤 Authors
+library-export = 📤 Export { $count }
+library-add-folder = 📁 Add Folder
+library-add-book = ➕ Add Book
+library-all-books = All books
+library-picker-error = File picker error: { $error }
+library-loading = Loading...
+
+sort-title = Title
+sort-author = Author
+sort-added = Recently added
+sort-last_read = Recently read
+
+book-by = by { $author }
+book-read = 📖 Read
+book-delete = 🗑️ Delete
+book-remove-failed = Failed to remove: { $error }
+
+empty-title = Your library is empty
+empty-hint = Click 'Add Book' or drop files here to import your first ebook
+empty-formats = Supports EPUB, PDF, and TXT
+
+## Authors
+
+authors-empty-title = No authors yet
+authors-empty-hint = Authors are read from your books' EPUB and PDF metadata
+authors-role-editor = Editor
+authors-role-translator = Translator
+authors-role-commentator = Commentator
+authors-role-other = Contributor
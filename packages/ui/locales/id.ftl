@@ -0,0 +1,66 @@
+app-name = Bayt Alhikmah
+
+## Navbar
+
+nav-home = Beranda
+nav-library = Pustaka
+nav-discover = Jelajahi
+nav-settings = Pengaturan
+
+## Landing page
+
+hero-title = Perpustakaan Digital Anda
+hero-highlight = Di Mana Saja, Kapan Saja
+hero-description = Baca buku favorit Anda di semua perangkat dengan pembaca ebook multi-platform yang andal. Progres, sorotan, dan catatan Anda tersinkron dengan mulus.
+hero-start-reading = Mulai Membaca
+hero-browse = Jelajahi Pustaka
+
+features-title = Mengapa { app-name }?
+feature-sync-title = Sinkron Lintas Platform
+feature-sync-description = Progres membaca tersinkron dengan mulus di semua perangkat
+feature-custom-title = Tampilan Baca Sesuai Selera
+feature-custom-description = Atur font, tema, dan tata letak sesuai keinginan Anda
+feature-notes-title = Catatan & Sorotan
+feature-notes-description = Buat catatan dan tandai bagian penting
+feature-formats-title = Dukungan Banyak Format
+feature-formats-description = Mendukung EPUB, PDF, MOBI, dan lainnya
+
+footer-text = © 2025 { app-name }. Dibuat dengan Dioxus & Rust 🦀
+
+## Library
+
+library-title = 📚 Pustaka
+library-collection-title = 🗂️ { $name }
+# Bahasa Indonesia tidak membedakan tunggal dan jamak
+library-book-count = { $count } buku
+library-tab-books = 📚 Buku
+library-tab-authors = 👤 Penulis
+library-export = 📤 Ekspor { $count }
+library-add-folder = 📁 Tambah Folder
+library-add-book = ➕ Tambah Buku
+library-all-books = Semua buku
+library-picker-error = Gagal memilih file: { $error }
+library-loading = Memuat...
+
+sort-title = Judul
+sort-author = Penulis
+sort-added = Baru ditambahkan
+sort-last_read = Baru dibaca
+
+book-by = oleh { $author }
+book-read = 📖 Baca
+book-delete = 🗑️ Hapus
+book-remove-failed = Gagal menghapus: { $error }
+
+empty-title = Pustaka Anda masih kosong
+empty-hint = Klik 'Tambah Buku' atau seret file ke sini untuk mengimpor ebook pertama Anda
+empty-formats = Mendukung EPUB, PDF, dan TXT
+
+## Authors
+
+authors-empty-title = Belum ada penulis
+authors-empty-hint = Penulis dibaca dari metadata EPUB dan PDF buku Anda
+authors-role-editor = Editor
+authors-role-translator = Penerjemah
+authors-role-commentator = Pensyarah
+authors-role-other = Kontributor
@@ -0,0 +1,81 @@
+use crate::i18n::{use_i18n, I18n};
+use dioxus::prelude::*;
+use hikmah_core::library::Library;
+use hikmah_core::models::{AuthorEntry, AuthorRole};
+
+/* =========================
+   AUTHORS INDEX
+========================= */
+
+#[component]
+pub fn AuthorsView(library: Signal<Library>) -> Element {
+    let authors = library.read().authors_index();
+    let i18n = use_i18n();
+
+    rsx! {
+        div { class: "p-6",
+            if authors.is_empty() {
+                div { class: "hero py-24",
+                    div { class: "hero-content text-center",
+                        div {
+                            h1 { class: "text-6xl", "👤" }
+                            h2 { class: "text-2xl font-bold mt-4", {i18n.t("authors-empty-title")} }
+                            p { class: "opacity-70 mt-2", {i18n.t("authors-empty-hint")} }
+                        }
+                    }
+                }
+            }
+
+            ul { class: "flex flex-col gap-4",
+                for entry in authors {
+                    AuthorRow { key: "{entry.sort_name}", entry }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn AuthorRow(entry: AuthorEntry) -> Element {
+    let i18n = use_i18n();
+    let book_count = entry.books.len();
+
+    rsx! {
+        li { class: "card bg-base-100 shadow",
+            div { class: "card-body",
+                div { class: "flex items-center justify-between",
+                    h2 { class: "card-title", "{entry.name}" }
+                    span { class: "badge badge-neutral",
+                        {i18n.t_with("library-book-count", &[("count", book_count.into())])}
+                    }
+                }
+                p { class: "text-sm opacity-50", "{entry.sort_name}" }
+
+                ul { class: "mt-2",
+                    for book in entry.books.iter() {
+                        li {
+                            key: "{book.book_id}",
+                            class: "flex items-center gap-2",
+                            span { "📖 {book.title}" }
+                            if let Some(role) = role_label(&i18n, book.role) {
+                                span { class: "badge badge-outline badge-sm", "{role}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Author is the default, so it gets no badge
+fn role_label(i18n: &I18n, role: AuthorRole) -> Option<String> {
+    let id = match role {
+        AuthorRole::Author => return None,
+        AuthorRole::Editor => "authors-role-editor",
+        AuthorRole::Translator => "authors-role-translator",
+        AuthorRole::Commentator => "authors-role-commentator",
+        AuthorRole::Other => "authors-role-other",
+    };
+    Some(i18n.t(id))
+}
@@ -0,0 +1,147 @@
+use dioxus::prelude::*;
+use hikmah_core::backup::{backup_file_name, RestoreMode};
+use hikmah_core::file_picker::{save_file, select_backup_file};
+use hikmah_core::library::Library;
+use hikmah_core::utils::decode_data_url;
+use tracing::info;
+
+/* =========================
+   BACKUP / RESTORE BUTTONS
+========================= */
+
+#[component]
+pub fn BackupControls(library: Signal<Library>, loading: Signal<bool>) -> Element {
+    // Arsip yang sudah dipilih, menunggu user memilih Merge / Replace
+    let mut pending = use_signal(|| None::<Vec<u8>>);
+    // Untuk backup terenkripsi dengan key yang tidak ada di perangkat ini
+    let mut passphrase = use_signal(String::new);
+    let mut report = use_signal(|| None::<String>);
+
+    let backup = move |_| {
+        spawn(async move {
+            loading.set(true);
+
+            let archive = library.read().export_backup();
+            let encrypted = library.read().encryption_enabled();
+            match archive {
+                Ok(bytes) => {
+                    info!("Backup created ({} bytes)", bytes.len());
+                    let mime = if encrypted {
+                        "application/octet-stream"
+                    } else {
+                        "application/zip"
+                    };
+                    if let Err(e) = save_file(&backup_file_name(encrypted), mime, &bytes).await {
+                        library.write().err = Some(format!("Failed to save backup: {e}"));
+                    }
+                }
+                Err(e) => library.write().err = Some(format!("Backup failed: {e}")),
+            }
+
+            loading.set(false);
+        });
+    };
+
+    let pick_restore = move |_| {
+        spawn(async move {
+            match select_backup_file().await {
+                Ok(Some(file)) => match decode_data_url(&file.data) {
+                    Ok(bytes) => {
+                        passphrase.set(String::new());
+                        pending.set(Some(bytes));
+                    }
+                    Err(e) => library.write().err = Some(format!("Invalid backup: {e}")),
+                },
+                Ok(None) => {}
+                Err(e) => library.write().err = Some(format!("File picker error: {e}")),
+            }
+        });
+    };
+
+    let mut restore = move |mode: RestoreMode| {
+        let Some(bytes) = pending.take() else {
+            return;
+        };
+
+        let entered = passphrase.take();
+        let entered = (!entered.is_empty()).then_some(entered.as_str());
+        let result = library.write().restore_backup(&bytes, mode, entered);
+        match result {
+            Ok(summary) => report.set(Some(summary.to_string())),
+            Err(e) => library.write().err = Some(format!("Restore failed: {e}")),
+        }
+    };
+
+    let needs_passphrase = pending
+        .read()
+        .as_ref()
+        .is_some_and(|bytes| library.read().backup_needs_passphrase(bytes));
+
+    rsx! {
+        button {
+            class: "btn btn-ghost",
+            onclick: backup,
+            disabled: loading(),
+            "💾 Backup"
+        }
+        button {
+            class: "btn btn-ghost",
+            onclick: pick_restore,
+            disabled: loading(),
+            "♻️ Restore"
+        }
+
+        if pending.read().is_some() {
+            div { class: "modal modal-open",
+                div { class: "modal-box",
+                    h3 { class: "font-bold text-lg", "Restore backup" }
+                    p { class: "py-4",
+                        "Merge keeps your current books and adds the missing ones. "
+                        "Replace removes your current library first."
+                    }
+                    if needs_passphrase {
+                        label { class: "form-control",
+                            span { class: "label-text", "This backup is encrypted. Passphrase:" }
+                            input {
+                                class: "input input-bordered",
+                                r#type: "password",
+                                value: "{passphrase}",
+                                oninput: move |e| passphrase.set(e.value())
+                            }
+                        }
+                    }
+                    div { class: "modal-action",
+                        button {
+                            class: "btn",
+                            onclick: move |_| pending.set(None),
+                            "Cancel"
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            onclick: move |_| restore(RestoreMode::Merge),
+                            "Merge"
+                        }
+                        button {
+                            class: "btn btn-error",
+                            onclick: move |_| restore(RestoreMode::Replace),
+                            "Replace"
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(summary) = report() {
+            div { class: "fixed bottom-4 left-4 z-50 max-w-md",
+                div { class: "alert alert-info shadow-lg",
+                    span { class: "whitespace-pre-line", "{summary}" }
+                    button {
+                        class: "btn btn-sm btn-ghost",
+                        onclick: move |_| report.set(None),
+                        "✕"
+                    }
+                }
+            }
+        }
+    }
+}
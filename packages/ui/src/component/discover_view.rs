@@ -0,0 +1,440 @@
+use dioxus::prelude::*;
+use hikmah_core::library::Library;
+use hikmah_core::opds::{OpdsCatalog, OpdsClient, OpdsEntry, OpdsFeed};
+use tracing::info;
+
+/* =========================
+   DISCOVER (ROOT)
+========================= */
+
+/// Browse OPDS catalogs (a Calibre content server, a community library)
+/// and import books from them into the library
+#[component]
+pub fn DiscoverView(library: Signal<Library>) -> Element {
+    let catalog = use_signal(|| None::<OpdsCatalog>);
+    // Halaman yang sudah dibuka, untuk tombol Back
+    let history = use_signal(Vec::<OpdsFeed>::new);
+    let feed = use_signal(|| None::<OpdsFeed>);
+    let loading = use_signal(|| false);
+    // (berhasil?, pesan)
+    let status = use_signal(|| None::<(bool, String)>);
+
+    rsx! {
+        main { class: "p-6 max-w-5xl mx-auto flex flex-col gap-6",
+            match catalog() {
+                None => rsx! {
+                    CatalogList { library, catalog, history, feed, loading, status }
+                },
+                Some(active) => rsx! {
+                    FeedBrowser {
+                        library,
+                        catalog: active,
+                        active: catalog,
+                        history,
+                        feed,
+                        loading,
+                        status
+                    }
+                },
+            }
+
+            if let Some((ok, message)) = status() {
+                div { class: if ok { "alert alert-success" } else { "alert alert-error" },
+                    span { class: "whitespace-pre-line", "{message}" }
+                }
+            }
+        }
+    }
+}
+
+/// Load `url` (or the catalog root) into `feed`; the page shown before
+/// goes onto `history`
+fn open_feed(
+    catalog: OpdsCatalog,
+    url: Option<String>,
+    mut history: Signal<Vec<OpdsFeed>>,
+    mut feed: Signal<Option<OpdsFeed>>,
+    mut loading: Signal<bool>,
+    mut status: Signal<Option<(bool, String)>>,
+) {
+    spawn(async move {
+        loading.set(true);
+        let client = OpdsClient::new(catalog);
+        let result = match url {
+            Some(url) => client.fetch(&url).await,
+            None => client.root().await,
+        };
+        match result {
+            Ok(next) => {
+                if let Some(current) = feed.take() {
+                    history.write().push(current);
+                }
+                status.set(None);
+                feed.set(Some(next));
+            }
+            Err(e) => status.set(Some((false, e))),
+        }
+        loading.set(false);
+    });
+}
+
+/* =========================
+   SAVED CATALOGS
+========================= */
+
+#[component]
+fn CatalogList(
+    library: Signal<Library>,
+    catalog: Signal<Option<OpdsCatalog>>,
+    history: Signal<Vec<OpdsFeed>>,
+    feed: Signal<Option<OpdsFeed>>,
+    loading: Signal<bool>,
+    status: Signal<Option<(bool, String)>>,
+) -> Element {
+    let mut title = use_signal(String::new);
+    let mut url = use_signal(String::new);
+    let mut username = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let catalogs = library.read().opds_catalogs();
+
+    let mut open = move |selected: OpdsCatalog| {
+        history.write().clear();
+        feed.set(None);
+        catalog.set(Some(selected.clone()));
+        open_feed(selected, None, history, feed, loading, status);
+    };
+
+    let add = move |_| {
+        let url = url.read().trim().to_string();
+        if url.is_empty() {
+            status.set(Some((false, "Enter the catalog URL first".to_string())));
+            return;
+        }
+        let title = title.read().trim().to_string();
+        let new = OpdsCatalog {
+            title: if title.is_empty() { url.clone() } else { title },
+            url,
+            username: username.read().trim().to_string(),
+            password: password.read().clone(),
+        };
+        let result = library.write().save_opds_catalog(new.clone());
+        match result {
+            Ok(()) => open(new),
+            Err(e) => status.set(Some((false, format!("Failed to save: {e}")))),
+        }
+    };
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "🧭 Catalogs" }
+                if catalogs.is_empty() {
+                    p { class: "text-sm opacity-70",
+                        "Add an OPDS catalog to browse and download books, "
+                        "e.g. a Calibre content server at http://localhost:8080/opds."
+                    }
+                }
+                for saved in catalogs {
+                    div { class: "flex items-center gap-2",
+                        button {
+                            class: "btn btn-ghost flex-1 justify-start",
+                            onclick: {
+                                let saved = saved.clone();
+                                move |_| open(saved.clone())
+                            },
+                            "📚 {saved.title}"
+                        }
+                        button {
+                            class: "btn btn-ghost btn-sm",
+                            title: "Remove",
+                            onclick: {
+                                let url = saved.url.clone();
+                                move |_| {
+                                    let result = library.write().remove_opds_catalog(&url);
+                                    if let Err(e) = result {
+                                        status.set(Some((false, format!("Failed to save: {e}"))));
+                                    }
+                                }
+                            },
+                            "✕"
+                        }
+                    }
+                }
+            }
+        }
+
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "Add catalog" }
+                label { class: "form-control",
+                    span { class: "label-text", "Name" }
+                    input {
+                        class: "input input-bordered",
+                        value: "{title}",
+                        oninput: move |e| title.set(e.value())
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "OPDS URL" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "url",
+                        placeholder: "http://localhost:8080/opds",
+                        value: "{url}",
+                        oninput: move |e| url.set(e.value())
+                    }
+                }
+                div { class: "grid grid-cols-2 gap-4",
+                    label { class: "form-control",
+                        span { class: "label-text", "Username (optional)" }
+                        input {
+                            class: "input input-bordered",
+                            autocomplete: "username",
+                            value: "{username}",
+                            oninput: move |e| username.set(e.value())
+                        }
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text", "Password" }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            autocomplete: "current-password",
+                            value: "{password}",
+                            oninput: move |e| password.set(e.value())
+                        }
+                    }
+                }
+                div { class: "card-actions justify-end",
+                    button {
+                        class: "btn btn-primary",
+                        onclick: add,
+                        disabled: loading(),
+                        "Add & open"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   FEED BROWSER
+========================= */
+
+#[component]
+fn FeedBrowser(
+    library: Signal<Library>,
+    catalog: OpdsCatalog,
+    active: Signal<Option<OpdsCatalog>>,
+    history: Signal<Vec<OpdsFeed>>,
+    feed: Signal<Option<OpdsFeed>>,
+    loading: Signal<bool>,
+    status: Signal<Option<(bool, String)>>,
+) -> Element {
+    let mut query = use_signal(String::new);
+    let current = feed();
+
+    let go = {
+        let catalog = catalog.clone();
+        move |url: String| open_feed(catalog.clone(), Some(url), history, feed, loading, status)
+    };
+
+    let back = move |_| match history.write().pop() {
+        Some(previous) => feed.set(Some(previous)),
+        None => {
+            feed.set(None);
+            active.set(None);
+        }
+    };
+
+    let search = {
+        let catalog = catalog.clone();
+        move |_| {
+            let Some(current) = feed() else {
+                return;
+            };
+            let terms = query.read().trim().to_string();
+            if terms.is_empty() {
+                return;
+            }
+            let catalog = catalog.clone();
+            let mut history = history;
+            let mut feed = feed;
+            let mut loading = loading;
+            let mut status = status;
+            spawn(async move {
+                loading.set(true);
+                match OpdsClient::new(catalog).search(&current, &terms).await {
+                    Ok(results) => {
+                        history.write().push(current);
+                        status.set(None);
+                        feed.set(Some(results));
+                    }
+                    Err(e) => status.set(Some((false, e))),
+                }
+                loading.set(false);
+            });
+        }
+    };
+
+    rsx! {
+        div { class: "flex flex-wrap items-center gap-2",
+            button { class: "btn btn-ghost", onclick: back, "← Back" }
+            h2 { class: "text-xl font-bold flex-1",
+                {current.as_ref().map(|f| f.title.clone()).unwrap_or_else(|| catalog.title.clone())}
+            }
+            if loading() {
+                span { class: "loading loading-spinner" }
+            }
+        }
+
+        if let Some(current) = current {
+            if current.search.is_some() {
+                form {
+                    class: "join w-full",
+                    onsubmit: search,
+                    input {
+                        class: "input input-bordered join-item flex-1",
+                        r#type: "search",
+                        placeholder: "Search this catalog",
+                        value: "{query}",
+                        oninput: move |e| query.set(e.value())
+                    }
+                    button { class: "btn btn-primary join-item", r#type: "submit", "🔍" }
+                }
+            }
+
+            if !current.navigation.is_empty() {
+                ul { class: "menu bg-base-200 rounded-box",
+                    for link in current.navigation.iter().cloned() {
+                        li {
+                            a {
+                                onclick: {
+                                    let go = go.clone();
+                                    let href = link.href.clone();
+                                    move |_| go(href.clone())
+                                },
+                                div {
+                                    div { class: "font-medium", "{link.title}" }
+                                    if let Some(summary) = &link.summary {
+                                        div { class: "text-sm opacity-70", "{summary}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "grid gap-4 grid-cols-1 sm:grid-cols-2",
+                for entry in current.entries.iter().cloned() {
+                    EntryCard {
+                        key: "{entry.id}{entry.title}",
+                        entry,
+                        catalog: catalog.clone(),
+                        library,
+                        status
+                    }
+                }
+            }
+
+            if current.navigation.is_empty() && current.entries.is_empty() {
+                p { class: "opacity-70 text-center py-12", "Nothing here." }
+            }
+
+            div { class: "flex justify-center gap-2",
+                for (label, link) in [
+                    ("« First", current.first.clone()),
+                    ("‹ Previous", current.previous.clone()),
+                    ("Next ›", current.next.clone()),
+                    ("Last »", current.last.clone()),
+                ] {
+                    if let Some(href) = link {
+                        button {
+                            class: "btn btn-sm",
+                            disabled: loading(),
+                            onclick: {
+                                let go = go.clone();
+                                move |_| go(href.clone())
+                            },
+                            "{label}"
+                        }
+                    }
+                }
+            }
+            if let Some(total) = current.total {
+                p { class: "text-center text-sm opacity-70", "{total} results" }
+            }
+        }
+    }
+}
+
+#[component]
+fn EntryCard(
+    entry: OpdsEntry,
+    catalog: OpdsCatalog,
+    library: Signal<Library>,
+    status: Signal<Option<(bool, String)>>,
+) -> Element {
+    let mut downloading = use_signal(|| false);
+    let downloadable = entry.best_download().is_some();
+
+    let download = {
+        let entry = entry.clone();
+        move |_| {
+            let entry = entry.clone();
+            let catalog = catalog.clone();
+            spawn(async move {
+                downloading.set(true);
+                let file = OpdsClient::new(catalog).download(&entry).await;
+                let result = file.and_then(|file| library.write().add_book_from_file(file));
+                match result {
+                    Ok(()) => {
+                        info!("Imported '{}' from OPDS", entry.title);
+                        status.set(Some((
+                            true,
+                            format!("Added '{}' to your library", entry.title),
+                        )));
+                    }
+                    Err(e) => status.set(Some((false, format!("{}: {e}", entry.title)))),
+                }
+                downloading.set(false);
+            });
+        }
+    };
+
+    rsx! {
+        div { class: "card card-side bg-base-200 shadow-sm",
+            if let Some(cover) = entry.thumbnail.clone().or(entry.cover.clone()) {
+                figure { class: "w-24 shrink-0",
+                    img { class: "object-cover h-full", src: "{cover}", alt: "{entry.title}" }
+                }
+            }
+            div { class: "card-body p-4 gap-1",
+                h3 { class: "card-title text-base", "{entry.title}" }
+                if !entry.authors.is_empty() {
+                    p { class: "text-sm opacity-70", {entry.authors.join(", ")} }
+                }
+                if let Some(summary) = &entry.summary {
+                    p { class: "text-sm line-clamp-3", "{summary}" }
+                }
+                div { class: "card-actions justify-end",
+                    if downloadable {
+                        button {
+                            class: "btn btn-primary btn-sm",
+                            onclick: download,
+                            disabled: downloading(),
+                            if downloading() {
+                                span { class: "loading loading-spinner loading-sm" }
+                            }
+                            "⬇️ Add to library"
+                        }
+                    } else {
+                        span { class: "text-sm opacity-60", "No free download" }
+                    }
+                }
+            }
+        }
+    }
+}
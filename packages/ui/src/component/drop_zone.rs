@@ -0,0 +1,254 @@
+use dioxus::html::{FileData, HasFileData};
+use dioxus::prelude::*;
+use hikmah_core::file_picker::{detect_book_format, FileSelection};
+use hikmah_core::import::{check_size, ImportItem, ImportQueue};
+use hikmah_core::library::Library;
+use hikmah_core::models::{BookFormat, ImportOutcome};
+use hikmah_core::utils::encode_data_url;
+use tracing::info;
+
+/* =========================
+   DROP ZONE OVERLAY
+========================= */
+
+/// Full-screen target shown while files are dragged over the library.
+/// Dropped files go through the same import queue as "Add Book"; the
+/// per-file results end up in `Library::import_results`.
+#[component]
+pub fn DropZone(
+    library: Signal<Library>,
+    loading: Signal<bool>,
+    dragging: Signal<bool>,
+) -> Element {
+    let on_drop = move |evt: DragEvent| {
+        evt.prevent_default();
+        dragging.set(false);
+
+        let files = evt.files();
+        if files.is_empty() {
+            return;
+        }
+        info!("Dropped {} items", files.len());
+
+        spawn(async move {
+            loading.set(true);
+            let max_size = library.read().app_preferences().max_import_bytes();
+            let (items, unreadable) = read_dropped_items(files, max_size).await;
+            loading.set(false);
+
+            import_in_background(library, items).await;
+            library.write().import_results.extend(unreadable);
+        });
+    };
+
+    rsx! {
+        div {
+            class: "fixed inset-0 z-50 bg-primary/20 backdrop-blur-sm p-8",
+            ondragover: move |evt| evt.prevent_default(),
+            ondragleave: move |_| dragging.set(false),
+            ondrop: on_drop,
+
+            // pointer-events-none: tanpa ini dragleave ikut terpicu di elemen anak
+            div { class: "pointer-events-none w-full h-full rounded-box border-4 border-dashed border-primary bg-base-100/80 flex flex-col items-center justify-center gap-2",
+                span { class: "text-6xl", "📥" }
+                h2 { class: "text-2xl font-bold", "Drop to add to your library" }
+                p { class: "opacity-70", "EPUB, PDF and TXT files, or folders of them" }
+            }
+        }
+    }
+}
+
+/// Desktop hands over real paths (folders too), which go straight into the
+/// blob store; in the browser every file is read into a data URL first
+async fn read_dropped_items(
+    files: Vec<FileData>,
+    max_size: u64,
+) -> (Vec<ImportItem>, Vec<ImportOutcome>) {
+    let mut items = Vec::new();
+    let mut unreadable = Vec::new();
+
+    for file in files {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = file.path();
+            if path.is_absolute() && path.exists() {
+                items.push(ImportItem::Path(path));
+                continue;
+            }
+        }
+
+        match read_dropped(&file, max_size).await {
+            Ok(selection) => items.push(ImportItem::File(selection)),
+            Err(e) => unreadable.push(ImportOutcome {
+                file_name: file.name(),
+                result: Err(e),
+            }),
+        }
+    }
+
+    (items, unreadable)
+}
+
+async fn read_dropped(file: &FileData, max_size: u64) -> Result<FileSelection, String> {
+    let name = file.name();
+    let content_type = file.content_type().unwrap_or_default();
+
+    let Some(format) =
+        detect_book_format(&content_type, &name).and_then(|f| BookFormat::from_extension(&f))
+    else {
+        // Browser memberi folder sebagai "file" tanpa tipe dan tanpa ekstensi
+        return Err(if content_type.is_empty() && !name.contains('.') {
+            "Folders can only be dropped in the desktop app".to_string()
+        } else {
+            "Unsupported file format".to_string()
+        });
+    };
+
+    // Cek sebelum dibaca, supaya file raksasa tidak dimuat ke memori dulu
+    check_size(file.size(), max_size)?;
+
+    let bytes = file
+        .read_bytes()
+        .await
+        .map_err(|e| format!("Could not read the file: {e}"))?;
+    Ok(FileSelection {
+        name,
+        r#type: format.mime_type().to_string(),
+        size: bytes.len() as u64,
+        data: encode_data_url(format.mime_type(), &bytes),
+    })
+}
+
+/* =========================
+   IMPORT RESULTS
+========================= */
+
+#[component]
+pub fn ImportResults(library: Signal<Library>) -> Element {
+    let results = library.read().import_results.clone();
+    let added = results.iter().filter(|r| r.result.is_ok()).count();
+    let total = results.len();
+    let retryable = library.read().import_retry.len();
+
+    rsx! {
+        div { class: "fixed bottom-4 left-4 z-40 w-96 max-w-[calc(100vw-2rem)]",
+            div { class: "card bg-base-100 shadow-xl border border-base-300",
+                div { class: "card-body p-4 gap-2",
+                    div { class: "flex items-center justify-between",
+                        h3 { class: "font-bold", "📥 Added {added} of {total}" }
+                        div { class: "flex gap-1",
+                            if retryable > 0 {
+                                button {
+                                    class: "btn btn-sm btn-primary",
+                                    onclick: move |_| {
+                                        let started = library.write().retry_failed_imports();
+                                        spawn(run_import(library, started));
+                                    },
+                                    "Retry {retryable}"
+                                }
+                            }
+                            button {
+                                class: "btn btn-sm btn-ghost",
+                                onclick: move |_| library.write().clear_import_results(),
+                                "✕"
+                            }
+                        }
+                    }
+                    ul { class: "max-h-64 overflow-y-auto text-sm flex flex-col gap-1",
+                        for outcome in results {
+                            li { class: "flex gap-2",
+                                match outcome.result {
+                                    Ok(title) => rsx! {
+                                        span { "✅" }
+                                        span { class: "truncate", title: "{outcome.file_name}", "{title}" }
+                                    },
+                                    Err(e) => rsx! {
+                                        span { "⚠️" }
+                                        span { class: "min-w-0",
+                                            span { class: "font-medium break-all", "{outcome.file_name}" }
+                                            span { class: "opacity-70", ": {e}" }
+                                        }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   IMPORT PROGRESS
+========================= */
+
+/// Import files (and folders, on desktop) without blocking the UI;
+/// progress is shown by [`ImportProgressPanel`]
+pub async fn import_in_background(mut library: Signal<Library>, items: Vec<ImportItem>) {
+    let started = library.write().start_import(items);
+    run_import(library, started).await;
+}
+
+async fn run_import(mut library: Signal<Library>, started: Result<ImportQueue, String>) {
+    let mut queue = match started {
+        Ok(queue) => queue,
+        Err(e) => {
+            library.write().err = Some(e);
+            return;
+        }
+    };
+
+    while let Some(events) = queue.next_batch().await {
+        let applied = library.write().apply_import_events(events);
+        if let Err(e) = applied {
+            library.write().err = Some(format!("Failed to save imported books: {e}"));
+        }
+    }
+}
+
+#[component]
+pub fn ImportProgressPanel(library: Signal<Library>) -> Element {
+    let Some(state) = library.read().import_progress.clone() else {
+        return rsx! {};
+    };
+    let errors = state.errors();
+    let cancelled = state.is_cancelled();
+
+    rsx! {
+        div { class: "fixed bottom-4 left-4 z-40 w-96 max-w-[calc(100vw-2rem)]",
+            div { class: "card bg-base-100 shadow-xl border border-base-300",
+                div { class: "card-body p-4 gap-2",
+                    div { class: "flex items-center justify-between",
+                        if state.total == 0 {
+                            h3 { class: "font-bold", "📥 Looking for books..." }
+                        } else {
+                            h3 { class: "font-bold", "📥 Importing {state.done} of {state.total}" }
+                        }
+                        button {
+                            class: "btn btn-sm btn-ghost",
+                            disabled: cancelled,
+                            onclick: move |_| {
+                                if let Some(progress) = &library.read().import_progress {
+                                    progress.cancel();
+                                }
+                            },
+                            if cancelled { "Stopping..." } else { "Cancel" }
+                        }
+                    }
+                    progress {
+                        class: "progress progress-primary w-full",
+                        value: "{state.done}",
+                        max: "{state.total.max(1)}"
+                    }
+                    if let Some(current) = &state.current {
+                        p { class: "text-sm truncate opacity-70", title: "{current}", "{current}" }
+                    }
+                    if errors > 0 {
+                        p { class: "text-sm text-warning", "⚠️ {errors} files skipped" }
+                    }
+                }
+            }
+        }
+    }
+}
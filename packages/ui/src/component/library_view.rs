@@ -1,46 +1,109 @@
 use dioxus::prelude::*;
 use hikmah_core::file_picker::select_ebook_files;
+use hikmah_core::import::ImportItem;
 use hikmah_core::library::Library;
 use hikmah_core::models::BookMetadata;
+use hikmah_core::preferences::SortOrder;
 use hikmah_core::utils::{format_date, format_size};
+use std::collections::HashSet;
 use tracing::info;
 
+use super::authors_view::AuthorsView;
+use super::backup_controls::BackupControls;
+use super::drop_zone::{import_in_background, DropZone};
+use crate::i18n::use_i18n;
+use crate::route::Route;
+
+#[derive(Clone, Copy, PartialEq)]
+enum LibraryTab {
+    Books,
+    Authors,
+}
+
 /* =========================
    LIBRARY VIEW (ROOT)
 ========================= */
 
+/// The books in the library, or in one collection when `collection` is set.
+/// `on_export` is provided by platforms that can write to a folder (desktop);
+/// when set, books can be selected and exported in bulk. `on_import` replaces
+/// the HTML file picker behind "Add Book" with a native one (desktop), and
+/// `on_import_folder` adds an "Add Folder" button for importing a whole tree.
 #[component]
-pub fn LibraryView(library: Signal<Library>) -> Element {
+pub fn LibraryView(
+    library: Signal<Library>,
+    collection: Option<String>,
+    on_export: Option<EventHandler<Vec<String>>>,
+    on_import: Option<EventHandler<()>>,
+    on_import_folder: Option<EventHandler<()>>,
+) -> Element {
     let loading = use_signal(|| false);
+    let tab = use_signal(|| LibraryTab::Books);
+    let selected = use_signal(HashSet::<String>::new);
+    let mut dragging = use_signal(|| false);
+    let sort = use_signal(|| library.read().app_preferences().default_sort);
     let lib = library.read();
 
+    let members: Option<Vec<String>> = collection
+        .as_ref()
+        .map(|name| lib.collections().remove(name).unwrap_or_default());
+    let mut books: Vec<&BookMetadata> = (lib.books.iter())
+        .filter(|b| members.as_ref().is_none_or(|m| m.contains(&b.id)))
+        .collect();
+    lib.sort_books(&mut books, sort());
+    let book_count = books.len();
+
     rsx! {
-        div { class: "min-h-screen bg-base-100",
-            Header { library, loading }
+        div {
+            class: "min-h-screen bg-base-100",
+            ondragenter: move |evt| {
+                evt.prevent_default();
+                dragging.set(true);
+            },
+
+            if dragging() {
+                DropZone { library, loading, dragging }
+            }
+
+            Header {
+                library,
+                loading,
+                tab,
+                selected,
+                collection: collection.clone(),
+                book_count,
+                on_export,
+                on_import,
+                on_import_folder
+            }
 
             if loading() {
                 LoadingOverlay {}
             }
 
-            main {
-                class: "p-6 grid gap-6 grid-cols-1 sm:grid-cols-2 lg:grid-cols-3",
-
-                for book in lib.books.iter() {
-                    BookCard {
-                        book: book.clone(),
-                        library
-                    }
+            if tab() == LibraryTab::Authors {
+                AuthorsView { library }
+            } else {
+                CollectionLinks { library, current: collection.clone() }
+                if books.len() > 1 {
+                    SortSelect { sort }
                 }
 
-                if lib.books.is_empty() && !loading() {
-                    EmptyLibrary {}
-                }
-            }
+                main {
+                    class: "p-6 grid gap-6 grid-cols-1 sm:grid-cols-2 lg:grid-cols-3",
 
-            if let Some(err) = &lib.err {
-                ErrorBanner {
-                    error: err.clone(),
-                    library
+                    for book in books {
+                        BookCard {
+                            book: book.clone(),
+                            library,
+                            selected,
+                            selectable: on_export.is_some()
+                        }
+                    }
+
+                    if lib.books.is_empty() && !loading() {
+                        EmptyLibrary {}
+                    }
                 }
             }
         }
@@ -52,56 +115,180 @@ pub fn LibraryView(library: Signal<Library>) -> Element {
 ========================= */
 
 #[component]
-fn Header(library: Signal<Library>, loading: Signal<bool>) -> Element {
-    let add_books = move |_| {
-        spawn({
-            let mut library = library;
-            let mut loading = loading;
-
-            async move {
-                loading.set(true);
-
-                match select_ebook_files().await {
-                    Ok(files) => {
-                        if files.is_empty() {
-                            loading.set(false);
-                            return;
-                        }
-
-                        info!("Selected {} files", files.len());
+fn Header(
+    library: Signal<Library>,
+    loading: Signal<bool>,
+    tab: Signal<LibraryTab>,
+    selected: Signal<HashSet<String>>,
+    collection: Option<String>,
+    book_count: usize,
+    on_export: Option<EventHandler<Vec<String>>>,
+    on_import: Option<EventHandler<()>>,
+    on_import_folder: Option<EventHandler<()>>,
+) -> Element {
+    let i18n = use_i18n();
+
+    let add_books = {
+        let i18n = i18n.clone();
+        move |_| {
+            if let Some(on_import) = on_import {
+                on_import.call(());
+                return;
+            }
 
-                        let errors = library.write().add_multiple_books(files);
-                        if !errors.is_empty() {
-                            library.write().err = Some(errors.join("\n"));
+            spawn({
+                let mut library = library;
+                let mut loading = loading;
+                let i18n = i18n.clone();
+
+                async move {
+                    loading.set(true);
+                    let picked = select_ebook_files().await;
+                    loading.set(false);
+
+                    match picked {
+                        Ok(files) if files.is_empty() => {}
+                        Ok(files) => {
+                            info!("Selected {} files", files.len());
+                            let items = files.into_iter().map(ImportItem::File).collect();
+                            import_in_background(library, items).await;
+                        }
+                        Err(e) => {
+                            let message = i18n
+                                .t_with("library-picker-error", &[("error", e.to_string().into())]);
+                            library.write().err = Some(message);
                         }
-                    }
-                    Err(e) => {
-                        library.write().err = Some(format!("File picker error: {e}"));
                     }
                 }
+            });
+        }
+    };
 
-                loading.set(false);
-            }
-        });
+    let export_selected = move |_| {
+        if let Some(on_export) = on_export {
+            on_export.call(selected.read().iter().cloned().collect());
+            selected.write().clear();
+        }
     };
 
-    let book_count = library.read().books.len();
+    let selected_count = selected.read().len();
+    let importing = library.read().import_progress.is_some();
 
     rsx! {
         div { class: "navbar bg-base-100 shadow",
             div { class: "flex-1",
-                a { class: "btn btn-ghost text-xl", "📚 Bayt Alhikmah" }
+                if let Some(name) = &collection {
+                    h1 { class: "text-xl font-bold px-4",
+                        {i18n.t_with("library-collection-title", &[("name", name.clone().into())])}
+                    }
+                } else {
+                    h1 { class: "text-xl font-bold px-4", {i18n.t("library-title")} }
+                }
                 if book_count > 0 {
-                    span { class: "ml-2 badge badge-neutral", "{book_count} books" }
+                    span { class: "ms-2 badge badge-neutral",
+                        {i18n.t_with("library-book-count", &[("count", book_count.into())])}
+                    }
                 }
             }
 
-            div { class: "flex-none",
+            div { class: "flex-none gap-2",
+                div { class: "join",
+                    button {
+                        class: if tab() == LibraryTab::Books { "btn btn-sm join-item btn-active" } else { "btn btn-sm join-item" },
+                        onclick: move |_| tab.set(LibraryTab::Books),
+                        {i18n.t("library-tab-books")}
+                    }
+                    button {
+                        class: if tab() == LibraryTab::Authors { "btn btn-sm join-item btn-active" } else { "btn btn-sm join-item" },
+                        onclick: move |_| tab.set(LibraryTab::Authors),
+                        {i18n.t("library-tab-authors")}
+                    }
+                }
+                if on_export.is_some() && selected_count > 0 {
+                    button {
+                        class: "btn btn-secondary",
+                        onclick: export_selected,
+                        disabled: loading(),
+                        {i18n.t_with("library-export", &[("count", selected_count.into())])}
+                    }
+                }
+                BackupControls { library, loading }
+                if let Some(on_import_folder) = on_import_folder {
+                    button {
+                        class: "btn",
+                        onclick: move |_| on_import_folder.call(()),
+                        disabled: loading() || importing,
+                        {i18n.t("library-add-folder")}
+                    }
+                }
                 button {
                     class: "btn btn-primary",
                     onclick: add_books,
-                    disabled: loading(),
-                    "➕ Add Book"
+                    disabled: loading() || importing,
+                    {i18n.t("library-add-book")}
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   COLLECTION LINKS
+========================= */
+
+/// Jump between collections; hidden while there are none
+#[component]
+fn CollectionLinks(library: Signal<Library>, current: Option<String>) -> Element {
+    let i18n = use_i18n();
+    let names: Vec<String> = library.read().collections().into_keys().collect();
+    if names.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        div { class: "px-6 pt-4 flex flex-wrap gap-2",
+            Link {
+                to: Route::Library {},
+                class: if current.is_none() { "badge badge-primary" } else { "badge badge-outline" },
+                {i18n.t("library-all-books")}
+            }
+            for name in names {
+                Link {
+                    key: "{name}",
+                    to: Route::Collection { name: name.clone() },
+                    class: if current.as_ref() == Some(&name) { "badge badge-primary" } else { "badge badge-outline" },
+                    {i18n.t_with("library-collection-title", &[("name", name.clone().into())])}
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   SORT
+========================= */
+
+/// Starts at the default from Settings; changing it here is not saved
+#[component]
+fn SortSelect(sort: Signal<SortOrder>) -> Element {
+    let i18n = use_i18n();
+
+    rsx! {
+        div { class: "px-6 pt-4 flex justify-end",
+            select {
+                class: "select select-bordered select-sm",
+                onchange: move |e| {
+                    if let Some(order) = SortOrder::from_name(&e.value()) {
+                        sort.set(order);
+                    }
+                },
+                for order in SortOrder::ALL {
+                    option {
+                        key: "{order.as_str()}",
+                        value: "{order.as_str()}",
+                        selected: sort() == order,
+                        {i18n.t(&format!("sort-{}", order.as_str()))}
+                    }
                 }
             }
         }
@@ -113,13 +300,33 @@ fn Header(library: Signal<Library>, loading: Signal<bool>) -> Element {
 ========================= */
 
 #[component]
-fn BookCard(book: BookMetadata, library: Signal<Library>) -> Element {
+fn BookCard(
+    book: BookMetadata,
+    library: Signal<Library>,
+    selected: Signal<HashSet<String>>,
+    selectable: bool,
+) -> Element {
+    let i18n = use_i18n();
     let book_id = book.id.clone();
+    let is_selected = selected.read().contains(&book.id);
+
+    let toggle_selected = {
+        let book_id = book.id.clone();
+        move |_| {
+            let mut selected = selected.write();
+            if !selected.remove(&book_id) {
+                selected.insert(book_id.clone());
+            }
+        }
+    };
 
-    let delete_book = move |_| {
-        let mut lib = library.write();
-        if let Err(e) = lib.remove_book(&book_id) {
-            lib.err = Some(format!("Failed to remove: {e}"));
+    let delete_book = {
+        let i18n = i18n.clone();
+        move |_| {
+            let mut lib = library.write();
+            if let Err(e) = lib.remove_book(&book_id) {
+                lib.err = Some(i18n.t_with("book-remove-failed", &[("error", e.into())]));
+            }
         }
     };
 
@@ -128,13 +335,32 @@ fn BookCard(book: BookMetadata, library: Signal<Library>) -> Element {
             key: "{book.id}",
             class: "card bg-base-100 shadow hover:shadow-lg transition",
 
+            figure { class: "bg-base-200 pt-4",
+                img {
+                    class: "h-48 w-32 object-cover rounded shadow",
+                    src: "{book.cover_src()}",
+                    alt: "{book.title}",
+                    loading: "lazy"
+                }
+            }
+
             div { class: "card-body",
                 div { class: "flex items-center justify-between",
+                    if selectable {
+                        input {
+                            r#type: "checkbox",
+                            class: "checkbox checkbox-sm me-2",
+                            checked: is_selected,
+                            onchange: toggle_selected
+                        }
+                    }
                     h2 { class: "card-title truncate", "{book.title}" }
                     span { class: "badge badge-outline", "{book.format}" }
                 }
 
-                p { class: "text-sm opacity-70", "by {book.author}" }
+                p { class: "text-sm opacity-70",
+                    {i18n.t_with("book-by", &[("author", book.author_display().into())])}
+                }
                 p { "📊 {format_size(book.size)}" }
                 p { "📅 {format_date(&book.added_date)}" }
 
@@ -147,11 +373,15 @@ fn BookCard(book: BookMetadata, library: Signal<Library>) -> Element {
                 }
 
                 div { class: "card-actions justify-end mt-4",
-                    button { class: "btn btn-sm btn-primary", "📖 Read" }
+                    Link {
+                        to: Route::Reader { book_id: book.id.clone() },
+                        class: "btn btn-sm btn-primary",
+                        {i18n.t("book-read")}
+                    }
                     button {
                         class: "btn btn-sm btn-error",
                         onclick: delete_book,
-                        "🗑️ Delete"
+                        {i18n.t("book-delete")}
                     }
                 }
             }
@@ -165,13 +395,15 @@ fn BookCard(book: BookMetadata, library: Signal<Library>) -> Element {
 
 #[component]
 fn LoadingOverlay() -> Element {
+    let i18n = use_i18n();
+
     rsx! {
         div {
             class: "fixed inset-0 bg-black/40 flex items-center justify-center z-50",
 
             div { class: "card bg-base-100 p-6 items-center",
                 span { class: "loading loading-spinner loading-lg text-primary" }
-                p { class: "mt-2", "Loading..." }
+                p { class: "mt-2", {i18n.t("library-loading")} }
             }
         }
     }
@@ -183,19 +415,17 @@ fn LoadingOverlay() -> Element {
 
 #[component]
 fn EmptyLibrary() -> Element {
+    let i18n = use_i18n();
+
     rsx! {
         div { class: "col-span-full",
             div { class: "hero py-24",
                 div { class: "hero-content text-center",
                     div {
                         h1 { class: "text-6xl", "📚" }
-                        h2 { class: "text-2xl font-bold mt-4", "Your library is empty" }
-                        p { class: "opacity-70 mt-2",
-                            "Click 'Add Book' to import your first ebook"
-                        }
-                        p { class: "text-sm opacity-50",
-                            "Supports EPUB, PDF, and TXT"
-                        }
+                        h2 { class: "text-2xl font-bold mt-4", {i18n.t("empty-title")} }
+                        p { class: "opacity-70 mt-2", {i18n.t("empty-hint")} }
+                        p { class: "text-sm opacity-50", {i18n.t("empty-formats")} }
                     }
                 }
             }
@@ -208,7 +438,7 @@ fn EmptyLibrary() -> Element {
 ========================= */
 
 #[component]
-fn ErrorBanner(error: String, library: Signal<Library>) -> Element {
+pub fn ErrorBanner(error: String, library: Signal<Library>) -> Element {
     rsx! {
         div { class: "fixed bottom-4 right-4 z-50 max-w-md",
             div { class: "alert alert-error shadow-lg",
@@ -1,2 +1,16 @@
+pub mod authors_view;
+pub mod backup_controls;
+pub mod discover_view;
+pub mod drop_zone;
 pub mod library_view;
+pub mod reader_settings_panel;
+pub mod reader_view;
+pub mod settings_view;
+pub use authors_view::AuthorsView;
+pub use backup_controls::BackupControls;
+pub use discover_view::DiscoverView;
+pub use drop_zone::{DropZone, ImportProgressPanel, ImportResults};
 pub use library_view::LibraryView;
+pub use reader_settings_panel::ReaderSettingsPanel;
+pub use reader_view::ReaderView;
+pub use settings_view::{SettingsView, SharingStatus};
@@ -0,0 +1,178 @@
+use dioxus::prelude::*;
+use hikmah_core::library::Library;
+use hikmah_core::reader_settings::{
+    FontFamily, ReaderSettings, ReaderTheme, TextAlign, FONT_SIZES, LINE_HEIGHTS, MARGINS,
+};
+
+/* =========================
+   READER SETTINGS PANEL
+========================= */
+
+/// Typography and theme for the text reader. Changes apply right away and
+/// go to the global settings, or only to this book while "This book only"
+/// is on; turning that off drops the book's own settings.
+#[component]
+pub fn ReaderSettingsPanel(library: Signal<Library>, book_id: String) -> Element {
+    let settings = library.read().reader_settings_for(&book_id);
+    let mut this_book = use_signal(|| !library.read().book_reader_overrides(&book_id).is_empty());
+    let mut error = use_signal(|| None::<String>);
+
+    let apply = use_callback({
+        let book_id = book_id.clone();
+        move |next: ReaderSettings| {
+            let mut lib = library.write();
+            let result = if this_book() {
+                lib.set_book_reader_settings(&book_id, Some(next))
+            } else {
+                lib.set_reader_settings(next)
+            };
+            error.set(result.err().map(|e| format!("Failed to save: {e}")));
+        }
+    });
+
+    let mut toggle_this_book = move |on: bool| {
+        this_book.set(on);
+        if !on {
+            let result = library.write().set_book_reader_settings(&book_id, None);
+            error.set(result.err().map(|e| format!("Failed to save: {e}")));
+        }
+    };
+
+    rsx! {
+        div { class: "card bg-base-200 text-base-content shadow max-w-3xl mx-auto mt-2",
+            div { class: "card-body gap-4 text-sm",
+                div { class: "join",
+                    for theme in ReaderTheme::ALL {
+                        button {
+                            key: "{theme.as_str()}",
+                            class: if settings.theme == theme { "btn btn-sm join-item btn-active" } else { "btn btn-sm join-item" },
+                            onclick: move |_| apply.call(ReaderSettings { theme, ..settings }),
+                            "{theme.label()}"
+                        }
+                    }
+                }
+
+                div { class: "flex flex-wrap items-center gap-4",
+                    select {
+                        class: "select select-bordered select-sm",
+                        onchange: move |e| {
+                            if let Some(font_family) = FontFamily::from_name(&e.value()) {
+                                apply.call(ReaderSettings { font_family, ..settings });
+                            }
+                        },
+                        for font in FontFamily::ALL {
+                            option {
+                                key: "{font.as_str()}",
+                                value: "{font.as_str()}",
+                                selected: settings.font_family == font,
+                                "{font.label()}"
+                            }
+                        }
+                    }
+
+                    div { class: "join",
+                        button {
+                            class: "btn btn-sm join-item",
+                            disabled: settings.font_size <= *FONT_SIZES.start(),
+                            onclick: move |_| apply.call(ReaderSettings { font_size: settings.font_size - 1, ..settings }),
+                            "A−"
+                        }
+                        span { class: "btn btn-sm join-item no-animation", "{settings.font_size}px" }
+                        button {
+                            class: "btn btn-sm join-item",
+                            disabled: settings.font_size >= *FONT_SIZES.end(),
+                            onclick: move |_| apply.call(ReaderSettings { font_size: settings.font_size + 1, ..settings }),
+                            "A+"
+                        }
+                    }
+                }
+
+                label { class: "flex items-center gap-4",
+                    span { class: "w-28", "Line height" }
+                    input {
+                        r#type: "range",
+                        class: "range range-sm flex-1",
+                        min: "{LINE_HEIGHTS.start()}",
+                        max: "{LINE_HEIGHTS.end()}",
+                        step: "0.1",
+                        value: "{settings.line_height}",
+                        oninput: move |e| {
+                            if let Ok(line_height) = e.value().parse() {
+                                apply.call(ReaderSettings { line_height, ..settings });
+                            }
+                        }
+                    }
+                    span { class: "w-10 text-right", "{settings.line_height:.1}" }
+                }
+
+                label { class: "flex items-center gap-4",
+                    span { class: "w-28", "Margins" }
+                    input {
+                        r#type: "range",
+                        class: "range range-sm flex-1",
+                        min: "{MARGINS.start()}",
+                        max: "{MARGINS.end()}",
+                        value: "{settings.margin}",
+                        oninput: move |e| {
+                            if let Ok(margin) = e.value().parse() {
+                                apply.call(ReaderSettings { margin, ..settings });
+                            }
+                        }
+                    }
+                    span { class: "w-10 text-right", "{settings.margin}%" }
+                }
+
+                div { class: "flex flex-wrap items-center gap-4",
+                    select {
+                        class: "select select-bordered select-sm",
+                        onchange: move |e| {
+                            if let Some(align) = TextAlign::from_name(&e.value()) {
+                                apply.call(ReaderSettings { align, ..settings });
+                            }
+                        },
+                        for align in TextAlign::ALL {
+                            option {
+                                key: "{align.as_str()}",
+                                value: "{align.as_str()}",
+                                selected: settings.align == align,
+                                "Align: {align.label()}"
+                            }
+                        }
+                    }
+                    label { class: "label cursor-pointer gap-2",
+                        input {
+                            r#type: "checkbox",
+                            class: "toggle toggle-sm",
+                            checked: settings.justify,
+                            onchange: move |e| apply.call(ReaderSettings { justify: e.checked(), ..settings })
+                        }
+                        span { class: "label-text", "Justify" }
+                    }
+                    label { class: "label cursor-pointer gap-2",
+                        input {
+                            r#type: "checkbox",
+                            class: "toggle toggle-sm",
+                            checked: settings.hyphenate,
+                            onchange: move |e| apply.call(ReaderSettings { hyphenate: e.checked(), ..settings })
+                        }
+                        span { class: "label-text", "Hyphenation" }
+                    }
+                }
+
+                label { class: "label cursor-pointer justify-start gap-2 border-t border-base-300 pt-3",
+                    input {
+                        r#type: "checkbox",
+                        class: "toggle toggle-primary toggle-sm",
+                        checked: this_book(),
+                        onchange: move |e| toggle_this_book(e.checked())
+                    }
+                    span { class: "label-text", "This book only" }
+                }
+
+                if let Some(err) = error() {
+                    p { class: "text-error", "{err}" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use dioxus::prelude::*;
+use hikmah_core::formats::{book_chapters, Chapter};
+use hikmah_core::library::Library;
+use hikmah_core::models::{BookFormat, BookMetadata};
+use hikmah_core::reader_settings::{FontFamily, ReaderSettings, ReaderTheme};
+use hikmah_core::utils::encode_data_url;
+use std::rc::Rc;
+
+use super::reader_settings_panel::ReaderSettingsPanel;
+
+/// What the reader shows: text chapters, or a PDF left to the webview's
+/// own viewer as a data URL
+enum Content {
+    Chapters(Vec<Chapter>),
+    Pdf(String),
+}
+
+fn load_content(book: &BookMetadata) -> Result<Content, String> {
+    if book.format == BookFormat::PDF && !book.file_data.is_empty() {
+        return Ok(Content::Pdf(book.file_data.clone()));
+    }
+    let bytes = book.read_file()?;
+    match book.format {
+        BookFormat::PDF => Ok(Content::Pdf(encode_data_url(
+            book.format.mime_type(),
+            &bytes,
+        ))),
+        _ => book_chapters(&book.format, &bytes).map(Content::Chapters),
+    }
+}
+
+/* =========================
+   READER VIEW
+========================= */
+
+/// One chapter at a time for EPUB and TXT. The reading position is the
+/// chapter number (1-based), and the chapter count becomes the book's
+/// `total_pages`, so progress and kosync percentages line up.
+#[component]
+pub fn ReaderView(
+    library: Signal<Library>,
+    book_id: String,
+    on_close: EventHandler<()>,
+) -> Element {
+    let book = library.read().get_book(&book_id).cloned();
+
+    // Dibaca sekali saat reader dibuka; parent memberi `key` per buku
+    let content = use_hook(|| {
+        Rc::new(match &book {
+            Some(book) => load_content(book),
+            None => Err("Book not found".to_string()),
+        })
+    });
+    let mut chapter =
+        use_signal(|| (book.as_ref()).map_or(0, |b| b.last_read_position.saturating_sub(1)));
+    let mut show_settings = use_signal(|| false);
+
+    // Posisi disimpan setiap pindah bab, dan juga begitu buku dibuka supaya
+    // buku baru langsung terhitung "dimulai"
+    use_effect({
+        let content = content.clone();
+        let book_id = book_id.clone();
+        move || {
+            let index = chapter();
+            let Ok(Content::Chapters(chapters)) = content.as_ref() else {
+                return;
+            };
+            if chapters.is_empty() {
+                return;
+            }
+            let count = chapters.len();
+            let mut lib = library.write();
+            let saved = (lib.set_total_pages(&book_id, count))
+                .and_then(|_| lib.update_reading_position(&book_id, index.min(count - 1) + 1));
+            if let Err(e) = saved {
+                lib.err = Some(format!("Failed to save the reading position: {e}"));
+            }
+        }
+    });
+
+    let Some(book) = book else {
+        return rsx! {
+            ReaderMessage { message: "This book is no longer in your library", on_close }
+        };
+    };
+    let chapters = match content.as_ref() {
+        Err(e) => {
+            return rsx! {
+                ReaderMessage { message: "Could not open {book.title}: {e}", on_close }
+            };
+        }
+        Ok(Content::Pdf(src)) => {
+            return rsx! {
+                div { class: "flex flex-col h-screen",
+                    ReaderBar { title: book.title.clone(), on_close, on_settings: None }
+                    iframe { class: "flex-1 w-full", src: "{src}", title: "{book.title}" }
+                }
+            };
+        }
+        Ok(Content::Chapters(chapters)) if chapters.is_empty() => {
+            return rsx! {
+                ReaderMessage { message: "{book.title} has no readable text", on_close }
+            };
+        }
+        Ok(Content::Chapters(chapters)) => chapters,
+    };
+
+    let count = chapters.len();
+    let current = chapter().min(count - 1);
+    let shown = &chapters[current];
+    let settings = library.read().reader_settings_for(&book.id);
+
+    rsx! {
+        div { class: "min-h-screen", style: theme_style(settings.theme),
+            ReaderBar {
+                title: book.title.clone(),
+                on_close,
+                on_settings: move |_| show_settings.toggle()
+            }
+
+            if show_settings() {
+                ReaderSettingsPanel { library, book_id: book.id.clone() }
+            }
+
+            article { class: "max-w-3xl mx-auto py-8", style: text_style(&settings),
+                if let Some(title) = &shown.title {
+                    h2 { class: "font-bold text-[1.4em] mb-[1em]", "{title}" }
+                }
+                for (i, paragraph) in shown.paragraphs.iter().enumerate() {
+                    p { key: "{i}", class: "whitespace-pre-line mb-[1em]", "{paragraph}" }
+                }
+            }
+
+            if count > 1 {
+                div { class: "flex items-center justify-center gap-4 pb-8",
+                    button {
+                        class: "btn btn-sm",
+                        disabled: current == 0,
+                        onclick: move |_| chapter.set(current - 1),
+                        "← Previous"
+                    }
+                    span { class: "text-sm opacity-70", "{current + 1} / {count}" }
+                    button {
+                        class: "btn btn-sm",
+                        disabled: current + 1 == count,
+                        onclick: move |_| chapter.set(current + 1),
+                        "Next →"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Inline CSS for the text column
+fn text_style(settings: &ReaderSettings) -> String {
+    let font = match settings.font_family {
+        FontFamily::Serif => "Georgia, 'Iowan Old Style', 'Times New Roman', serif",
+        FontFamily::SansSerif => "ui-sans-serif, system-ui, sans-serif",
+        FontFamily::Monospace => "ui-monospace, Menlo, Consolas, monospace",
+    };
+    let align = settings.align.as_str();
+    let align = if settings.justify {
+        format!("text-align: justify; text-align-last: {align}")
+    } else {
+        format!("text-align: {align}")
+    };
+    // WebKitGTK (webview desktop) masih butuh prefix
+    let hyphens = if settings.hyphenate { "auto" } else { "manual" };
+
+    format!(
+        "font-family: {font}; font-size: {}px; line-height: {:.1}; \
+         padding-inline: calc(1.5rem + {}%); {align}; \
+         hyphens: {hyphens}; -webkit-hyphens: {hyphens}",
+        settings.font_size, settings.line_height, settings.margin
+    )
+}
+
+/// Page background and text colour
+fn theme_style(theme: ReaderTheme) -> &'static str {
+    match theme {
+        ReaderTheme::Light => "background-color: #ffffff; color: #1f2937",
+        ReaderTheme::Dark => "background-color: #1a1a1a; color: #d4d4d4; color-scheme: dark",
+        ReaderTheme::Sepia => "background-color: #f4ecd8; color: #5b4636",
+    }
+}
+
+/// `on_settings` adds the "Aa" button for the text settings
+#[component]
+fn ReaderBar(
+    title: String,
+    on_close: EventHandler<()>,
+    on_settings: Option<EventHandler<()>>,
+) -> Element {
+    rsx! {
+        div {
+            class: "navbar shadow sticky top-0 z-10",
+            style: "background-color: inherit",
+            button {
+                class: "btn btn-ghost",
+                onclick: move |_| on_close.call(()),
+                "← Library"
+            }
+            h1 { class: "flex-1 truncate font-bold px-2", "{title}" }
+            if let Some(on_settings) = on_settings {
+                button {
+                    class: "btn btn-ghost",
+                    title: "Reading settings",
+                    onclick: move |_| on_settings.call(()),
+                    "Aa"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ReaderMessage(message: String, on_close: EventHandler<()>) -> Element {
+    rsx! {
+        div { class: "min-h-screen flex flex-col items-center justify-center gap-4",
+            p { class: "opacity-70", "{message}" }
+            button {
+                class: "btn",
+                onclick: move |_| on_close.call(()),
+                "← Library"
+            }
+        }
+    }
+}
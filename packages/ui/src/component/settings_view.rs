@@ -0,0 +1,824 @@
+use dioxus::prelude::*;
+use hikmah_core::library::Library;
+use hikmah_core::opds::OpdsServerSettings;
+use hikmah_core::preferences::{Language, Preferences, SortOrder, MAX_IMPORT_MB};
+use hikmah_core::sync::{SyncClient, SyncConfig, SyncRound, MAX_ROUNDS};
+use hikmah_core::webdav::{sync_library, WebDavClient, WebDavConfig};
+use tracing::info;
+
+/* =========================
+   SETTINGS (ROOT)
+========================= */
+
+/// State of the desktop OPDS server; platforms without one pass no status
+/// and the card is hidden
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharingStatus {
+    Off,
+    /// Catalog URL for e-readers
+    Serving(String),
+    Failed(String),
+}
+
+/// `on_add_watch_folder` opens a folder picker (desktop); without it the
+/// watched folders card is hidden
+#[component]
+pub fn SettingsView(
+    library: Signal<Library>,
+    sharing: Option<Signal<SharingStatus>>,
+    on_add_watch_folder: Option<EventHandler<()>>,
+) -> Element {
+    rsx! {
+        main { class: "p-6 max-w-2xl mx-auto flex flex-col gap-6",
+            GeneralSettings { library }
+            WebDavSettings { library }
+            SyncServerSettings { library }
+            EncryptionSettings { library }
+            if let Some(on_add) = on_add_watch_folder {
+                WatchedFoldersCard { library, on_add }
+            }
+            if let Some(status) = sharing {
+                OpdsServerCard { library, status }
+            }
+        }
+    }
+}
+
+/* =========================
+   GENERAL
+========================= */
+
+#[component]
+fn GeneralSettings(library: Signal<Library>) -> Element {
+    let saved = library.read().app_preferences();
+    let mut max_import = use_signal(|| saved.max_import_mb.to_string());
+    let mut default_sort = use_signal(|| saved.default_sort);
+    let mut language = use_signal(|| saved.language);
+    // (berhasil?, pesan)
+    let mut status = use_signal(|| None::<(bool, String)>);
+
+    let save = move |_| {
+        let Ok(max_import_mb) = max_import.read().trim().parse() else {
+            status.set(Some((
+                false,
+                "Import limit must be a number of MB".to_string(),
+            )));
+            return;
+        };
+        let prefs = Preferences {
+            max_import_mb,
+            default_sort: default_sort(),
+            language: language(),
+        };
+        match library.write().set_app_preferences(prefs) {
+            Ok(()) => status.set(Some((true, "Saved".to_string()))),
+            Err(e) => status.set(Some((false, e))),
+        }
+    };
+
+    let reset = move |_| {
+        let defaults = Preferences::default();
+        max_import.set(defaults.max_import_mb.to_string());
+        default_sort.set(defaults.default_sort);
+        language.set(defaults.language);
+        status.set(None);
+    };
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "⚙️ General" }
+
+                label { class: "form-control",
+                    span { class: "label-text", "Language" }
+                    select {
+                        class: "select select-bordered",
+                        onchange: move |e| language.set(Language::from_code(&e.value())),
+                        option { value: "", selected: language().is_none(), "System default" }
+                        for lang in Language::ALL {
+                            option {
+                                key: "{lang.code()}",
+                                value: "{lang.code()}",
+                                selected: language() == Some(lang),
+                                "{lang.native_name()}"
+                            }
+                        }
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Sort library by" }
+                    select {
+                        class: "select select-bordered",
+                        onchange: move |e| {
+                            if let Some(order) = SortOrder::from_name(&e.value()) {
+                                default_sort.set(order);
+                            }
+                        },
+                        for order in SortOrder::ALL {
+                            option {
+                                key: "{order.as_str()}",
+                                value: "{order.as_str()}",
+                                selected: default_sort() == order,
+                                "{order.label()}"
+                            }
+                        }
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Largest book to import (MB)" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "number",
+                        min: "{MAX_IMPORT_MB.start()}",
+                        max: "{MAX_IMPORT_MB.end()}",
+                        value: "{max_import}",
+                        oninput: move |e| max_import.set(e.value())
+                    }
+                    span { class: "label-text-alt opacity-70 mt-1",
+                        "Applies to files read into memory: the file picker and drops in the browser. "
+                        "Files added from disk in the desktop app have no limit."
+                    }
+                }
+
+                if let Some((ok, message)) = status() {
+                    div { class: if ok { "alert alert-success" } else { "alert alert-error" },
+                        span { "{message}" }
+                    }
+                }
+
+                div { class: "card-actions justify-end",
+                    button { class: "btn btn-ghost", onclick: reset, "Reset to defaults" }
+                    button { class: "btn btn-primary", onclick: save, "Save" }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   WEBDAV
+========================= */
+
+#[component]
+fn WebDavSettings(library: Signal<Library>) -> Element {
+    let saved = library.read().webdav_config();
+    let mut url = use_signal(|| saved.as_ref().map(|c| c.url.clone()).unwrap_or_default());
+    let mut username = use_signal(|| {
+        saved
+            .as_ref()
+            .map(|c| c.username.clone())
+            .unwrap_or_default()
+    });
+    let mut password = use_signal(|| {
+        saved
+            .as_ref()
+            .map(|c| c.password.clone())
+            .unwrap_or_default()
+    });
+    let mut busy = use_signal(|| false);
+    // (berhasil?, pesan)
+    let mut status = use_signal(|| None::<(bool, String)>);
+
+    let form_config = move || {
+        let url = url.read().trim().to_string();
+        (!url.is_empty()).then(|| WebDavConfig {
+            url,
+            username: username.read().trim().to_string(),
+            password: password.read().clone(),
+        })
+    };
+
+    let save = move |_| {
+        let result = library.write().set_webdav_config(form_config());
+        match result {
+            Ok(()) => status.set(Some((true, "Saved".to_string()))),
+            Err(e) => status.set(Some((false, format!("Failed to save: {e}")))),
+        }
+    };
+
+    let test = move |_| {
+        let Some(config) = form_config() else {
+            status.set(Some((false, "Enter the folder URL first".to_string())));
+            return;
+        };
+        spawn(async move {
+            busy.set(true);
+            match WebDavClient::new(config).check().await {
+                Ok(()) => status.set(Some((true, "Connection works".to_string()))),
+                Err(e) => status.set(Some((false, e))),
+            }
+            busy.set(false);
+        });
+    };
+
+    let sync_now = move |_| {
+        let Some(config) = library.read().webdav_config() else {
+            status.set(Some((false, "Save the WebDAV settings first".to_string())));
+            return;
+        };
+        spawn(async move {
+            busy.set(true);
+            // Request berjalan pada snapshot, supaya signal tidak terkunci;
+            // hasilnya digabung ke library, jadi perubahan selama sync tetap ada
+            let mut snapshot = library.read().sync_snapshot();
+            match sync_library(&WebDavClient::new(config), &mut snapshot).await {
+                Ok(report) => {
+                    info!("{}", report);
+                    let result = library.write().apply_webdav_sync(snapshot);
+                    match result {
+                        Ok(()) => status.set(Some((true, report.to_string()))),
+                        Err(e) => status.set(Some((false, format!("Failed to save: {e}")))),
+                    }
+                }
+                Err(e) => {
+                    // Data terenkripsi yang belum bisa dibuka: simpan contohnya
+                    // supaya passphrase yang dimasukkan nanti bisa diperiksa
+                    library.write().keep_encryption_lock(&snapshot);
+                    status.set(Some((false, e)));
+                }
+            }
+            busy.set(false);
+        });
+    };
+
+    let disconnect = move |_| {
+        url.set(String::new());
+        username.set(String::new());
+        password.set(String::new());
+        let result = library.write().set_webdav_config(None);
+        if let Err(e) = result {
+            status.set(Some((false, format!("Failed to save: {e}"))));
+        } else {
+            status.set(None);
+        }
+    };
+
+    let connected = library.read().webdav_config().is_some();
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "☁️ WebDAV" }
+                p { class: "text-sm opacity-70",
+                    "Keep your library and book files in a WebDAV folder, e.g. on Nextcloud. "
+                    "Every device that uses the same folder stays in sync."
+                }
+
+                label { class: "form-control",
+                    span { class: "label-text", "Folder URL" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "url",
+                        placeholder: "https://cloud.example.com/remote.php/dav/files/me/Hikmah",
+                        value: "{url}",
+                        oninput: move |e| url.set(e.value())
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Username" }
+                    input {
+                        class: "input input-bordered",
+                        autocomplete: "username",
+                        value: "{username}",
+                        oninput: move |e| username.set(e.value())
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Password" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "password",
+                        autocomplete: "current-password",
+                        value: "{password}",
+                        oninput: move |e| password.set(e.value())
+                    }
+                }
+
+                if let Some((ok, message)) = status() {
+                    div { class: if ok { "alert alert-success" } else { "alert alert-error" },
+                        span { class: "whitespace-pre-line", "{message}" }
+                    }
+                }
+
+                div { class: "card-actions justify-end",
+                    if connected {
+                        button {
+                            class: "btn btn-ghost",
+                            onclick: disconnect,
+                            disabled: busy(),
+                            "Disconnect"
+                        }
+                    }
+                    button {
+                        class: "btn",
+                        onclick: test,
+                        disabled: busy(),
+                        "Test connection"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        onclick: save,
+                        disabled: busy(),
+                        "Save"
+                    }
+                    if connected {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: sync_now,
+                            disabled: busy(),
+                            if busy() {
+                                span { class: "loading loading-spinner loading-sm" }
+                            }
+                            "🔄 Sync now"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   SYNC SERVER
+========================= */
+
+#[component]
+fn SyncServerSettings(library: Signal<Library>) -> Element {
+    let saved = library.read().sync_server_config();
+    let mut server_url = use_signal(|| {
+        saved
+            .as_ref()
+            .map(|c| c.server_url.clone())
+            .unwrap_or_default()
+    });
+    let mut username = use_signal(|| {
+        saved
+            .as_ref()
+            .map(|c| c.username.clone())
+            .unwrap_or_default()
+    });
+    // Hanya key turunannya yang disimpan, password dikosongkan lagi
+    let mut password = use_signal(String::new);
+    let mut busy = use_signal(|| false);
+    // (berhasil?, pesan)
+    let mut status = use_signal(|| None::<(bool, String)>);
+
+    let form_config = move || -> Result<SyncConfig, String> {
+        let url = server_url.read().trim().to_string();
+        let name = username.read().trim().to_string();
+        if url.is_empty() || name.is_empty() || password.read().is_empty() {
+            return Err("Enter the server URL, username and password".to_string());
+        }
+        SyncConfig::new(&url, &name, &password.read())
+    };
+
+    let mut save_config = move |config: SyncConfig, message: String| {
+        let result = library.write().set_sync_server_config(Some(config));
+        match result {
+            Ok(()) => {
+                password.set(String::new());
+                status.set(Some((true, message)));
+            }
+            Err(e) => status.set(Some((false, format!("Failed to save: {e}")))),
+        }
+    };
+
+    let save = move |_| match form_config() {
+        Ok(config) => save_config(config, "Saved".to_string()),
+        Err(e) => status.set(Some((false, e))),
+    };
+
+    let register = move |_| {
+        let config = match form_config() {
+            Ok(config) => config,
+            Err(e) => {
+                status.set(Some((false, e)));
+                return;
+            }
+        };
+        spawn(async move {
+            busy.set(true);
+            match SyncClient::new(config.clone()).register().await {
+                Ok(()) => save_config(config, "Account created".to_string()),
+                Err(e) => status.set(Some((false, e))),
+            }
+            busy.set(false);
+        });
+    };
+
+    let sync_now = move |_| {
+        let Some(config) = library.read().sync_server_config() else {
+            status.set(Some((
+                false,
+                "Save the sync server settings first".to_string(),
+            )));
+            return;
+        };
+        spawn(async move {
+            busy.set(true);
+            let client = SyncClient::new(config);
+            // Library tidak dipinjam selama request berjalan; perubahan di
+            // antaranya tetap pending dan ikut di sync berikutnya
+            let mut result = Ok(());
+            for _ in 0..MAX_ROUNDS {
+                let round = SyncRound::new(&library.read());
+                let round = match round {
+                    Ok(round) => round,
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                };
+                let pulled = client.sync(&round.outgoing, round.since).await;
+                result =
+                    pulled.and_then(|pulled| library.write().apply_sync(&round.pending, pulled));
+                if result.is_err() || library.read().sync.pending.is_empty() {
+                    break;
+                }
+            }
+            match result {
+                Ok(()) => {
+                    let version = library.read().sync.last_version;
+                    status.set(Some((true, format!("Synced (server version {version})"))));
+                }
+                Err(e) => status.set(Some((false, e))),
+            }
+            busy.set(false);
+        });
+    };
+
+    let disconnect = move |_| {
+        server_url.set(String::new());
+        username.set(String::new());
+        password.set(String::new());
+        let result = library.write().set_sync_server_config(None);
+        if let Err(e) = result {
+            status.set(Some((false, format!("Failed to save: {e}"))));
+        } else {
+            status.set(None);
+        }
+    };
+
+    let connected = library.read().sync_server_config().is_some();
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "🔁 Sync server" }
+                p { class: "text-sm opacity-70",
+                    "Sync tags, reading positions, notes and collections through a Hikmah sync server. "
+                    "Book files stay on each device."
+                }
+
+                label { class: "form-control",
+                    span { class: "label-text", "Server URL" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "url",
+                        placeholder: "https://sync.example.com",
+                        value: "{server_url}",
+                        oninput: move |e| server_url.set(e.value())
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Username" }
+                    input {
+                        class: "input input-bordered",
+                        autocomplete: "username",
+                        value: "{username}",
+                        oninput: move |e| username.set(e.value())
+                    }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Password" }
+                    input {
+                        class: "input input-bordered",
+                        r#type: "password",
+                        autocomplete: "current-password",
+                        placeholder: if connected { "Saved; enter it again to change" } else { "" },
+                        value: "{password}",
+                        oninput: move |e| password.set(e.value())
+                    }
+                }
+
+                if let Some((ok, message)) = status() {
+                    div { class: if ok { "alert alert-success" } else { "alert alert-error" },
+                        span { class: "whitespace-pre-line", "{message}" }
+                    }
+                }
+
+                div { class: "card-actions justify-end",
+                    if connected {
+                        button {
+                            class: "btn btn-ghost",
+                            onclick: disconnect,
+                            disabled: busy(),
+                            "Disconnect"
+                        }
+                    }
+                    button {
+                        class: "btn",
+                        onclick: register,
+                        disabled: busy(),
+                        "Create account"
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        onclick: save,
+                        disabled: busy(),
+                        "Save"
+                    }
+                    if connected {
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: sync_now,
+                            disabled: busy(),
+                            if busy() {
+                                span { class: "loading loading-spinner loading-sm" }
+                            }
+                            "🔄 Sync now"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   ENCRYPTION
+========================= */
+
+#[component]
+fn EncryptionSettings(library: Signal<Library>) -> Element {
+    let mut current = use_signal(String::new);
+    let mut new_passphrase = use_signal(String::new);
+    let mut confirm = use_signal(String::new);
+    // (berhasil?, pesan)
+    let mut status = use_signal(|| None::<(bool, String)>);
+
+    let enabled = library.read().encryption_enabled();
+    let locked = library.read().encryption_locked();
+
+    let mut finish = move |result: Result<(), String>, done: &str| match result {
+        Ok(()) => {
+            current.set(String::new());
+            new_passphrase.set(String::new());
+            confirm.set(String::new());
+            status.set(Some((true, done.to_string())));
+        }
+        Err(e) => status.set(Some((false, e))),
+    };
+
+    let checked_new = move || {
+        let new = new_passphrase.read().clone();
+        if new.chars().count() < 8 {
+            Err("Use at least 8 characters".to_string())
+        } else if new != *confirm.read() {
+            Err("The passphrases don't match".to_string())
+        } else {
+            Ok(new)
+        }
+    };
+
+    let turn_on = move |_| {
+        let result = checked_new().and_then(|new| library.write().set_passphrase(&new));
+        finish(
+            result,
+            "Encryption is on. Everything is re-encrypted on the next sync.",
+        );
+    };
+
+    let unlock = move |_| {
+        let result = library.write().set_passphrase(&current.read());
+        finish(result, "Unlocked. Sync again to finish.");
+    };
+
+    let change = move |_| {
+        let result =
+            checked_new().and_then(|new| library.write().change_passphrase(&current.read(), &new));
+        finish(
+            result,
+            "Passphrase changed. Everything is re-encrypted on the next sync; other devices will ask for the new passphrase.",
+        );
+    };
+
+    let turn_off = move |_| {
+        let result = library.write().disable_encryption(&current.read());
+        finish(result, "Encryption is off.");
+    };
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title",
+                    "🔒 Encryption"
+                    if enabled {
+                        span { class: "badge badge-success", "On" }
+                    }
+                }
+                p { class: "text-sm opacity-70",
+                    "Encrypts what's sent to the sync server and WebDAV, and your backups, "
+                    "with a key made from your passphrase. Use the same passphrase on every device. "
+                    "If you forget it, encrypted data can't be recovered. "
+                    "The key stays on this device so syncing doesn't ask for it every time: "
+                    "it protects what leaves the device, not the device itself."
+                }
+
+                if locked {
+                    div { class: "alert alert-warning",
+                        span { "Synced data is encrypted with a passphrase this device doesn't know yet." }
+                    }
+                }
+
+                if enabled || locked {
+                    label { class: "form-control",
+                        span { class: "label-text",
+                            if locked { "Passphrase" } else { "Current passphrase" }
+                        }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            autocomplete: "current-password",
+                            value: "{current}",
+                            oninput: move |e| current.set(e.value())
+                        }
+                    }
+                }
+                if !locked {
+                    label { class: "form-control",
+                        span { class: "label-text",
+                            if enabled { "New passphrase" } else { "Passphrase" }
+                        }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            autocomplete: "new-password",
+                            value: "{new_passphrase}",
+                            oninput: move |e| new_passphrase.set(e.value())
+                        }
+                    }
+                    label { class: "form-control",
+                        span { class: "label-text", "Repeat passphrase" }
+                        input {
+                            class: "input input-bordered",
+                            r#type: "password",
+                            autocomplete: "new-password",
+                            value: "{confirm}",
+                            oninput: move |e| confirm.set(e.value())
+                        }
+                    }
+                }
+
+                if let Some((ok, message)) = status() {
+                    div { class: if ok { "alert alert-success" } else { "alert alert-error" },
+                        span { "{message}" }
+                    }
+                }
+
+                div { class: "card-actions justify-end",
+                    if locked {
+                        button { class: "btn btn-primary", onclick: unlock, "Unlock" }
+                    } else if enabled {
+                        button { class: "btn btn-ghost", onclick: turn_off, "Turn off" }
+                        button { class: "btn btn-primary", onclick: change, "Change passphrase" }
+                    } else {
+                        button { class: "btn btn-primary", onclick: turn_on, "Turn on encryption" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   OPDS SERVER
+========================= */
+
+#[component]
+fn OpdsServerCard(library: Signal<Library>, status: Signal<SharingStatus>) -> Element {
+    let saved = library.read().opds_server_settings();
+    let mut port = use_signal(|| saved.port.to_string());
+    let mut error = use_signal(|| None::<String>);
+
+    // Aplikasi desktop memantau setting ini dan menyalakan/mematikan server
+    let mut apply = move |enabled: bool| {
+        let Ok(port) = port.read().trim().parse::<u16>() else {
+            error.set(Some("The port must be a number up to 65535".to_string()));
+            return;
+        };
+        if port < 1024 {
+            error.set(Some("Use a port from 1024 up".to_string()));
+            return;
+        }
+        let result = library
+            .write()
+            .set_opds_server_settings(OpdsServerSettings { enabled, port });
+        error.set(result.err().map(|e| format!("Failed to save: {e}")));
+    };
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "📡 Share on local network" }
+                p { class: "text-sm opacity-70",
+                    "Serve your library as an OPDS catalog so e-readers on the same Wi-Fi "
+                    "(KOReader, Moon+ Reader, ...) can browse and download books. "
+                    "Anyone on the network can reach it while it's on."
+                }
+
+                label { class: "label cursor-pointer justify-start gap-4",
+                    input {
+                        r#type: "checkbox",
+                        class: "toggle toggle-primary",
+                        checked: saved.enabled,
+                        onchange: move |e| apply(e.checked())
+                    }
+                    span { class: "label-text", "Serve OPDS catalog" }
+                }
+                label { class: "form-control",
+                    span { class: "label-text", "Port" }
+                    div { class: "join",
+                        input {
+                            class: "input input-bordered join-item flex-1",
+                            r#type: "number",
+                            min: "1024",
+                            max: "65535",
+                            value: "{port}",
+                            oninput: move |e| port.set(e.value())
+                        }
+                        button {
+                            class: "btn join-item",
+                            onclick: move |_| apply(saved.enabled),
+                            "Apply"
+                        }
+                    }
+                }
+
+                if let Some(message) = error() {
+                    div { class: "alert alert-error", span { "{message}" } }
+                }
+                match status() {
+                    SharingStatus::Off => rsx! {},
+                    SharingStatus::Serving(url) => rsx! {
+                        div { class: "alert alert-success",
+                            span { "Add this catalog on your e-reader: " code { "{url}" } }
+                        }
+                    },
+                    SharingStatus::Failed(message) => rsx! {
+                        div { class: "alert alert-error", span { "{message}" } }
+                    },
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   WATCHED FOLDERS
+========================= */
+
+#[component]
+fn WatchedFoldersCard(library: Signal<Library>, on_add: EventHandler<()>) -> Element {
+    let folders = library.read().watched_folders().to_vec();
+    let mut error = use_signal(|| None::<String>);
+
+    rsx! {
+        section { class: "card bg-base-200",
+            div { class: "card-body gap-4",
+                h2 { class: "card-title", "📂 Watched folders" }
+                p { class: "text-sm opacity-70",
+                    "New EPUB, PDF and TXT files in these folders are added to your library "
+                    "automatically, also when they arrive while the app is closed. "
+                    "Deleting a file from a folder keeps its book in the library."
+                }
+
+                if folders.is_empty() {
+                    p { class: "text-sm italic opacity-70", "No folders watched yet." }
+                }
+                ul { class: "flex flex-col gap-2",
+                    for folder in folders {
+                        li { key: "{folder}", class: "flex items-center gap-2",
+                            code { class: "flex-1 truncate text-sm", title: "{folder}", "{folder}" }
+                            button {
+                                class: "btn btn-sm btn-ghost",
+                                onclick: move |_| {
+                                    let result = library.write().remove_watched_folder(&folder);
+                                    error.set(result.err().map(|e| format!("Failed to save: {e}")));
+                                },
+                                "Stop watching"
+                            }
+                        }
+                    }
+                }
+
+                if let Some(message) = error() {
+                    div { class: "alert alert-error", span { "{message}" } }
+                }
+                div { class: "card-actions",
+                    button { class: "btn btn-primary", onclick: move |_| on_add.call(()), "Add folder" }
+                }
+            }
+        }
+    }
+}
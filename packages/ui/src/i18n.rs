@@ -0,0 +1,131 @@
+use dioxus::prelude::*;
+use fluent_bundle::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use hikmah_core::library::Library;
+use hikmah_core::preferences::Language;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tracing::warn;
+use unic_langid::LanguageIdentifier;
+
+type Bundle = FluentBundle<FluentResource>;
+
+/* =========================
+   I18N
+========================= */
+
+thread_local! {
+    /// Satu bundle per bahasa, dibuat saat pertama dipakai
+    static BUNDLES: RefCell<HashMap<Language, Rc<Bundle>>> = RefCell::new(HashMap::new());
+}
+
+/// Interface messages in one language, from the Fluent catalogs in
+/// `ui/locales`. Messages not translated yet fall back to English.
+#[derive(Clone)]
+pub struct I18n {
+    pub language: Language,
+    bundle: Rc<Bundle>,
+    fallback: Rc<Bundle>,
+}
+
+impl I18n {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            bundle: bundle(language),
+            fallback: bundle(Language::English),
+        }
+    }
+
+    /// Arabic is written right to left
+    pub fn is_rtl(&self) -> bool {
+        self.language == Language::Arabic
+    }
+
+    pub fn t(&self, id: &str) -> String {
+        self.format(id, None)
+    }
+
+    /// A message with arguments; numbers pick the plural form, e.g.
+    /// `t_with("library-book-count", &[("count", 3.into())])`
+    pub fn t_with(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        self.format(id, Some(&fluent_args))
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in [&self.bundle, &self.fallback] {
+            let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) else {
+                continue;
+            };
+            let mut errors = vec![];
+            let text = bundle.format_pattern(pattern, args, &mut errors);
+            if !errors.is_empty() {
+                warn!("Message {} ({}): {:?}", id, self.language.code(), errors);
+            }
+            return text.into_owned();
+        }
+        warn!("Missing message {}", id);
+        id.to_string()
+    }
+}
+
+fn bundle(language: Language) -> Rc<Bundle> {
+    BUNDLES.with(|bundles| {
+        (bundles.borrow_mut())
+            .entry(language)
+            .or_insert_with(|| Rc::new(load_bundle(language)))
+            .clone()
+    })
+}
+
+fn load_bundle(language: Language) -> Bundle {
+    let source = match language {
+        Language::English => include_str!("../locales/en.ftl"),
+        Language::Indonesian => include_str!("../locales/id.ftl"),
+        Language::Arabic => include_str!("../locales/ar.ftl"),
+    };
+    let resource =
+        FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+            warn!("Errors in {}.ftl: {:?}", language.code(), errors);
+            resource
+        });
+
+    let locale: LanguageIdentifier = language.code().parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new(vec![locale]);
+    // Tanpa tanda isolasi Unicode (FSI/PDI) di sekitar argumen: webview
+    // sudah mengatur arah teks, dan tandanya ikut muncul saat disalin
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+        warn!("Errors in {}.ftl: {:?}", language.code(), errors);
+    }
+    bundle
+}
+
+/// The language chosen in Settings, else the first system language we have
+/// messages for, else English
+pub fn resolve_language(preferred: Option<Language>) -> Language {
+    preferred
+        .or_else(|| sys_locale::get_locales().find_map(|locale| Language::from_code(&locale)))
+        .unwrap_or(Language::English)
+}
+
+/// Make the language available to every component below, following the
+/// preferences as they change. The router's layout calls this once.
+pub fn use_i18n_provider(library: Signal<Library>) -> I18n {
+    let language = use_memo(move || resolve_language(library.read().app_preferences().language));
+    use_context_provider(|| language);
+    I18n::new(language())
+}
+
+/// Messages in the current language
+pub fn use_i18n() -> I18n {
+    let language = try_use_context::<Memo<Language>>();
+    I18n::new(match language {
+        Some(language) => language(),
+        None => resolve_language(None),
+    })
+}
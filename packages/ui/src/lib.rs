@@ -1,6 +1,8 @@
 use dioxus::prelude::*;
 
 pub mod component;
+pub mod i18n;
+pub mod route;
 pub mod web;
 
 pub const GLOBAL_CSS: Asset = asset!("/assets/styling/global.css");
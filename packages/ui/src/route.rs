@@ -0,0 +1,196 @@
+use dioxus::prelude::*;
+use hikmah_core::library::Library;
+
+use crate::component::drop_zone::{ImportProgressPanel, ImportResults};
+use crate::component::library_view::ErrorBanner;
+use crate::component::{DiscoverView, LibraryView, ReaderView, SettingsView, SharingStatus};
+use crate::i18n::use_i18n_provider;
+use crate::web::{Features, Footer, Hero, Navbar};
+use crate::GLOBAL_CSS;
+
+/* =========================
+   ROUTES
+========================= */
+
+/// Pages shared by web, desktop and mobile. Platforms provide a
+/// `Signal<Library>` (and optionally [`PlatformActions`]) as context, then
+/// render `Router::<Route> {}`.
+#[derive(Routable, Clone, PartialEq, Debug)]
+#[rustfmt::skip]
+pub enum Route {
+    #[layout(Shell)]
+        #[route("/")]
+        Home {},
+        #[route("/library", LibraryPage)]
+        Library {},
+        #[route("/reader/:book_id", ReaderPage)]
+        Reader { book_id: String },
+        #[route("/collection/:name", CollectionPage)]
+        Collection { name: String },
+        #[route("/discover", DiscoverPage)]
+        Discover {},
+        #[route("/settings", SettingsPage)]
+        Settings {},
+}
+
+/// What a platform can do natively. Everything is optional; web and mobile
+/// provide none of it.
+#[derive(Clone, Copy, Default)]
+pub struct PlatformActions {
+    /// Export selected books to a folder; enables selection on book cards
+    pub on_export: Option<EventHandler<Vec<String>>>,
+    /// Native file picker behind "Add Book"
+    pub on_import: Option<EventHandler<()>>,
+    /// "Add Folder" button for importing a whole tree
+    pub on_import_folder: Option<EventHandler<()>>,
+    /// Folder picker for watched folders in Settings
+    pub on_add_watch_folder: Option<EventHandler<()>>,
+    /// Status of the desktop OPDS server, controlled from Settings
+    pub sharing: Option<Signal<SharingStatus>>,
+    /// Set to a book id to open it in the reader from outside the UI
+    /// (files opened with the desktop app)
+    pub open_book: Option<Signal<Option<String>>>,
+}
+
+fn platform_actions() -> PlatformActions {
+    try_use_context::<PlatformActions>().unwrap_or_default()
+}
+
+/* =========================
+   SHELL LAYOUT
+========================= */
+
+/// Navbar on every page but the reader, plus import progress and errors,
+/// which can come in from anywhere (watched folders, sync). Also sets the
+/// interface language (and right-to-left layout for Arabic) for every page.
+#[component]
+fn Shell() -> Element {
+    let library = use_context::<Signal<Library>>();
+    let i18n = use_i18n_provider(library);
+    let route = use_route::<Route>();
+    let open_book = platform_actions().open_book;
+
+    use_effect(move || {
+        let Some(mut open_book) = open_book else {
+            return;
+        };
+        if let Some(book_id) = open_book() {
+            open_book.set(None);
+            navigator().push(Route::Reader { book_id });
+        }
+    });
+
+    let lib = library.read();
+
+    rsx! {
+        div {
+            lang: i18n.language.code(),
+            dir: if i18n.is_rtl() { "rtl" } else { "ltr" },
+
+            if !matches!(route, Route::Reader { .. }) {
+                Navbar {}
+            }
+
+            Outlet::<Route> {}
+
+            if lib.import_progress.is_some() {
+                ImportProgressPanel { library }
+            } else if !lib.import_results.is_empty() {
+                ImportResults { library }
+            }
+
+            if let Some(err) = &lib.err {
+                ErrorBanner {
+                    error: err.clone(),
+                    library
+                }
+            }
+        }
+    }
+}
+
+/* =========================
+   PAGES
+========================= */
+
+#[component]
+fn Home() -> Element {
+    rsx! {
+        document::Link { rel: "stylesheet", href: GLOBAL_CSS }
+        div { class: "app-container",
+            Hero {}
+            Features {}
+            Footer {}
+        }
+    }
+}
+
+#[component]
+fn LibraryPage() -> Element {
+    let library = use_context::<Signal<Library>>();
+    let actions = platform_actions();
+
+    rsx! {
+        LibraryView {
+            library,
+            on_export: actions.on_export,
+            on_import: actions.on_import,
+            on_import_folder: actions.on_import_folder
+        }
+    }
+}
+
+#[component]
+fn CollectionPage(name: String) -> Element {
+    let library = use_context::<Signal<Library>>();
+    let actions = platform_actions();
+
+    rsx! {
+        LibraryView {
+            library,
+            collection: name,
+            on_export: actions.on_export,
+            on_import: actions.on_import,
+            on_import_folder: actions.on_import_folder
+        }
+    }
+}
+
+#[component]
+fn ReaderPage(book_id: String) -> Element {
+    let library = use_context::<Signal<Library>>();
+
+    rsx! {
+        ReaderView {
+            key: "{book_id}",
+            library,
+            book_id: book_id.clone(),
+            on_close: move |_| {
+                navigator().push(Route::Library {});
+            }
+        }
+    }
+}
+
+#[component]
+fn DiscoverPage() -> Element {
+    let library = use_context::<Signal<Library>>();
+
+    rsx! {
+        DiscoverView { library }
+    }
+}
+
+#[component]
+fn SettingsPage() -> Element {
+    let library = use_context::<Signal<Library>>();
+    let actions = platform_actions();
+
+    rsx! {
+        SettingsView {
+            library,
+            sharing: actions.sharing,
+            on_add_watch_folder: actions.on_add_watch_folder
+        }
+    }
+}
@@ -1,36 +1,28 @@
 use dioxus::prelude::*;
 
+use crate::i18n::use_i18n;
+
 const FEATURES_CSS: Asset = asset!("/assets/styling/features.css");
 
 #[component]
 pub fn Features() -> Element {
+    let i18n = use_i18n();
+
     rsx! {
         document::Link { rel: "stylesheet", href: FEATURES_CSS }
         section {
             id: "features",
-            h2 { class: "section-title", "Why Choose BookReader?" }
+            h2 { class: "section-title", {i18n.t("features-title")} }
             div {
                 class: "features-grid",
 
-                FeatureCard {
-                    icon: "🔄",
-                    title: "Cross-Platform Sync",
-                    description: "Seamlessly sync your reading progress across all devices"
-                }
-                FeatureCard {
-                    icon: "🎨",
-                    title: "Customizable Reading",
-                    description: "Adjust fonts, themes, and layout to your preference"
-                }
-                FeatureCard {
-                    icon: "📝",
-                    title: "Notes & Highlights",
-                    description: "Take notes and highlight important passages"
-                }
-                FeatureCard {
-                    icon: "📚",
-                    title: "Large Format Support",
-                    description: "Support for EPUB, PDF, MOBI, and more"
+                for (icon, name) in [("🔄", "sync"), ("🎨", "custom"), ("📝", "notes"), ("📚", "formats")] {
+                    FeatureCard {
+                        key: "{name}",
+                        icon,
+                        title: i18n.t(&format!("feature-{name}-title")),
+                        description: i18n.t(&format!("feature-{name}-description"))
+                    }
                 }
             }
         }
@@ -38,7 +30,7 @@ pub fn Features() -> Element {
 }
 
 #[component]
-fn FeatureCard(icon: &'static str, title: &'static str, description: &'static str) -> Element {
+fn FeatureCard(icon: &'static str, title: String, description: String) -> Element {
     rsx! {
         div {
             class: "feature-card",
@@ -1,14 +1,18 @@
 use dioxus::prelude::*;
 
+use crate::i18n::use_i18n;
+
 const FOOTER_CSS: Asset = asset!("/assets/styling/footer.css");
 
 #[component]
 pub fn Footer() -> Element {
+    let i18n = use_i18n();
+
     rsx! {
         document::Link { rel: "stylesheet", href: FOOTER_CSS }
         footer {
             id: "footer",
-            p { "© 2025 BookReader. Built with Dioxus & Rust 🦀" }
+            p { {i18n.t("footer-text")} }
         }
     }
 }
@@ -1,9 +1,25 @@
 use dioxus::prelude::*;
+use hikmah_core::library::Library;
+
+use crate::i18n::use_i18n;
+use crate::route::Route;
 
 const HERO_CSS: Asset = asset!("/assets/styling/hero.css");
 
 #[component]
 pub fn Hero() -> Element {
+    let i18n = use_i18n();
+    // Lanjutkan buku terakhir kalau ada, selain itu mulai dari library
+    let library = try_use_context::<Signal<Library>>();
+    let start_reading = move |_| {
+        let last = library.and_then(|l| l.read().last_read_book().map(|b| b.id.clone()));
+        let target = match last {
+            Some(book_id) => Route::Reader { book_id },
+            None => Route::Library {},
+        };
+        navigator().push(target);
+    };
+
     rsx! {
         document::Link { rel: "stylesheet", href: HERO_CSS }
         section {
@@ -12,29 +28,27 @@ pub fn Hero() -> Element {
                 class: "hero-content",
                 h1 {
                     class: "hero-title",
-                    "Your Digital Library"
+                    {i18n.t("hero-title")}
                     br {}
-                    span { class: "hero-highlight", "Anywhere, Anytime" }
+                    span { class: "hero-highlight", {i18n.t("hero-highlight")} }
                 }
                 p {
                     class: "hero-description",
-                    "Read your favorite books across all your devices with our powerful multi-platform ebook reader. Sync your progress, highlights, and notes seamlessly."
+                    {i18n.t("hero-description")}
                 }
                 div {
                     class: "hero-buttons",
                     button {
                         class: "btn btn-primary",
-                        onclick: |_| {
-                            // Handle start reading
-                        },
-                        "Start Reading"
+                        onclick: start_reading,
+                        {i18n.t("hero-start-reading")}
                     }
                     button {
                         class: "btn btn-secondary",
-                        onclick: |_| {
-                            // Handle browse library
+                        onclick: move |_| {
+                            navigator().push(Route::Library {});
                         },
-                        "Browse Library"
+                        {i18n.t("hero-browse")}
                     }
                 }
             }
@@ -51,4 +65,3 @@ pub fn Hero() -> Element {
         }
     }
 }
-
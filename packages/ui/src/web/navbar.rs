@@ -1,10 +1,14 @@
 use dioxus::prelude::*;
 
+use crate::i18n::use_i18n;
+use crate::route::Route;
+
 const NAVBAR_CSS: Asset = asset!("/assets/styling/navbar.css");
 
 #[component]
 pub fn Navbar() -> Element {
     let mut menu_open = use_signal(|| false);
+    let i18n = use_i18n();
 
     rsx! {
         document::Link { rel: "stylesheet", href: NAVBAR_CSS }
@@ -13,18 +17,19 @@ pub fn Navbar() -> Element {
             div {
                 class: "nav-container",
 
-                div {
+                Link {
                     class: "nav-logo",
+                    to: Route::Home {},
                     span { class: "logo-icon", "📚" }
-                    span { class: "logo-text", "BookReader" }
+                    span { class: "logo-text", {i18n.t("app-name")} }
                 }
 
                 div {
                     class: "nav-menu",
-                    a { class: "nav-link", href: "#", "Home" }
-                    a { class: "nav-link", href: "#", "Library" }
-                    a { class: "nav-link", href: "#", "Discover" }
-                    a { class: "nav-link", href: "#", "Settings" }
+                    Link { class: "nav-link", to: Route::Home {}, {i18n.t("nav-home")} }
+                    Link { class: "nav-link", to: Route::Library {}, {i18n.t("nav-library")} }
+                    Link { class: "nav-link", to: Route::Discover {}, {i18n.t("nav-discover")} }
+                    Link { class: "nav-link", to: Route::Settings {}, {i18n.t("nav-settings")} }
                 }
 
                 button {
@@ -37,10 +42,11 @@ pub fn Navbar() -> Element {
             if menu_open() {
                 div {
                     class: "mobile-menu",
-                    a { class: "mobile-link", href: "#", "Home" }
-                    a { class: "mobile-link", href: "#", "Library" }
-                    a { class: "mobile-link", href: "#", "Discover" }
-                    a { class: "mobile-link", href: "#", "Settings" }
+                    onclick: move |_| menu_open.set(false),
+                    Link { class: "mobile-link", to: Route::Home {}, {i18n.t("nav-home")} }
+                    Link { class: "mobile-link", to: Route::Library {}, {i18n.t("nav-library")} }
+                    Link { class: "mobile-link", to: Route::Discover {}, {i18n.t("nav-discover")} }
+                    Link { class: "mobile-link", to: Route::Settings {}, {i18n.t("nav-settings")} }
                 }
             }
         }
@@ -1,20 +1,44 @@
 use dioxus::prelude::*;
-use ui::web::{Features, Footer, Hero, Navbar};
-use ui::GLOBAL_CSS;
+use hikmah_core::library::Library;
+use ui::route::Route;
+use ui::MAIN_CSS;
 fn main() {
     dioxus::launch(App);
 }
 
 #[component]
 pub fn App() -> Element {
+    let mut library = use_signal(Library::new);
+    let mut ready = use_signal(|| false);
+    use_context_provider(|| library);
+
+    // Library tersimpan di IndexedDB, yang hanya bisa dibaca secara async
+    use_future(move || async move {
+        library.set(Library::load_from_browser().await);
+        ready.set(true);
+    });
+
+    // Service worker bikin app bisa dibuka offline dan di-install. Tidak
+    // dipakai saat `dx serve`, supaya perubahan langsung kelihatan
+    use_hook(|| {
+        if !cfg!(debug_assertions) {
+            document::eval(
+                "if ('serviceWorker' in navigator) { navigator.serviceWorker.register('/sw.js'); }",
+            );
+        }
+    });
+
     rsx! {
-         document::Link { rel: "stylesheet", href: GLOBAL_CSS }
-         div {
-             class: "app-container",
-             Navbar {  }
-             Hero {  }
-             Features {  }
-             Footer {  }
-         }
+        document::Link { rel: "stylesheet", href: MAIN_CSS }
+        document::Link { rel: "manifest", href: "/manifest.webmanifest" }
+        document::Meta { name: "theme-color", content: "#667eea" }
+
+        if ready() {
+            Router::<Route> {}
+        } else {
+            div { class: "min-h-screen flex items-center justify-center",
+                span { class: "loading loading-spinner loading-lg text-primary" }
+            }
+        }
     }
 }